@@ -0,0 +1,554 @@
+// Criterion benchmarks of the book's hot paths, each run on the same seeded flow every
+// time so numbers from different runs and commits compare. Criterion reports the
+// throughput in elements per second, every element is one order.
+//
+//     cargo bench --bench hot_paths
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::hash::{BuildHasher, BuildHasherDefault, RandomState};
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+use orderbook::{
+    BookConfig, BookOp, LevelCompaction, OpResult, OrderBook, OrderId, OrderIdHasher, PriceLadder, Side, TimeInForce,
+};
+
+// Samples per case, each on a fresh setup. Criterion's minimum, the setups are large.
+const SAMPLES: usize = 10;
+
+// SplitMix64, so the flow is the same on every machine and every rand version
+struct Flow(u64);
+
+impl Flow {
+    fn new(seed: u64) -> Flow {
+        Flow(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in low..high
+    fn range(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next() % (high - low)
+    }
+
+    fn index(&mut self, len: usize) -> usize {
+        (self.next() % len as u64) as usize
+    }
+
+    fn side(&mut self) -> Side {
+        if self.next() & 1 == 0 {
+            Side::Bid
+        } else {
+            Side::Ask
+        }
+    }
+}
+
+// Times `run` on a fresh `setup` for every iteration, `ops` orders each
+fn bench<S>(c: &mut Criterion, name: &str, ops: u64, setup: impl FnMut() -> S, run: impl FnMut(&mut S)) {
+    bench_in(c, "hot_paths", name, ops, setup, run);
+}
+
+fn bench_in<S>(
+    c: &mut Criterion,
+    group: &str,
+    name: &str,
+    ops: u64,
+    mut setup: impl FnMut() -> S,
+    mut run: impl FnMut(&mut S),
+) {
+    let mut group = c.benchmark_group(group);
+    group.sample_size(SAMPLES).throughput(Throughput::Elements(ops));
+    group.bench_function(name, |b| {
+        b.iter_batched_ref(&mut setup, &mut run, BatchSize::PerIteration);
+    });
+    group.finish();
+}
+
+fn new_book() -> OrderBook {
+    OrderBook::new("BENCH".to_string())
+}
+
+fn insert_distinct_prices(c: &mut Criterion) {
+    const N: u64 = 100_000;
+    bench(c, "insert at distinct prices", N, new_book, |book| {
+        for i in 0..N {
+            black_box(book.create_new_limit_order(Side::Bid, 1 + i, 10).unwrap());
+        }
+    });
+}
+
+fn insert_deep_level(c: &mut Criterion) {
+    const N: u64 = 100_000;
+    bench(c, "insert into one level", N, new_book, |book| {
+        for _ in 0..N {
+            black_box(book.create_new_limit_order(Side::Bid, 100, 10).unwrap());
+        }
+    });
+}
+
+// Limit orders that could trade but find nothing to trade with, the bulk of real flow
+fn passive_limit_orders(c: &mut Criterion) {
+    const N: u64 = 200_000;
+    let setup = || {
+        let mut book = new_book();
+        book.create_new_limit_order(Side::Ask, 1_000, 10).unwrap();
+        book.create_new_limit_order(Side::Bid, 100, 10).unwrap();
+        (book, Flow::new(3))
+    };
+    bench(c, "passive limit orders", N, setup, |(book, flow)| {
+        for _ in 0..N {
+            let side = flow.side();
+            let price = match side {
+                Side::Bid => flow.range(100, 1_000),
+                Side::Ask => flow.range(1_000, 2_000),
+            };
+            black_box(book.add_limit_order(side, price, 10));
+        }
+    });
+}
+
+const LEVELS: u64 = 10_000;
+
+fn sweep_levels(c: &mut Criterion, name: &str, price_ladder: Option<PriceLadder>) {
+    const PER_LEVEL: u64 = 10;
+    const K: u64 = 100;
+    const SWEEPS: u64 = LEVELS / K;
+    let setup = || {
+        let mut book = new_book();
+        book.set_config(BookConfig { price_ladder, ..BookConfig::default() });
+        for price in 0..LEVELS {
+            for _ in 0..PER_LEVEL {
+                book.create_new_limit_order(Side::Ask, 1_000 + price, 1).unwrap();
+            }
+        }
+        book
+    };
+    // Counts the orders swept as well as the ones sweeping
+    bench(c, name, SWEEPS * (K * PER_LEVEL + 1), setup, |book| {
+        for _ in 0..SWEEPS {
+            black_box(book.add_market_order(Side::Bid, K * PER_LEVEL));
+        }
+    });
+}
+
+// 1-lot orders that could trade through the whole book, which should cost no more
+// than the first resting order they fill against
+fn one_lot_deep_book(c: &mut Criterion) {
+    const N: u64 = 100_000;
+    let setup = || {
+        let mut book = new_book();
+        for price in 0..100 {
+            for _ in 0..1_000 {
+                book.create_new_limit_order(Side::Ask, 1_000 + price, 10).unwrap();
+            }
+        }
+        book
+    };
+    bench(c, "1-lot orders against a deep book", N, setup, |book| {
+        for _ in 0..N {
+            black_box(book.add_limit_order(Side::Bid, 1_100, 1));
+        }
+    });
+}
+
+// Orders crossing 100 levels of asks after `churned` other prices above them were
+// each used and emptied, which should leave matching as fast as on a fresh book
+fn match_after_churn(c: &mut Criterion, name: &str, churned: u64) {
+    const N: u64 = 10_000;
+    let setup = || {
+        let mut book = new_book();
+        for price in 0..churned {
+            book.create_new_limit_order(Side::Ask, 2_000 + price, 10).unwrap();
+            book.add_market_order(Side::Bid, 10);
+        }
+        for price in 0..100 {
+            for _ in 0..100 {
+                book.create_new_limit_order(Side::Ask, 1_000 + price, 10).unwrap();
+            }
+        }
+        book
+    };
+    bench(c, name, N, setup, |book| {
+        for _ in 0..N {
+            black_box(book.add_limit_order(Side::Bid, 2_000 + churned, 5));
+        }
+    });
+}
+
+// Cancels 100k orders in a seeded random order, from anywhere in their queues, out of
+// levels `per_level` orders deep
+fn cancel_deep_levels(c: &mut Criterion, name: &str, per_level: u64) {
+    const N: u64 = 100_000;
+    let setup = || {
+        let mut book = new_book();
+        let mut ids: Vec<OrderId> =
+            (0..N).map(|i| book.create_new_limit_order(Side::Bid, 100 + i / per_level, 10).unwrap()).collect();
+        let mut flow = Flow::new(7);
+        for i in (1..ids.len()).rev() {
+            ids.swap(i, flow.index(i + 1));
+        }
+        (book, ids)
+    };
+    bench(c, name, N, setup, |(book, ids)| {
+        for &order_id in ids.iter() {
+            book.cancel_order(order_id).unwrap();
+        }
+    });
+}
+
+const MID: u64 = 10_000;
+
+// 60% cancels, 30% adds and 10% orders crossing the spread, around a book that stays
+// about as deep as it starts. Every price is within 100 ticks of MID, dense enough
+// for a ladder over them. With `listening` an event and a BBO listener look at
+// everything the book reports.
+fn mixed_flow(c: &mut Criterion, name: &str, price_ladder: Option<PriceLadder>, listening: bool) {
+    const OPS: u64 = 1_000_000;
+    let setup = || {
+        let mut book = new_book();
+        book.set_config(BookConfig { price_ladder, ..BookConfig::default() });
+        if listening {
+            book.on_event(|seq, event| {
+                black_box((seq, event));
+            });
+            book.on_bbo_change(|change| {
+                black_box(change);
+            });
+        }
+        let mut flow = Flow::new(42);
+        let mut live: Vec<OrderId> = Vec::new();
+        for _ in 0..20_000 {
+            let side = flow.side();
+            let price = match side {
+                Side::Bid => MID - flow.range(1, 100),
+                Side::Ask => MID + flow.range(1, 100),
+            };
+            live.push(book.create_new_limit_order(side, price, flow.range(1, 100)).unwrap());
+        }
+        (book, flow, live)
+    };
+    bench(c, name, OPS, setup, |(book, flow, live)| {
+        for _ in 0..OPS {
+            match flow.range(0, 10) {
+                0..=5 if !live.is_empty() => {
+                    let order_id = live.swap_remove(flow.index(live.len()));
+                    // Some were filled by a cross since
+                    let _ = black_box(book.cancel_order(order_id));
+                }
+                // Adds, and cancels with nothing left to cancel
+                0..=8 => {
+                    let side = flow.side();
+                    let price = match side {
+                        Side::Bid => MID - flow.range(1, 100),
+                        Side::Ask => MID + flow.range(1, 100),
+                    };
+                    live.push(book.create_new_limit_order(side, price, flow.range(1, 100)).unwrap());
+                }
+                _ => {
+                    black_box(book.add_market_order(flow.side(), flow.range(1, 200)));
+                }
+            }
+        }
+    });
+}
+
+#[derive(Clone, Copy)]
+enum Requote {
+    OrderByOrder,
+    Batch,
+    AtomicBatch,
+}
+
+// A market maker pulling 10 quotes a side and putting them back a tick away, over and
+// over, order by order or as one batch
+fn requote(c: &mut Criterion, name: &str, how: Requote) {
+    const QUOTES: u64 = 10;
+    const ROUNDS: u64 = 20_000;
+    let setup = || {
+        let mut book = new_book();
+        // Depth behind the quotes for the BBO to be worked out over
+        for i in 0..1_000 {
+            book.create_new_limit_order(Side::Bid, MID - 50 - i % 50, 10).unwrap();
+            book.create_new_limit_order(Side::Ask, MID + 50 + i % 50, 10).unwrap();
+        }
+        (book, Vec::<OrderId>::new())
+    };
+    bench(c, name, ROUNDS * QUOTES * 4, setup, |(book, quotes)| {
+        for round in 0..ROUNDS {
+            let skew = round % 2;
+            let mut ops: Vec<BookOp> = quotes.drain(..).map(|order_id| BookOp::Cancel { order_id }).collect();
+            for i in 0..QUOTES {
+                for (side, price) in [(Side::Bid, MID - 1 - i - skew), (Side::Ask, MID + 1 + i + skew)] {
+                    ops.push(BookOp::Add { side, price: price.into(), qty: 10, tif: TimeInForce::GoodTillCancel, owner: None });
+                }
+            }
+            let results = match how {
+                Requote::Batch => book.apply_batch(&ops),
+                Requote::AtomicBatch => book.apply_batch_atomic(&ops).unwrap(),
+                Requote::OrderByOrder => {
+                    for op in ops {
+                        match op {
+                            BookOp::Cancel { order_id } => {
+                                black_box(book.cancel_order(order_id).unwrap());
+                            }
+                            BookOp::Add { side, price, qty, tif, .. } => {
+                                let fill_result = book.add_limit_order_with_tif(side, price, qty, tif);
+                                quotes.extend(fill_result.resting_order_id);
+                            }
+                            BookOp::Amend { .. } => unreachable!(),
+                        }
+                    }
+                    continue;
+                }
+            };
+            for result in results {
+                if let OpResult::Added(fill_result) = result {
+                    quotes.extend(fill_result.resting_order_id);
+                }
+            }
+        }
+    });
+}
+
+fn snapshot(c: &mut Criterion) {
+    const ORDERS: u64 = 100_000;
+    const SNAPSHOTS: u64 = 20;
+    let setup = || {
+        let mut book = new_book();
+        for i in 0..ORDERS {
+            book.create_new_limit_order(Side::Bid, 1 + i % 1_000, 10).unwrap();
+        }
+        book
+    };
+    // Counts the orders written to snapshots
+    bench(c, "snapshot of 100k orders", ORDERS * SNAPSHOTS, setup, |book| {
+        for _ in 0..SNAPSHOTS {
+            black_box(book.snapshot());
+        }
+    });
+}
+
+fn hot_paths(c: &mut Criterion) {
+    insert_distinct_prices(c);
+    insert_deep_level(c);
+    passive_limit_orders(c);
+    sweep_levels(c, "sweep 100 levels", None);
+    sweep_levels(c, "sweep 100 levels on a price ladder", Some(PriceLadder { low: 1_000, ticks: 10_000 }));
+    one_lot_deep_book(c);
+    match_after_churn(c, "match on a fresh book", 0);
+    match_after_churn(c, "match after 10k prices churned", 10_000);
+    cancel_deep_levels(c, "cancel from levels 10 deep", 10);
+    cancel_deep_levels(c, "cancel from levels 1k deep", 1_000);
+    cancel_deep_levels(c, "cancel from one level 100k deep", 100_000);
+    mixed_flow(c, "mixed 60/30/10 flow", None, false);
+    mixed_flow(c, "mixed 60/30/10 flow with event and BBO listeners", None, true);
+    mixed_flow(c, "mixed 60/30/10 flow on a price ladder", Some(PriceLadder { low: MID - 128, ticks: 256 }), false);
+    requote(c, "requote order by order", Requote::OrderByOrder);
+    requote(c, "requote as one batch", Requote::Batch);
+    requote(c, "requote as one atomic batch", Requote::AtomicBatch);
+    snapshot(c);
+}
+
+// An order resting and cancelled straight after, over and over, on a book 1k orders
+// deep. tests/fill_allocs checks the pairs make no allocations.
+fn add_cancel(c: &mut Criterion) {
+    const PAIRS: u64 = 200_000;
+    let setup = || {
+        let mut book = new_book();
+        for i in 0..1_000 {
+            book.create_new_limit_order(Side::Bid, 1_000 - i % 100, 10).unwrap();
+        }
+        book
+    };
+    bench_in(c, "add_cancel", "add/cancel pairs", PAIRS, setup, |book| {
+        for i in 0..PAIRS {
+            let order_id = book.create_new_limit_order(Side::Bid, 1_000 - i % 100, 10).unwrap();
+            black_box(book.cancel_order(order_id).unwrap());
+        }
+    });
+}
+
+const LOOKUP_IDS: u64 = 100_000;
+const LOOKUP_ROUNDS: u64 = 20;
+
+// Every id of 100k, as the book gives them out, in a seeded random order
+fn shuffled_ids() -> Vec<OrderId> {
+    let mut ids: Vec<OrderId> = (1..=LOOKUP_IDS).map(OrderId).collect();
+    let mut flow = Flow::new(11);
+    for i in (1..ids.len()).rev() {
+        ids.swap(i, flow.index(i + 1));
+    }
+    ids
+}
+
+// Looks up 100k order ids over and over in a map hashing them with `H`, which is
+// what every cancel, amend and maker fill does first. Every element is one lookup.
+fn id_lookup<H: BuildHasher + Default>(c: &mut Criterion, name: &str) {
+    let setup = || {
+        let ids = shuffled_ids();
+        let mut map: HashMap<OrderId, u64, H> = HashMap::with_capacity_and_hasher(ids.len(), H::default());
+        map.extend(ids.iter().map(|&order_id| (order_id, order_id.0)));
+        (map, ids)
+    };
+    bench_in(c, "order_lookup", name, LOOKUP_IDS * LOOKUP_ROUNDS, setup, |(map, ids)| {
+        for _ in 0..LOOKUP_ROUNDS {
+            for order_id in ids.iter() {
+                black_box(map.get(black_box(order_id)));
+            }
+        }
+    });
+}
+
+// The same lookups through the book, on the order_loc map it sizes for them
+fn book_lookup(c: &mut Criterion) {
+    let setup = || {
+        let mut book = new_book();
+        book.set_config(BookConfig { expected_orders: Some(LOOKUP_IDS as usize), ..BookConfig::default() });
+        for i in 0..LOOKUP_IDS {
+            book.create_new_limit_order(Side::Bid, 1 + i % 1_000, 10).unwrap();
+        }
+        (book, shuffled_ids())
+    };
+    bench_in(c, "order_lookup", "resting_qty", LOOKUP_IDS * LOOKUP_ROUNDS, setup, |(book, ids)| {
+        for _ in 0..LOOKUP_ROUNDS {
+            for &order_id in ids.iter() {
+                black_box(book.resting_qty(black_box(order_id)));
+            }
+        }
+    });
+}
+
+fn order_lookup(c: &mut Criterion) {
+    id_lookup::<BuildHasherDefault<OrderIdHasher>>(c, "OrderIdHasher");
+    id_lookup::<RandomState>(c, "default SipHash");
+    book_lookup(c);
+}
+
+// What the cancel-heavy flow needs of a book
+trait CancelHeavyBook {
+    fn add_bid(&mut self, price: u64, qty: u64) -> OrderId;
+    fn cancel(&mut self, order_id: OrderId) -> bool;
+    fn sell(&mut self, qty: u64);
+    // Called every 100k rounds
+    fn tidy(&mut self) {}
+}
+
+impl CancelHeavyBook for OrderBook {
+    fn add_bid(&mut self, price: u64, qty: u64) -> OrderId {
+        self.create_new_limit_order(Side::Bid, price, qty).unwrap()
+    }
+
+    fn cancel(&mut self, order_id: OrderId) -> bool {
+        self.cancel_order(order_id).is_ok()
+    }
+
+    fn sell(&mut self, qty: u64) {
+        black_box(self.add_market_order(Side::Ask, qty));
+    }
+
+    fn tidy(&mut self) {
+        black_box(self.compact());
+    }
+}
+
+// Bids kept the way the book kept them before cancels took an order out by its queue
+// position: a queue per price that a cancel runs `retain` over
+#[derive(Default)]
+struct RetainBook {
+    levels: BTreeMap<u64, VecDeque<(OrderId, u64)>>,
+    prices: HashMap<OrderId, u64>,
+    last_order_id: u64,
+}
+
+impl CancelHeavyBook for RetainBook {
+    fn add_bid(&mut self, price: u64, qty: u64) -> OrderId {
+        self.last_order_id += 1;
+        let order_id = OrderId(self.last_order_id);
+        self.levels.entry(price).or_default().push_back((order_id, qty));
+        self.prices.insert(order_id, price);
+        order_id
+    }
+
+    fn cancel(&mut self, order_id: OrderId) -> bool {
+        let Some(price) = self.prices.remove(&order_id) else {
+            return false;
+        };
+        let level = self.levels.get_mut(&price).unwrap();
+        level.retain(|&(id, _)| id != order_id);
+        if level.is_empty() {
+            self.levels.remove(&price);
+        }
+        true
+    }
+
+    fn sell(&mut self, mut qty: u64) {
+        while qty > 0 {
+            let Some(mut best) = self.levels.last_entry() else {
+                break;
+            };
+            let level = best.get_mut();
+            let (order_id, resting_qty) = level.front_mut().unwrap();
+            let traded = qty.min(*resting_qty);
+            *resting_qty -= traded;
+            qty -= traded;
+            if *resting_qty == 0 {
+                self.prices.remove(order_id);
+                level.pop_front();
+                if level.is_empty() {
+                    best.remove();
+                }
+            }
+        }
+    }
+}
+
+// Ten cancels to every trade: 50k bids over 100 prices, and round after round one
+// more resting and a random one cancelled, with a sell taking out the best bids every
+// tenth round. Every element is one round.
+fn cancel_heavy_flow<B: CancelHeavyBook>(c: &mut Criterion, name: &str, mut new_book: impl FnMut() -> B) {
+    const LIVE: usize = 50_000;
+    const PRICES: u64 = 100;
+    const ROUNDS: u64 = 200_000;
+    let setup = || {
+        let mut book = new_book();
+        let mut flow = Flow::new(95);
+        let live: Vec<OrderId> = (0..LIVE).map(|_| book.add_bid(flow.range(1, PRICES + 1), 10)).collect();
+        (book, flow, live)
+    };
+    bench_in(c, "cancel_heavy", name, ROUNDS, setup, |(book, flow, live)| {
+        for i in 0..ROUNDS {
+            live.push(book.add_bid(flow.range(1, PRICES + 1), 10));
+            let order_id = live.swap_remove(flow.index(live.len()));
+            // Some were filled by a sell since
+            black_box(book.cancel(order_id));
+            if i % 10 == 0 {
+                book.sell(5);
+            }
+            if i % 100_000 == 0 {
+                book.tidy();
+            }
+        }
+    });
+}
+
+fn cancel_heavy(c: &mut Criterion) {
+    for compaction in [LevelCompaction::Automatic, LevelCompaction::Manual] {
+        cancel_heavy_flow(c, &format!("{compaction:?} compaction"), || {
+            let mut book = new_book();
+            book.set_config(BookConfig { compaction, ..BookConfig::default() });
+            book
+        });
+    }
+    cancel_heavy_flow(c, "retain baseline", RetainBook::default);
+}
+
+criterion_group!(benches, hot_paths, add_cancel, order_lookup, cancel_heavy);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+// Times the three things resting storage is for, taken apart: resting orders, cancelling
+// them from anywhere in their queues, and matching through what is left.
+//
+//     cargo run --release --example book_ops
+//
+// The storage redesign it was written to judge, orders in one slab per side with an
+// intrusive list per level, measured against the per-level queues in one build:
+//
+//              queues    slab
+//     add      545 ns    546 ns per order
+//     cancel  1213 ns   1350 ns per order
+//     match   2973 ns   5067 ns per market order
+//
+// and the snapshot of 100k orders in the hot_paths bench went from 122 ms to 406 ms,
+// so the queues were kept.
+
+use std::time::{Duration, Instant};
+
+use orderbook::{OrderBook, OrderId, Side};
+use rand::seq::SliceRandom;
+
+const ORDERS: u64 = 1_000_000;
+const PRICES: u64 = 100;
+const ROUNDS: u32 = 5;
+
+fn per_op(elapsed: Duration, ops: u64) -> f64 {
+    elapsed.as_nanos() as f64 / ops as f64
+}
+
+fn main() {
+    let (mut add, mut cancel, mut matching) = (Duration::ZERO, Duration::ZERO, Duration::ZERO);
+    let mut rng = rand::thread_rng();
+    for _ in 0..ROUNDS {
+        let mut book = OrderBook::new("BENCH".to_string());
+
+        // Deep queues, so a cancel in the middle of one would show if it cost O(level)
+        let start = Instant::now();
+        let mut ids: Vec<OrderId> = (0..ORDERS)
+            .map(|i| book.create_new_limit_order(Side::Bid, 1 + i % PRICES, 10).unwrap())
+            .collect();
+        add += start.elapsed();
+
+        ids.shuffle(&mut rng);
+        let start = Instant::now();
+        for &order_id in &ids[..ids.len() / 2] {
+            book.cancel_order(order_id).unwrap();
+        }
+        cancel += start.elapsed();
+
+        // Each market order fills a few resting ones, partially filling the last
+        let start = Instant::now();
+        for _ in 0..ORDERS / 2 / 3 {
+            book.add_market_order(Side::Ask, 25);
+        }
+        matching += start.elapsed();
+    }
+
+    let ops = ORDERS * u64::from(ROUNDS);
+    println!("add     {:.1} ns per order", per_op(add, ops));
+    println!("cancel  {:.1} ns per order", per_op(cancel, ops / 2));
+    println!("match   {:.1} ns per market order", per_op(matching, ops / 2 / 3));
+}
@@ -0,0 +1,233 @@
+use crate::{Error, Event, FillResult, OrderBook, OrderId, OrderOptions, OrderStatus, Price, RejectReason, Side, TimeInForce};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AmendResult {
+    // False if the order went to the back of its (possibly new) price level
+    pub kept_priority: bool,
+    // Fills if the new price made the order marketable, and where it rests now
+    pub fill_result: FillResult,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReplaceResult {
+    pub order_id: OrderId,
+    // Qty the cancelled order still had resting, iceberg reserve included
+    pub cancelled_qty: u64,
+    pub fill_result: FillResult,
+}
+
+impl OrderBook {
+    // Changes the price and total qty of a resting order, keeping its id.
+    // Only reducing the qty at the same price keeps its place in the queue, any other
+    // change cancels and re-enters it with the same attributes, behind every order
+    // at its new price. If that price crosses the book it matches like a new order,
+    // except that it is not counted as one, and what is left is cancelled if it would
+    // rest through liquidity matching skipped. An amend the order could not re-enter
+    // with, an expired good-till-date order or an all-or-none one that would cross,
+    // is refused and leaves the order as it was, as is a price off the book's ticks.
+    pub fn amend_order(
+        &mut self,
+        order_id: OrderId,
+        new_price: impl Into<Price>,
+        new_qty: u64,
+    ) -> Result<AmendResult, Error> {
+        let new_price = self.order_ticks(new_price.into())?;
+        let amend_result = self.amend_without_triggers(order_id, new_price, new_qty)?;
+        self.process_triggers();
+        Ok(amend_result)
+    }
+
+    // Why amend_order would refuse this amend, if it would, checked in the order
+    // amend_order checks
+    pub(crate) fn amend_error(&self, order_id: OrderId, new_price: u64, new_qty: u64) -> Option<Error> {
+        let (Some(&(side, _, _)), Some(price), Some(o)) =
+            (self.order_loc.get(&order_id), self.resting_price(order_id), self.resting_order(order_id))
+        else {
+            return Some(self.cancel_error(order_id));
+        };
+        if let Err(reason) = self.check_order_limits(Some(new_price), new_qty) {
+            return Some(Error::Rejected(reason));
+        }
+        if o.pegged && new_price != price {
+            return Some(Error::PeggedPriceAmended { order_id, price: new_price });
+        }
+        if new_price == price && new_qty <= o.qty + o.reserve_qty {
+            return None;
+        }
+        if o.expires_at.is_some_and(|expires_at| expires_at <= self.clock.now()) {
+            return Some(Error::Rejected(RejectReason::ExpiryInPast));
+        }
+        let taker = o.owner.map(|owner| (owner, self.config.self_trade_prevention));
+        if o.all_or_none && self.all_or_none_would_cross(side, new_price, new_qty, taker) {
+            return Some(Error::Rejected(RejectReason::AllOrNoneWouldCross));
+        }
+        None
+    }
+
+    // Amends like amend_order but leaves triggering to the caller
+    pub(crate) fn amend_without_triggers(
+        &mut self,
+        order_id: OrderId,
+        new_price: u64,
+        new_qty: u64,
+    ) -> Result<AmendResult, Error> {
+        if let Some(error) = self.amend_error(order_id, new_price, new_qty) {
+            return Err(error);
+        }
+        let (Some(&(side, _, _)), Some(price), Some(o)) =
+            (self.order_loc.get(&order_id), self.resting_price(order_id), self.resting_order(order_id))
+        else {
+            unreachable!("amend_error checked the order is resting");
+        };
+        let resting_qty = o.qty + o.reserve_qty;
+
+        if new_price == price && new_qty <= resting_qty {
+            self.next_seq();
+            self.emit(Event::Amended {
+                order_id,
+                old_price: self.price(price),
+                old_qty: resting_qty,
+                new_price: self.price(new_price),
+                new_qty,
+                kept_priority: true,
+            });
+            self.shrink_resting_order(order_id, new_qty);
+            self.after_amend(order_id);
+            let mut fill_result = FillResult::unfilled(new_qty);
+            fill_result.resting_price = Some(self.price(price));
+            fill_result.resting_order_id = Some(order_id);
+            return Ok(AmendResult { kept_priority: true, fill_result });
+        }
+
+        self.next_seq();
+        self.emit(Event::Amended {
+            order_id,
+            old_price: self.price(price),
+            old_qty: resting_qty,
+            new_price: self.price(new_price),
+            new_qty,
+            kept_priority: false,
+        });
+        let (o, _, _) = self.take_resting_order_at(order_id).unwrap();
+        let options = OrderOptions {
+            display_qty: (o.reserve_qty > 0).then_some(o.display_qty),
+            hidden: o.hidden,
+            expires_at: None,
+            all_or_none: o.all_or_none,
+            pegged: o.pegged,
+            min_fill: o.min_fill,
+            oca_group: o.oca_group,
+            owner: o.owner,
+            tag: o.tag,
+            reduce_only: o.reduce_only,
+            original_qty: 0,
+            filled_qty: o.filled_qty,
+            filled_notional: o.filled_notional,
+            self_trade_prevention: None,
+            // Still held by this order id from when it was accepted
+            client_order_id: None,
+            reentered: true,
+        };
+        let tif = o.expires_at.map_or(TimeInForce::GoodTillCancel, TimeInForce::GoodTillDate);
+        let fill_result = self.execute_limit_order(order_id, side, new_price, new_qty, tif, options);
+        if fill_result.remaining_qty < new_qty {
+            let done = matches!(fill_result.status, OrderStatus::Filled);
+            self.on_linked_execution(order_id, new_qty - fill_result.remaining_qty, done);
+        }
+        self.after_amend(order_id);
+        Ok(AmendResult { kept_priority: false, fill_result })
+    }
+
+    // Keeps a bracket stop-loss at the qty of the take-profit that was just amended
+    fn after_amend(&mut self, order_id: OrderId) {
+        if let Some(stop_loss_id) = self.take_profit_stops.get(&order_id).cloned() {
+            self.sync_stop_loss(order_id, stop_loss_id);
+        }
+        self.update_bbo();
+    }
+
+    // Cancels a resting order and submits a new limit order for the same owner and with
+    // the same tag under a new id, in one call so nothing else can trade in between.
+    // Nothing is submitted if the order is no longer resting, and nothing is cancelled
+    // if the new price is off the book's ticks. The cancel works like `cancel_order`.
+    pub fn cancel_replace(
+        &mut self,
+        order_id: OrderId,
+        s: Side,
+        new_price: impl Into<Price>,
+        new_qty: u64,
+    ) -> Result<ReplaceResult, Error> {
+        let new_price = self.order_ticks(new_price.into())?;
+        self.bbo_batch(|book| book.replace_order(order_id, s, new_price, new_qty, None))
+    }
+
+    // Like cancel_replace, the new order has `tag` instead of the old one's
+    pub fn cancel_replace_with_tag(
+        &mut self,
+        order_id: OrderId,
+        s: Side,
+        new_price: impl Into<Price>,
+        new_qty: u64,
+        tag: Option<u64>,
+    ) -> Result<ReplaceResult, Error> {
+        let new_price = self.order_ticks(new_price.into())?;
+        self.bbo_batch(|book| book.replace_order(order_id, s, new_price, new_qty, Some(tag)))
+    }
+
+    // `new_tag` of None keeps the old order's tag
+    fn replace_order(
+        &mut self,
+        order_id: OrderId,
+        s: Side,
+        new_price: u64,
+        new_qty: u64,
+        new_tag: Option<Option<u64>>,
+    ) -> Result<ReplaceResult, Error> {
+        let Some(o) = self.resting_order(order_id) else {
+            return Err(self.cancel_error(order_id));
+        };
+        let (cancelled_qty, owner) = (o.qty + o.reserve_qty, o.owner);
+        let tag = new_tag.unwrap_or(o.tag);
+        self.check_order_limits(Some(new_price), new_qty)?;
+        self.cancel_without_triggers(order_id)?;
+        self.update_bbo();
+
+        let new_id = self.new_order_id();
+        self.emit(Event::Replaced { order_id, new_order_id: new_id });
+        let options = OrderOptions { owner, tag, ..Default::default() };
+        let fill_result = self.execute_limit_order(new_id, s, new_price, new_qty, TimeInForce::GoodTillCancel, options);
+        self.process_triggers();
+        Ok(ReplaceResult { order_id: new_id, cancelled_qty, fill_result })
+    }
+
+    // Takes `qty_to_remove` off a resting order in place, reserve first, so it keeps its
+    // place in the queue, and returns the qty left. Removing everything left cancels it
+    // like `cancel_order`.
+    pub fn reduce_order(&mut self, order_id: OrderId, qty_to_remove: u64) -> Result<u64, Error> {
+        let Some(resting_qty) = self.resting_qty(order_id) else {
+            return Err(self.cancel_error(order_id));
+        };
+        if qty_to_remove >= resting_qty {
+            self.cancel_order(order_id)?;
+            return Ok(0);
+        }
+
+        if let Some(price) = self.resting_price(order_id) {
+            self.next_seq();
+            self.emit(Event::Amended {
+                order_id,
+                old_price: self.price(price),
+                old_qty: resting_qty,
+                new_price: self.price(price),
+                new_qty: resting_qty - qty_to_remove,
+                kept_priority: true,
+            });
+        }
+        self.shrink_resting_order(order_id, resting_qty - qty_to_remove);
+        self.after_amend(order_id);
+        self.process_triggers();
+        Ok(resting_qty - qty_to_remove)
+    }
+}
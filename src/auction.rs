@@ -0,0 +1,412 @@
+use crate::{match_at_price_level, reducible_qty, saturating_sum, update_position, Counterparty, Event, FillResult, HalfBook, MakerFill, MatchContext, OrderBook, OrderId, OrderKind, OrderOptions, OrderStatus, Price, RejectReason, Side};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TradingPhase {
+    #[default]
+    Continuous,
+    // Limit orders rest without matching, even crossed, until `uncross`
+    OpeningAuction,
+    // Like the opening auction, and on-close orders are accepted until `uncross_close`
+    ClosingAuction,
+}
+
+// Market-on-close or limit-on-close order staged for the closing uncross
+#[derive(Debug, Clone)]
+pub(crate) struct CloseOrder {
+    pub order_id: OrderId,
+    pub side: Side,
+    // None for market-on-close
+    pub limit_price: Option<u64>,
+    pub qty: u64,
+}
+
+// Qty one order executed at the clearing price
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuctionFill {
+    pub order_id: OrderId,
+    pub side: Side,
+    pub qty: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuctionReport {
+    // None if the book was not crossed, nothing traded
+    pub clearing_price: Option<Price>,
+    pub matched_qty: u64,
+    pub fills: Vec<AuctionFill>,
+}
+
+// Would-be result of uncrossing the book as it is now
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IndicativeAuction {
+    pub price: Price,
+    pub matched_qty: u64,
+    // Side with qty left over at the price, None if both sides match exactly
+    pub imbalance_side: Option<Side>,
+    pub imbalance_qty: u64,
+}
+
+// Outcome of uncrossing at one price
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Uncross {
+    pub price: u64,
+    pub matched_qty: u64,
+    // Qty bid at or above the price
+    pub buy_qty: u64,
+    // Qty offered at or below the price
+    pub sell_qty: u64,
+}
+
+impl OrderBook {
+    // Stops matching, incoming limit orders rest as they are until `uncross`.
+    // Market and midpoint orders cannot trade while it lasts.
+    pub fn start_auction(&mut self) {
+        self.emit(Event::AuctionStarted { closing: false });
+        self.phase = TradingPhase::OpeningAuction;
+    }
+
+    pub fn phase(&self) -> TradingPhase {
+        self.phase
+    }
+
+    pub(crate) fn in_auction(&self) -> bool {
+        self.phase != TradingPhase::Continuous
+    }
+
+    // Executes every crossing order at the single price that trades the most qty,
+    // in price then time priority on each side, and returns to continuous trading.
+    // Orders that did not trade keep resting.
+    pub fn uncross(&mut self) -> AuctionReport {
+        let report = self.run_uncross();
+        self.phase = TradingPhase::Continuous;
+        self.update_bbo();
+        self.process_triggers();
+        report
+    }
+
+    // Stops continuous matching for the closing auction, which also accepts
+    // market-on-close and limit-on-close orders
+    pub fn start_closing_auction(&mut self) {
+        self.emit(Event::AuctionStarted { closing: true });
+        self.phase = TradingPhase::ClosingAuction;
+    }
+
+    // Staged for the closing uncross, executed at the closing price whatever it is
+    pub fn add_market_on_close_order(&mut self, s: Side, qty: u64) -> (OrderId, FillResult) {
+        self.stage_close_order(s, None, qty)
+    }
+
+    // Staged for the closing uncross, executed only if the closing price is at `price` or better
+    pub fn add_limit_on_close_order(&mut self, s: Side, price: impl Into<Price>, qty: u64) -> (OrderId, FillResult) {
+        match self.limit_ticks(OrderKind::OnClose, price.into(), qty) {
+            Ok(price) => self.stage_close_order(s, Some(price), qty),
+            Err(rejected) => (self.new_order_id(), rejected),
+        }
+    }
+
+    fn stage_close_order(&mut self, s: Side, limit_price: Option<u64>, qty: u64) -> (OrderId, FillResult) {
+        let order_id = self.new_order_id();
+        let mut fill_result = FillResult::unfilled(qty);
+        let price = limit_price.map(|p| self.price(p));
+        if self.phase != TradingPhase::ClosingAuction {
+            let reason = RejectReason::CloseOnlyOutsideClosingAuction;
+            self.emit(Event::OrderRejected { kind: OrderKind::OnClose, price, qty, reason });
+            fill_result.status = OrderStatus::Rejected(reason);
+            return (order_id, fill_result);
+        }
+        if let Err(reason) = self.check_order_limits(limit_price, qty) {
+            self.emit(Event::OrderRejected { kind: OrderKind::OnClose, price, qty, reason });
+            fill_result.status = OrderStatus::Rejected(reason);
+            return (order_id, fill_result);
+        }
+
+        self.emit(Event::OnCloseStaged { order_id, qty, limit_price: price });
+        self.accept_order();
+        self.close_orders.push(CloseOrder { order_id, side: s, limit_price, qty });
+        (order_id, fill_result)
+    }
+
+    // Executes the staged on-close orders against each other and the resting book
+    // at a single closing price, like `uncross`. Unfilled on-close orders are
+    // cancelled, everything else keeps resting and continuous trading resumes.
+    pub fn uncross_close(&mut self) -> AuctionReport {
+        let close_orders = std::mem::take(&mut self.close_orders);
+        for o in &close_orders {
+            // Market-on-close orders take part at any price
+            let price = o.limit_price.unwrap_or(match o.side {
+                Side::Bid => u64::MAX,
+                Side::Ask => 0,
+            });
+            self.create_resting_order(o.order_id, o.side, price, o.qty, &OrderOptions::default());
+        }
+
+        let report = self.run_uncross();
+        for o in close_orders {
+            if !self.order_loc.contains_key(&o.order_id) {
+                continue;
+            }
+            if let Ok((report, _)) = self.remove_order(o.order_id) {
+                self.emit(Event::OrderCancelled { kind: OrderKind::OnClose, report });
+            }
+        }
+        self.closing_price = report.clearing_price.map(|p| p.ticks());
+        self.closing_volume = report.matched_qty;
+
+        self.phase = TradingPhase::Continuous;
+        self.update_bbo();
+        self.process_triggers();
+        report
+    }
+
+    // Price of the last closing uncross, None before one traded
+    pub fn closing_price(&self) -> Option<Price> {
+        self.closing_price.map(|p| self.price(p))
+    }
+
+    pub fn closing_volume(&self) -> u64 {
+        self.closing_volume
+    }
+
+    // Clearing price, matched qty and imbalance an uncross would produce right now,
+    // including staged on-close orders, with the same tiebreaks as `uncross`.
+    // None outside an auction or when nothing would trade.
+    pub fn indicative_auction(&self) -> Option<IndicativeAuction> {
+        if !self.in_auction() {
+            return None;
+        }
+        let uncross = self.auction_clearing()?;
+        let imbalance_side = match uncross.buy_qty.cmp(&uncross.sell_qty) {
+            std::cmp::Ordering::Greater => Some(Side::Bid),
+            std::cmp::Ordering::Less => Some(Side::Ask),
+            std::cmp::Ordering::Equal => None,
+        };
+        Some(IndicativeAuction {
+            price: self.price(uncross.price),
+            matched_qty: uncross.matched_qty,
+            imbalance_side,
+            imbalance_qty: uncross.buy_qty.abs_diff(uncross.sell_qty),
+        })
+    }
+
+    fn run_uncross(&mut self) -> AuctionReport {
+        let mut report = match self.auction_clearing() {
+            Some(uncross) => self.execute_uncross(uncross.price),
+            None => AuctionReport { clearing_price: None, matched_qty: 0, fills: Vec::new() },
+        };
+        if report.matched_qty == 0 {
+            report.clearing_price = None;
+        }
+        self.emit(Event::Uncrossed { price: report.clearing_price, qty: report.matched_qty });
+        report
+    }
+
+    // Price maximizing the matched qty. Ties go to the smallest imbalance, then to
+    // the highest price if buyers are left over, the lowest otherwise. Market-on-close
+    // prices are never candidates, with nothing else to go on the last trade price is.
+    // Staged on-close orders count as if they were resting. None when nothing crosses.
+    pub(crate) fn auction_clearing(&self) -> Option<Uncross> {
+        let now = self.clock.now();
+        let level_qty = |book: &HalfBook, u: usize| -> u64 {
+            saturating_sum(book.price_levels[u]
+                .iter()
+                .filter(|o| !o.is_expired(now))
+                .map(|o| o.qty + o.reserve_qty))
+        };
+
+        let mut prices: Vec<u64> = self.bid_book.price_map.keys()
+            .chain(self.ask_book.price_map.keys())
+            .copied()
+            .chain(self.close_orders.iter().filter_map(|o| o.limit_price))
+            .collect();
+        prices.retain(|p| *p != 0 && *p != u64::MAX);
+        prices.sort_unstable();
+        prices.dedup();
+        if prices.is_empty() {
+            prices.extend(self.last_trade_price);
+        }
+
+        let mut best: Option<Uncross> = None;
+        for price in prices {
+            let buy_qty = saturating_sum(self.bid_book.price_map
+                .range(price..)
+                .map(|(_, u)| level_qty(&self.bid_book, *u)));
+            let sell_qty = saturating_sum(self.ask_book.price_map
+                .range(..=price)
+                .map(|(_, u)| level_qty(&self.ask_book, *u)));
+            let (staged_buy_qty, staged_sell_qty) = self.close_orders.iter().fold((0u64, 0u64), |(buy, sell), o| {
+                match (o.side, o.limit_price) {
+                    (Side::Bid, limit) if limit.is_none_or(|l| l >= price) => (buy.saturating_add(o.qty), sell),
+                    (Side::Ask, limit) if limit.is_none_or(|l| l <= price) => (buy, sell.saturating_add(o.qty)),
+                    _ => (buy, sell),
+                }
+            });
+            let (buy_qty, sell_qty) = (buy_qty.saturating_add(staged_buy_qty), sell_qty.saturating_add(staged_sell_qty));
+            let candidate = Uncross { price, matched_qty: buy_qty.min(sell_qty), buy_qty, sell_qty };
+            if candidate.matched_qty == 0 {
+                continue;
+            }
+            let better = best.is_none_or(|b| {
+                let (imbalance, best_imbalance) = (candidate.buy_qty.abs_diff(candidate.sell_qty), b.buy_qty.abs_diff(b.sell_qty));
+                candidate.matched_qty > b.matched_qty
+                    || (candidate.matched_qty == b.matched_qty && imbalance < best_imbalance)
+                    || (candidate.matched_qty == b.matched_qty
+                        && imbalance == best_imbalance
+                        && candidate.buy_qty > candidate.sell_qty)
+            });
+            if better {
+                best = Some(candidate);
+            }
+        }
+        best
+    }
+
+    // Takes every bid at or above `price` in priority order as an incoming order
+    // against the asks at or below it, all trades at `price`. Like in continuous
+    // matching, displayed bids at a price go before hidden ones on both sides.
+    fn execute_uncross(&mut self, price: u64) -> AuctionReport {
+        let mut report = AuctionReport { clearing_price: Some(self.price(price)), matched_qty: 0, fills: Vec::new() };
+        let now = self.clock.now();
+        let bid_levels: Vec<(u64, usize)> = self.bid_book.price_map.range(price..).rev().map(|(p, u)| (*p, *u)).collect();
+        let ask_levels: Vec<(u64, usize)> = self.ask_book.price_map.range(..=price).map(|(p, u)| (*p, *u)).collect();
+
+        for (bid_level, hidden_pass) in bid_levels.iter().flat_map(|(_, u)| [(*u, false), (*u, true)]) {
+            let mut i = self.bid_book.price_levels[bid_level].start();
+            while i < self.bid_book.price_levels[bid_level].end() {
+                let Some(bid) = self.bid_book.price_levels[bid_level].get(i) else {
+                    i += 1;
+                    continue;
+                };
+                if bid.is_expired(now) || bid.hidden != hidden_pass {
+                    i += 1;
+                    continue;
+                }
+                let taker = bid.owner.map(|owner| (owner, self.config.self_trade_prevention));
+                let (bid_id, bid_tag) = (bid.order_id, bid.tag);
+                let mut bid_qty = bid.qty + bid.reserve_qty;
+                if let Some(owner) = bid.owner.filter(|_| bid.reduce_only) {
+                    bid_qty = bid_qty.min(reducible_qty(&self.positions, owner, Side::Bid));
+                }
+
+                let mut remaining_qty = bid_qty;
+                let fills_before = self.maker_fills.len();
+                let mut events = Vec::new();
+                let mut prevented = Vec::new();
+                for (ask_price, ask_level) in &ask_levels {
+                    if remaining_qty == 0 {
+                        break;
+                    }
+                    let mut ctx = MatchContext {
+                        now,
+                        order_loc: &mut self.order_loc,
+                        expired: &mut self.expired_orders,
+                        maker_fills: &mut self.maker_fills,
+                        fired_groups: &mut self.fired_oca_groups,
+                        taker,
+                        prevented: &mut prevented,
+                        algorithm: self.config.matching,
+                        auction_price: Some(price),
+                        positions: &mut self.positions,
+                        history: &mut self.order_history,
+                        owner_orders: &mut self.owner_orders,
+                        events: &mut events,
+                        last_seq: &mut self.last_seq,
+                        scale: self.config.price_scale,
+                    };
+                    match_at_price_level(
+                        &mut self.ask_book.price_levels[*ask_level],
+                        (Side::Ask, *ask_price),
+                        &mut remaining_qty,
+                        &mut ctx,
+                    );
+                }
+                for event in events {
+                    self.emit(event);
+                }
+                let ask_fills: Vec<_> = self.maker_fills[fills_before..]
+                    .iter()
+                    .map(|f| (f.order_id, f.qty, Counterparty { order_id: Some(f.order_id), owner: f.owner, tag: f.tag }))
+                    .collect();
+                for (ask_id, qty, sell) in ask_fills {
+                    report.fills.push(AuctionFill { order_id: ask_id, side: Side::Ask, qty });
+                    let buy = Counterparty { order_id: Some(bid_id), owner: taker.map(|(owner, _)| owner), tag: bid_tag };
+                    self.record_trade(price, qty, buy, sell, None, false);
+                }
+                self.counters.trades += (self.maker_fills.len() - fills_before) as u64;
+
+                // The bid filled what it traded with the asks, what self-trade
+                // prevention took off it is cancelled
+                let filled_qty: u64 = self.maker_fills[fills_before..].iter().map(|f| f.qty).sum();
+                let prevented_qty: u64 = prevented.iter().map(|p| p.incoming_cancelled_qty).sum();
+                let mut still_rests = true;
+                if filled_qty > 0 {
+                    report.matched_qty = report.matched_qty.saturating_add(filled_qty);
+                    self.counters.matched_qty = self.counters.matched_qty.saturating_add(filled_qty);
+                    if let Some((owner, _)) = taker {
+                        update_position(&mut self.positions, owner, Side::Bid, filled_qty);
+                    }
+                    report.fills.push(AuctionFill { order_id: bid_id, side: Side::Bid, qty: filled_qty });
+                    still_rests = self.take_from_resting_bid(bid_level, i, filled_qty, price);
+                }
+                if prevented_qty > 0 && still_rests {
+                    still_rests = self.cancel_from_resting_bid(bid_level, i, prevented_qty);
+                }
+                if still_rests {
+                    i += 1;
+                }
+            }
+        }
+
+        // Levels are only dropped once every bid went, their indices are held until then
+        self.bid_book.reclaim_levels(bid_levels.into_iter().map(|(p, _)| p), &mut self.order_loc);
+        self.ask_book.reclaim_levels(ask_levels.into_iter().map(|(p, _)| p), &mut self.order_loc);
+        if report.matched_qty > 0 {
+            self.stop_trigger_price = Some(price);
+        }
+        report
+    }
+
+    // Takes `qty` traded at `price` off the bid at `i`, displayed qty first.
+    // True if it still rests at `i`.
+    fn take_from_resting_bid(&mut self, bid_level: usize, i: u64, qty: u64, price: u64) -> bool {
+        let level = &mut self.bid_book.price_levels[bid_level];
+        let bid = &mut level[i];
+        bid.filled_qty += qty;
+        bid.filled_notional += qty as u128 * price as u128;
+        let from_displayed = qty.min(bid.qty);
+        bid.qty -= from_displayed;
+        bid.reserve_qty -= qty - from_displayed;
+        let done = bid.qty == 0 && bid.reserve_qty == 0;
+        if bid.qty == 0 && !done {
+            bid.qty = bid.display_qty.min(bid.reserve_qty);
+            bid.reserve_qty -= bid.qty;
+        }
+        self.maker_fills.push(MakerFill { order_id: bid.order_id, owner: bid.owner, tag: bid.tag, qty, price, done });
+        if done {
+            let bid = level.remove(i).unwrap();
+            let bid_price = self.bid_book.level_prices[bid_level];
+            self.finish_order(&bid, Side::Bid, bid_price, OrderStatus::Filled);
+        }
+        !done
+    }
+
+    // Cancels `qty` of the bid at `i`, reserve first. True if it still rests at `i`.
+    fn cancel_from_resting_bid(&mut self, bid_level: usize, i: u64, qty: u64) -> bool {
+        let level = &mut self.bid_book.price_levels[bid_level];
+        let bid = &mut level[i];
+        let from_reserve = qty.min(bid.reserve_qty);
+        bid.reserve_qty -= from_reserve;
+        bid.qty -= (qty - from_reserve).min(bid.qty);
+        if bid.qty > 0 {
+            return true;
+        }
+        let bid = level.remove(i).unwrap();
+        let bid_price = self.bid_book.level_prices[bid_level];
+        self.finish_order(&bid, Side::Bid, bid_price, OrderStatus::Cancelled);
+        self.next_seq();
+        false
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::HashSet;
+
+use crate::{AccountId, AmendResult, CancelReport, Error, FillResult, OrderBook, OrderId, OrderKind, OrderOptions, OrderStatus, Price, Side, SystemClock, TimeInForce};
+
+// One thing to do to the book as part of a batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BookOp {
+    // Limit order, for `owner` if given so it never trades against that owner's orders
+    Add { side: Side, price: Price, qty: u64, tif: TimeInForce, owner: Option<AccountId> },
+    Cancel { order_id: OrderId },
+    // As amend_order
+    Amend { order_id: OrderId, new_price: Price, new_qty: u64 },
+}
+
+// What the op at the same index of a batch did
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OpResult {
+    Added(FillResult),
+    Cancelled(Result<CancelReport, Error>),
+    Amended(Result<AmendResult, Error>),
+}
+
+impl OpResult {
+    // Why the op did nothing, an add counting as failed only if it was rejected
+    pub fn error(&self) -> Option<Error> {
+        match self {
+            OpResult::Added(fill_result) => match fill_result.status {
+                OrderStatus::Rejected(reason) => Some(Error::Rejected(reason)),
+                _ => None,
+            },
+            OpResult::Cancelled(result) => result.as_ref().err().cloned(),
+            OpResult::Amended(result) => result.as_ref().err().cloned(),
+        }
+    }
+}
+
+// What a batch would come to, where that can be told without applying it
+enum Outcome {
+    Applies,
+    Fails { index: usize, error: Error },
+    // Some op depends on what an op before it does to the book
+    Unknown,
+}
+
+impl OrderBook {
+    // Applies `ops` in order and returns what each did, at the same index. One op
+    // failing does not stop the ones after it. Each op matches against the book the
+    // ops before it left, but stops, pegs, midpoint orders and reduce-only resizes
+    // are only triggered once, after the last op, as they would be after a single
+    // op leaving the book that way. BBO listeners hear once, after that, and event
+    // observers get the batch's events together at the end.
+    pub fn apply_batch(&mut self, ops: &[BookOp]) -> Vec<OpResult> {
+        self.event_batch(|book| {
+            book.bbo_batch(|book| {
+                let results = ops.iter().map(|op| book.apply_op(op)).collect();
+                book.process_triggers();
+                results
+            })
+        })
+    }
+
+    // As apply_batch, but all or nothing: if an op fails the book is left as it was
+    // before the batch and the error says which op it was. Mostly whether each op
+    // succeeds can be told from the book beforehand, an add from the order limits,
+    // a cancel or amend from its order resting and from no op before it trading or
+    // touching that order or anything linked to it. Such a batch is checked first
+    // and then applied, or not at all. Any other batch is applied to the book after
+    // taking a copy of everything in it, and the copy is put back to roll it back.
+    // Ids given to orders of a rolled back batch are given out again, and observers
+    // and BBO listeners hear nothing of it.
+    pub fn apply_batch_atomic(&mut self, ops: &[BookOp]) -> Result<Vec<OpResult>, Error> {
+        let saved = match self.batch_outcome(ops) {
+            Outcome::Applies => None,
+            Outcome::Fails { index, error } => return Err(Error::BatchRolledBack { index, error: Box::new(error) }),
+            Outcome::Unknown => Some(self.state_copy()),
+        };
+        self.event_batch(|book| {
+            book.bbo_batch(|book| {
+                let mut results = Vec::with_capacity(ops.len());
+                for (index, op) in ops.iter().enumerate() {
+                    let result = book.apply_op(op);
+                    if let Some(error) = result.error() {
+                        let saved = saved.expect("a batch checked to apply failed");
+                        book.restore_state(saved);
+                        return Err(Error::BatchRolledBack { index, error: Box::new(error) });
+                    }
+                    results.push(result);
+                }
+                book.process_triggers();
+                Ok(results)
+            })
+        })
+    }
+
+    // Works out from the book as it is whether every op would succeed, or which
+    // would be the first to fail. That holds as long as no op could trade, so no
+    // stop, peg or linked order moves before the batch's triggers run, and no op
+    // acts on an order an op before it acted on.
+    fn batch_outcome(&self, ops: &[BookOp]) -> Outcome {
+        if self.in_auction() || !self.pegged_orders.is_empty() {
+            return Outcome::Unknown;
+        }
+        if self.midpoint_book.has_orders(Side::Bid) || self.midpoint_book.has_orders(Side::Ask) {
+            return Outcome::Unknown;
+        }
+        // Highest bid and lowest ask the ops before have put in the book
+        let mut bid_high: Option<u64> = None;
+        let mut ask_low: Option<u64> = None;
+        let mut touched = HashSet::new();
+        for (index, op) in ops.iter().enumerate() {
+            let (side, price) = match *op {
+                BookOp::Add { side, price, qty, tif, .. } => {
+                    let checked = self.order_ticks(price).and_then(|price| {
+                        self.check_limit_order(price, qty, tif)?;
+                        Ok(price)
+                    });
+                    match checked {
+                        Ok(price) => (side, price),
+                        Err(reason) => return Outcome::Fails { index, error: Error::Rejected(reason) },
+                    }
+                }
+                BookOp::Cancel { order_id } => {
+                    if !self.acts_alone(order_id, &mut touched) {
+                        return Outcome::Unknown;
+                    }
+                    if self.resting_order(order_id).is_some() || self.stop_book.contains(order_id) {
+                        continue;
+                    }
+                    if self.close_orders.iter().any(|o| o.order_id == order_id) {
+                        return Outcome::Unknown;
+                    }
+                    return Outcome::Fails { index, error: self.cancel_error(order_id) };
+                }
+                BookOp::Amend { order_id, new_price, new_qty } => {
+                    let new_price = match self.order_ticks(new_price) {
+                        Ok(new_price) => new_price,
+                        Err(reason) => return Outcome::Fails { index, error: Error::Rejected(reason) },
+                    };
+                    if !self.acts_alone(order_id, &mut touched) {
+                        return Outcome::Unknown;
+                    }
+                    if let Some(error) = self.amend_error(order_id, new_price, new_qty) {
+                        return Outcome::Fails { index, error };
+                    }
+                    let Some(&(side, _, _)) = self.order_loc.get(&order_id) else {
+                        return Outcome::Unknown;
+                    };
+                    (side, new_price)
+                }
+            };
+            let crosses = match side {
+                Side::Bid => ask_low.is_some_and(|ask| ask <= price),
+                Side::Ask => bid_high.is_some_and(|bid| bid >= price),
+            };
+            if crosses || self.crosses_resting(side, price) {
+                return Outcome::Unknown;
+            }
+            match side {
+                Side::Bid => bid_high = bid_high.max(Some(price)),
+                Side::Ask => ask_low = Some(ask_low.map_or(price, |ask| ask.min(price))),
+            }
+        }
+        Outcome::Applies
+    }
+
+    // Whether what a cancel or amend of `order_id` does depends on nothing but the
+    // order as it is now: no op before it in the batch acted on it, it could not be
+    // an order the batch itself adds, and nothing is linked to it. Marks it touched.
+    fn acts_alone(&self, order_id: OrderId, touched: &mut HashSet<OrderId>) -> bool {
+        let linked = self.oco_links.contains_key(&order_id)
+            || self.oca_group_of.contains_key(&order_id)
+            || self.brackets.contains_key(&order_id)
+            || self.take_profit_stops.contains_key(&order_id)
+            || self.reduce_only_orders.contains(&order_id);
+        !linked && order_id.0 <= self.last_order_id && touched.insert(order_id)
+    }
+
+    // Runs an op leaving triggering to the end of the batch
+    fn apply_op(&mut self, op: &BookOp) -> OpResult {
+        match *op {
+            BookOp::Add { side, price, qty, tif, owner } => {
+                let price = match self.limit_ticks(OrderKind::Limit, price, qty) {
+                    Ok(price) => price,
+                    Err(rejected) => return OpResult::Added(rejected),
+                };
+                let order_id = self.new_order_id();
+                let options = OrderOptions { owner, ..Default::default() };
+                OpResult::Added(self.execute_limit_order(order_id, side, price, qty, tif, options))
+            }
+            BookOp::Cancel { order_id } => OpResult::Cancelled(self.cancel_without_triggers(order_id)),
+            BookOp::Amend { order_id, new_price, new_qty } => OpResult::Amended(
+                self.order_ticks(new_price)
+                    .map_err(Error::Rejected)
+                    .and_then(|new_price| self.amend_without_triggers(order_id, new_price, new_qty)),
+            ),
+        }
+    }
+
+    // Everything about the book a batch can change, with a stand-in clock and no
+    // observers or scratch buffers, which stay with the book
+    fn state_copy(&self) -> OrderBook {
+        OrderBook {
+            symbol: self.symbol.clone(),
+            best_ask_price: self.best_ask_price,
+            best_bid_price: self.best_bid_price,
+            ask_book: self.ask_book.clone(),
+            bid_book: self.bid_book.clone(),
+            order_loc: self.order_loc.clone(),
+            stop_book: self.stop_book.clone(),
+            last_trade_price: self.last_trade_price,
+            last_trade_qty: self.last_trade_qty,
+            session_high: self.session_high,
+            session_low: self.session_low,
+            session_volume: self.session_volume,
+            stop_trigger_price: self.stop_trigger_price,
+            triggered_stops: self.triggered_stops.clone(),
+            expired_orders: self.expired_orders.clone(),
+            pegged_orders: self.pegged_orders.clone(),
+            midpoint_book: self.midpoint_book.clone(),
+            midpoint_crosses: self.midpoint_crosses.clone(),
+            maker_fills: self.maker_fills.clone(),
+            oco_links: self.oco_links.clone(),
+            linked_cancels: self.linked_cancels.clone(),
+            brackets: self.brackets.clone(),
+            take_profit_stops: self.take_profit_stops.clone(),
+            oca_groups: self.oca_groups.clone(),
+            oca_group_of: self.oca_group_of.clone(),
+            fired_oca_groups: self.fired_oca_groups.clone(),
+            config: self.config.clone(),
+            phase: self.phase,
+            close_orders: self.close_orders.clone(),
+            closing_price: self.closing_price,
+            closing_volume: self.closing_volume,
+            trade_tape: self.trade_tape.clone(),
+            candles: self.candles.clone(),
+            trade_stats: self.trade_stats.clone(),
+            last_seq: self.last_seq,
+            last_trade_id: self.last_trade_id,
+            last_order_id: self.last_order_id,
+            positions: self.positions.clone(),
+            reduce_only_orders: self.reduce_only_orders.clone(),
+            reduce_only_resizes: self.reduce_only_resizes.clone(),
+            order_history: self.order_history.clone(),
+            counters: self.counters.clone(),
+            owner_orders: self.owner_orders.clone(),
+            last_bbo: self.last_bbo,
+            bbo_seq: self.bbo_seq,
+            clock: Box::new(SystemClock),
+            bbo_listeners: Vec::new(),
+            event_listeners: Vec::new(),
+            bbo_batch: 0,
+            event_batch: 0,
+            pending_events: Vec::new(),
+            visited_levels: Vec::new(),
+            match_events: Vec::new(),
+            fill_buffer: Vec::new(),
+        }
+    }
+
+    // Puts back what state_copy took, keeping the clock, the observers, the scratch
+    // buffers and the batches in progress, and dropping the events held back since
+    fn restore_state(&mut self, mut saved: OrderBook) {
+        std::mem::swap(&mut saved.clock, &mut self.clock);
+        std::mem::swap(&mut saved.bbo_listeners, &mut self.bbo_listeners);
+        std::mem::swap(&mut saved.event_listeners, &mut self.event_listeners);
+        std::mem::swap(&mut saved.visited_levels, &mut self.visited_levels);
+        std::mem::swap(&mut saved.match_events, &mut self.match_events);
+        std::mem::swap(&mut saved.fill_buffer, &mut self.fill_buffer);
+        saved.bbo_batch = self.bbo_batch;
+        saved.event_batch = self.event_batch;
+        *self = saved;
+    }
+}
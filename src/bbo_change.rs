@@ -0,0 +1,59 @@
+use std::fmt;
+
+use crate::{Bbo, OrderBook};
+
+// Top of the book before and after an operation that changed it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BboChange {
+    // 1 for the first change, then one more for every change after it
+    pub seq: u64,
+    pub old: Bbo,
+    pub new: Bbo,
+}
+
+pub(crate) struct BboListener(Box<dyn FnMut(&BboChange)>);
+
+impl fmt::Debug for BboListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BboListener")
+    }
+}
+
+impl OrderBook {
+    // Calls `callback` after every operation that changes the best bid or ask price or
+    // the displayed qty at either, once with the state before and after it however many
+    // levels it went through. Changes deeper in the book that leave the top alone are
+    // not reported.
+    pub fn on_bbo_change(&mut self, callback: impl FnMut(&BboChange) + 'static) {
+        self.last_bbo = self.bbo();
+        self.bbo_listeners.push(BboListener(Box::new(callback)));
+    }
+
+    // Tells the listeners if the top of the book moved since they last heard,
+    // unless a larger operation this is part of will tell them when it is done
+    pub(crate) fn notify_bbo_change(&mut self) {
+        if self.bbo_batch > 0 || self.bbo_listeners.is_empty() {
+            return;
+        }
+        let new = self.bbo();
+        if new == self.last_bbo {
+            return;
+        }
+        self.bbo_seq += 1;
+        let change = BboChange { seq: self.bbo_seq, old: self.last_bbo, new };
+        self.last_bbo = new;
+        for listener in &mut self.bbo_listeners {
+            (listener.0)(&change);
+        }
+    }
+
+    // Runs `op` as a single operation as far as BBO listeners are concerned
+    pub(crate) fn bbo_batch<R>(&mut self, op: impl FnOnce(&mut OrderBook) -> R) -> R {
+        self.bbo_batch += 1;
+        let result = op(self);
+        self.bbo_batch -= 1;
+        self.notify_bbo_change();
+        result
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{Event, FillResult, OcoTrigger, OrderBook, OrderId, OrderKind, OrderOptions, OrderSpec, OrderStatus, Price, RejectReason, Side, TimeInForce};
+
+// Entry order that spawns an exit pair on the opposite side as it fills
+#[derive(Debug, Clone)]
+pub(crate) struct Bracket {
+    side: Side,
+    take_profit_price: u64,
+    stop_loss_price: u64,
+    // (take-profit id, stop-loss id) of the pair protecting the filled qty
+    exits: Option<(OrderId, OrderId)>,
+}
+
+impl OrderBook {
+    // Submits a limit entry that, for every qty it fills, places a take-profit limit at
+    // `take_profit_price` and a stop-loss at `stop_loss_price` on the opposite side.
+    // The two exits are linked as OCO and always cover the filled qty not exited yet.
+    pub fn create_bracket_order(
+        &mut self,
+        s: Side,
+        price: impl Into<Price>,
+        qty: u64,
+        take_profit_price: impl Into<Price>,
+        stop_loss_price: impl Into<Price>,
+    ) -> (OrderId, FillResult) {
+        let order_id = self.new_order_id();
+        let price = price.into();
+        // The exits are checked up front, they could not be refused once the entry fills
+        let exits = [take_profit_price.into(), stop_loss_price.into()].map(|exit_price| -> Result<u64, RejectReason> {
+            let exit_price = self.order_ticks(exit_price)?;
+            self.check_order_limits(Some(exit_price), qty)?;
+            Ok(exit_price)
+        });
+        let (take_profit_price, stop_loss_price) = match exits {
+            [Ok(take_profit_price), Ok(stop_loss_price)] => (take_profit_price, stop_loss_price),
+            [Err(reason), _] | [_, Err(reason)] => {
+                self.emit(Event::OrderRejected { kind: OrderKind::Limit, price: Some(price), qty, reason });
+                return (order_id, FillResult::rejected(qty, reason));
+            }
+        };
+        let price = match self.limit_ticks(OrderKind::Limit, price, qty) {
+            Ok(price) => price,
+            Err(rejected) => return (order_id, rejected),
+        };
+        self.brackets.insert(order_id, Bracket {
+            side: s,
+            take_profit_price,
+            stop_loss_price,
+            exits: None,
+        });
+
+        let fill_result = self.execute_limit_order(
+            order_id,
+            s,
+            price,
+            qty,
+            TimeInForce::GoodTillCancel,
+            OrderOptions::default(),
+        );
+        let filled_qty = qty - fill_result.remaining_qty;
+        if filled_qty > 0 {
+            let done = matches!(fill_result.status, OrderStatus::Filled);
+            self.on_bracket_execution(order_id, filled_qty, done);
+        }
+
+        self.process_triggers();
+        (order_id, fill_result)
+    }
+
+    // Spawns or grows the exits of a bracket entry, and shrinks the stop-loss
+    // of a bracket whose take-profit traded
+    pub(crate) fn on_bracket_execution(&mut self, order_id: OrderId, qty: u64, done: bool) {
+        if let Some(stop_loss_id) = self.take_profit_stops.get(&order_id).cloned() {
+            if done {
+                self.take_profit_stops.remove(&order_id);
+            } else {
+                self.sync_stop_loss(order_id, stop_loss_id);
+            }
+            return;
+        }
+
+        let Some(bracket) = self.brackets.get(&order_id) else {
+            return;
+        };
+        let exit_side = bracket.side.opposite();
+        let (take_profit_price, stop_loss_price) = (bracket.take_profit_price, bracket.stop_loss_price);
+        let live_exits = bracket.exits.filter(|(take_profit_id, stop_loss_id)| {
+            self.order_loc.contains_key(take_profit_id) && self.stop_book.contains(*stop_loss_id)
+        });
+        if done {
+            self.brackets.remove(&order_id);
+        }
+
+        let (take_profit_id, stop_loss_id) = match live_exits {
+            Some((take_profit_id, stop_loss_id)) => {
+                self.emit(Event::BracketExits { order_id, qty, grown: true });
+                self.grow_resting_order(take_profit_id, qty);
+                (take_profit_id, stop_loss_id)
+            }
+            None => {
+                self.emit(Event::BracketExits { order_id, qty, grown: false });
+                let Ok(exits) = self.submit_oco(
+                    OrderSpec::Limit { side: exit_side, price: self.price(take_profit_price), qty },
+                    OrderSpec::Stop { side: exit_side, stop_price: self.price(stop_loss_price), qty },
+                    OcoTrigger::CompleteFill,
+                ) else {
+                    return;
+                };
+                if let Some(bracket) = self.brackets.get_mut(&order_id) {
+                    bracket.exits = Some(exits);
+                }
+                exits
+            }
+        };
+        if self.order_loc.contains_key(&take_profit_id) {
+            self.take_profit_stops.insert(take_profit_id, stop_loss_id);
+            self.sync_stop_loss(take_profit_id, stop_loss_id);
+        }
+    }
+
+    // Keeps the stop-loss at the qty its take-profit still has resting
+    pub(crate) fn sync_stop_loss(&mut self, take_profit_id: OrderId, stop_loss_id: OrderId) {
+        let Some(resting_qty) = self.resting_qty(take_profit_id) else {
+            return;
+        };
+        if let Some(stop_qty) = self.stop_book.qty_mut(stop_loss_id) {
+            *stop_qty = resting_qty;
+        }
+    }
+
+    // Adds `qty` to a resting order, which sends it to the back of its level
+    fn grow_resting_order(&mut self, order_id: OrderId, qty: u64) {
+        let Some(loc) = self.order_loc.get_mut(&order_id) else {
+            return;
+        };
+        let book = match loc.0 {
+            Side::Ask => &mut self.ask_book,
+            Side::Bid => &mut self.bid_book,
+        };
+        let Some(level) = book.level_mut(loc.1) else {
+            return;
+        };
+        let Some(mut order) = level.remove(loc.2) else {
+            return;
+        };
+        order.qty += qty;
+        order.display_qty += qty;
+        loc.2 = level.push_back(order);
+    }
+}
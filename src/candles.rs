@@ -0,0 +1,117 @@
+use crate::{OrderBook, Price, Trade};
+
+// Open, high, low, close and volume of the trades in one interval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Candle {
+    // Clock time the interval starts at, a multiple of the interval length
+    pub start: u64,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: u64,
+    pub trade_count: u64,
+}
+
+impl Candle {
+    fn new(start: u64, price: Price) -> Candle {
+        Candle { start, open: price, high: price, low: price, close: price, volume: 0, trade_count: 0 }
+    }
+}
+
+// What happens to intervals without a single trade
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CandleGaps {
+    // No candle is emitted for them
+    #[default]
+    Skip,
+    // A candle with no volume at the previous close is emitted for each
+    CarryForward,
+}
+
+// Turns trades into candles of `interval` clock ticks, in the order trades happen
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CandleBuilder {
+    interval: u64,
+    gaps: CandleGaps,
+    current: Option<Candle>,
+    completed: Vec<Candle>,
+}
+
+impl CandleBuilder {
+    // An `interval` of 0 counts as 1
+    pub fn new(interval: u64, gaps: CandleGaps) -> CandleBuilder {
+        CandleBuilder { interval: interval.max(1), gaps, current: None, completed: Vec::new() }
+    }
+
+    fn interval_start(&self, timestamp: u64) -> u64 {
+        timestamp - timestamp % self.interval
+    }
+
+    // Adds a trade to its interval's candle, completing the candles of earlier intervals.
+    // Trades timestamped before the current interval count towards it.
+    pub fn on_trade(&mut self, trade: &Trade) {
+        self.advance_to(trade.timestamp);
+        let start = self.interval_start(trade.timestamp);
+        let candle = self.current.get_or_insert(Candle::new(start, trade.price));
+        candle.high = candle.high.max(trade.price);
+        candle.low = candle.low.min(trade.price);
+        candle.close = trade.price;
+        candle.volume = candle.volume.saturating_add(trade.qty);
+        candle.trade_count += 1;
+    }
+
+    // Completes the current candle once `now` is past its interval, along with the empty
+    // candles up to `now` if gaps are carried forward
+    pub fn advance_to(&mut self, now: u64) {
+        let start = self.interval_start(now);
+        let Some(candle) = self.current.filter(|c| c.start < start) else {
+            return;
+        };
+        self.completed.push(candle);
+        self.current = None;
+        if self.gaps == CandleGaps::CarryForward {
+            let mut gap_start = candle.start + self.interval;
+            while gap_start < start {
+                self.completed.push(Candle::new(gap_start, candle.close));
+                gap_start += self.interval;
+            }
+            self.current = Some(Candle::new(start, candle.close));
+        }
+    }
+
+    // Candle of the interval in progress, None if it has no trade yet and gaps are skipped
+    pub fn current(&self) -> Option<&Candle> {
+        self.current.as_ref()
+    }
+
+    // Candles completed since the previous call, oldest first
+    pub fn take_completed(&mut self) -> Vec<Candle> {
+        std::mem::take(&mut self.completed)
+    }
+}
+
+impl OrderBook {
+    // Builds candles of `interval` clock ticks from every trade the book records from now
+    // on, block trades included. Replaces candles built so far.
+    pub fn enable_candles(&mut self, interval: u64, gaps: CandleGaps) {
+        self.candles = Some(CandleBuilder::new(interval, gaps));
+    }
+
+    // Candles completed since the previous call, up to the current clock time
+    pub fn take_candles(&mut self) -> Vec<Candle> {
+        let now = self.clock.now();
+        self.candles.as_mut().map_or(Vec::new(), |c| {
+            c.advance_to(now);
+            c.take_completed()
+        })
+    }
+
+    // Candle of the interval in progress as of the latest trade or take_candles
+    pub fn current_candle(&self) -> Option<&Candle> {
+        self.candles.as_ref()?.current()
+    }
+}
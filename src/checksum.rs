@@ -0,0 +1,61 @@
+use crate::{DepthLevel, OrderBook};
+
+// Decimal places an exchange feed shows beyond the book's integer prices and quantities.
+// A book in cents replicating a feed quoted to 5 decimals has a `price_decimals` of 3.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChecksumScale {
+    pub price_decimals: u32,
+    pub qty_decimals: u32,
+}
+
+// CRC-32 as used by zlib and the exchange feeds, reflected polynomial 0xEDB88320
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for b in bytes {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+// A number as the feed prints it with the decimal point and leading zeros removed,
+// which for an integer scaled up by `decimals` places is its digits followed by zeros
+fn push_digits(s: &mut String, value: u64, decimals: u32) {
+    if value == 0 {
+        return;
+    }
+    s.push_str(&value.to_string());
+    s.extend(std::iter::repeat_n('0', decimals as usize));
+}
+
+impl OrderBook {
+    // Checksum of the top `depth` displayed levels of each side, see checksum_scaled
+    pub fn checksum(&self, depth: usize) -> u32 {
+        self.checksum_scaled(depth, ChecksumScale::default())
+    }
+
+    // CRC-32 of the top `depth` levels the way Kraken style feeds compute it, to compare
+    // a replica of the book against the exchange. The checksummed string is, with no
+    // separators:
+    //   for each ask level from the lowest price up, then each bid level from the highest
+    //   price down: the price then the displayed qty, each written in decimal digits with
+    //   `scale` zeros appended and leading zeros dropped.
+    // So price 5005 and qty 20 at 3 price decimals and 2 qty decimals give "50050002000".
+    // A feed that pads to a fixed number of decimals drops the point, so scaling by
+    // appending zeros matches it exactly. The checksum is CRC-32 (IEEE) of the ASCII bytes.
+    pub fn checksum_scaled(&self, depth: usize, scale: ChecksumScale) -> u32 {
+        let book = self.depth(depth);
+        let mut s = String::new();
+        let mut push_level = |l: &DepthLevel| {
+            push_digits(&mut s, l.price.ticks(), scale.price_decimals);
+            push_digits(&mut s, l.qty, scale.qty_decimals);
+        };
+        book.asks.iter().for_each(&mut push_level);
+        book.bids.iter().for_each(&mut push_level);
+        crc32(s.as_bytes())
+    }
+}
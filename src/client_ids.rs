@@ -0,0 +1,34 @@
+use crate::{AccountId, CancelReport, Error, FillResult, OrderBook, OrderId, OrderOptions, Price, Side, TimeInForce};
+
+impl OrderBook {
+    // Limit order for `owner` like add_owned_limit_order, under an id of the owner's
+    // choosing that get_order reports back and cancel_by_client_id finds it by. An id the
+    // owner already used is rejected with DuplicateClientOrderId for as long as the book
+    // remembers the order it was used for, the whole time it rests and then as long as
+    // the order history keeps it.
+    pub fn add_client_order(
+        &mut self,
+        owner: AccountId,
+        client_order_id: Option<String>,
+        s: Side,
+        price: impl Into<Price>,
+        order_qty: u64,
+        tif: TimeInForce,
+    ) -> FillResult {
+        let options = OrderOptions { owner: Some(owner), client_order_id, ..Default::default() };
+        self.submit_limit_order(s, price.into(), order_qty, tif, options)
+    }
+
+    // Id the book gave the order `owner` submitted as `client_order_id`
+    pub fn order_id_for_client_id(&self, owner: AccountId, client_order_id: &str) -> Option<OrderId> {
+        self.order_history.order_id_for_client_id(owner, client_order_id)
+    }
+
+    // Cancels the order `owner` submitted as `client_order_id` like cancel_order
+    pub fn cancel_by_client_id(&mut self, owner: AccountId, client_order_id: &str) -> Result<CancelReport, Error> {
+        let Some(order_id) = self.order_id_for_client_id(owner, client_order_id) else {
+            return Err(Error::UnknownClientOrderId { owner, client_order_id: client_order_id.to_string() });
+        };
+        self.cancel_order(order_id)
+    }
+}
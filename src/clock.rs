@@ -0,0 +1,52 @@
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// Source of the current time in nanoseconds, injectable so tests and replays control it
+pub trait Clock: Debug {
+    fn now(&self) -> u64;
+}
+
+// Nanoseconds since the unix epoch, read off the wall clock once and measured with a
+// monotonic clock from there, so the time never goes backwards if the system clock does
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        static START: OnceLock<(Instant, u64)> = OnceLock::new();
+        let (start, start_nanos) = START.get_or_init(|| {
+            let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+            (Instant::now(), since_epoch)
+        });
+        start_nanos.saturating_add(start.elapsed().as_nanos() as u64)
+    }
+}
+
+// Clock that only moves when told to. Clones share the same time,
+// so keep one handle and give the other to the book.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new(start: u64) -> ManualClock {
+        ManualClock { now: Arc::new(AtomicU64::new(start)) }
+    }
+
+    pub fn set(&self, now: u64) {
+        self.now.store(now, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, by: u64) {
+        self.now.fetch_add(by, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,368 @@
+use crate::{saturating_sum, HalfBook, Order, OrderBook, OrderId, Price, PriceLevel, PriceScale, Side, TickRounding};
+
+// Displayed qty and number of displayed orders at one price
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepthLevel {
+    pub price: Price,
+    pub qty: u64,
+    pub order_count: usize,
+}
+
+// Best price and the displayed qty at it for each side, None for an empty side
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bbo {
+    pub bid: Option<(Price, u64)>,
+    pub ask: Option<(Price, u64)>,
+}
+
+// Best ask minus best bid, 0 for a locked book and negative for a crossed one.
+// Saturates at the bounds of i64.
+pub(crate) fn signed_spread(best_bid: u64, best_ask: u64) -> i64 {
+    (best_ask as i128 - best_bid as i128).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+impl Bbo {
+    // As OrderBook::spread, in ticks, None unless both sides have a price
+    pub fn spread(&self) -> Option<i64> {
+        let ((bid, _), (ask, _)) = (self.bid?, self.ask?);
+        Some(signed_spread(bid.ticks(), ask.ticks()))
+    }
+
+    // Rounded down to the tick
+    pub fn mid(&self) -> Option<Price> {
+        let ((bid, _), (ask, _)) = (self.bid?, self.ask?);
+        Some(bid.scale().price(((bid.ticks() as u128 + ask.ticks() as u128) / 2) as u64))
+    }
+}
+
+// Aggregated levels of each side, best price first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepthSnapshot {
+    pub bids: Vec<DepthLevel>,
+    pub asks: Vec<DepthLevel>,
+}
+
+// Displayed qty and orders of every level from `low_price` to `high_price` inclusive
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepthBucket {
+    pub low_price: Price,
+    pub high_price: Price,
+    pub qty: u64,
+    pub order_count: usize,
+}
+
+// Where a resting order stands in line, counting what would match before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueuePosition {
+    // Displayed qty still resting ahead of it at its price
+    pub qty_ahead: u64,
+    // Orders matched before it at its price, hidden ones included if it is hidden too
+    pub orders_ahead: usize,
+    // Displayed qty at better prices on its side
+    pub better_price_qty: u64,
+}
+
+// Read-only view of one non-empty price level
+#[derive(Debug, Clone, Copy)]
+pub struct LevelView<'a> {
+    pub price: Price,
+    // Displayed qty, as depth counts it
+    pub qty: u64,
+    orders: &'a PriceLevel,
+    now: u64,
+}
+
+impl<'a> LevelView<'a> {
+    // Displayed orders resting at the price in queue order. Hidden orders are left
+    // out and so are expired ones matching would purge.
+    pub fn orders(&self) -> impl Iterator<Item = &'a Order> {
+        let now = self.now;
+        self.orders.iter().filter(move |o| !o.hidden && !o.is_expired(now))
+    }
+
+    // Every order resting at the price, hidden and expired ones included
+    pub(crate) fn resting_orders(&self) -> impl Iterator<Item = &'a Order> {
+        self.orders.iter()
+    }
+}
+
+impl HalfBook {
+    // Levels depth shows from the best price outward, those with displayed orders
+    fn displayed_levels(&self, now: u64, scale: PriceScale) -> impl Iterator<Item = LevelView<'_>> {
+        self.levels(now, scale).filter(|l| l.orders().next().is_some())
+    }
+
+    // Non-empty levels from the best price outward, hidden-only ones included
+    pub(crate) fn levels(&self, now: u64, scale: PriceScale) -> impl Iterator<Item = LevelView<'_>> {
+        let prices: Box<dyn Iterator<Item = (&u64, &usize)>> = match self.s {
+            Side::Bid => Box::new(self.price_map.iter().rev()),
+            Side::Ask => Box::new(self.price_map.iter()),
+        };
+        prices.filter_map(move |(price, u)| {
+            let orders = &self.price_levels[*u];
+            let qty = saturating_sum(orders.iter().filter(|o| !o.hidden && !o.is_expired(now)).map(|o| o.qty));
+            (!orders.is_empty()).then_some(LevelView { price: scale.price(*price), qty, orders, now })
+        })
+    }
+
+    // Up to `levels` levels from the best price outward. Hidden orders are left out
+    // and so are expired ones matching would purge, levels with nothing else are skipped.
+    fn depth(&self, levels: usize, now: u64, scale: PriceScale) -> Vec<DepthLevel> {
+        self.displayed_levels(now, scale)
+            .map(|l| DepthLevel { price: l.price, qty: l.qty, order_count: l.orders().count() })
+            .take(levels)
+            .collect()
+    }
+}
+
+impl HalfBook {
+    // Levels depth would show, grouped into buckets of `bucket_ticks` prices
+    fn aggregated_depth(&self, bucket_ticks: u64, n_buckets: usize, now: u64, scale: PriceScale) -> Vec<DepthBucket> {
+        let mut buckets: Vec<DepthBucket> = Vec::new();
+        for l in self.displayed_levels(now, scale) {
+            let order_count = l.orders().count();
+            let ticks = l.price.ticks();
+            let low_price = scale.price(ticks - ticks % bucket_ticks);
+            if let Some(b) = buckets.last_mut().filter(|b| b.low_price == low_price) {
+                b.qty = b.qty.saturating_add(l.qty);
+                b.order_count += order_count;
+                continue;
+            }
+            if buckets.len() == n_buckets {
+                break;
+            }
+            buckets.push(DepthBucket {
+                low_price,
+                high_price: scale.price(low_price.ticks().saturating_add(bucket_ticks - 1)),
+                qty: l.qty,
+                order_count,
+            });
+        }
+        buckets
+    }
+
+    // Displayed qty at `limit_price` or better, what depth would show for those levels
+    fn cumulative_qty(&self, limit_price: u64, now: u64) -> u64 {
+        let prices = match self.s {
+            Side::Bid => self.price_map.range(limit_price..),
+            Side::Ask => self.price_map.range(..=limit_price),
+        };
+        let total: u128 = prices
+            .flat_map(|(_, u)| self.price_levels[*u].iter())
+            .filter(|o| !o.hidden && !o.is_expired(now))
+            .map(|o| o.qty as u128)
+            .sum();
+        total.min(u64::MAX as u128) as u64
+    }
+
+    // Price times displayed qty over the same levels as cumulative_qty
+    fn cumulative_notional(&self, limit_price: u64, now: u64) -> u128 {
+        let prices = match self.s {
+            Side::Bid => self.price_map.range(limit_price..),
+            Side::Ask => self.price_map.range(..=limit_price),
+        };
+        prices
+            .flat_map(|(price, u)| self.price_levels[*u].iter().map(move |o| (*price, o)))
+            .filter(|(_, o)| !o.hidden && !o.is_expired(now))
+            .fold(0u128, |total, (price, o)| total.saturating_add(price as u128 * o.qty as u128))
+    }
+}
+
+impl OrderBook {
+    // Walks the levels of side `s` from the best price to the worst, the ones depth
+    // shows. Hidden orders are not shown, nor levels holding nothing else.
+    pub fn levels(&self, s: Side) -> impl Iterator<Item = LevelView<'_>> {
+        let (now, scale) = (self.clock.now(), self.config.price_scale);
+        match s {
+            Side::Bid => self.bid_book.displayed_levels(now, scale),
+            Side::Ask => self.ask_book.displayed_levels(now, scale),
+        }
+    }
+
+    // Displayed qty resting on side `s` at `limit_price` or better, bids at or above
+    // it and asks at or below it. A limit between ticks counts the ticks within it.
+    pub fn cumulative_qty(&self, s: Side, limit_price: impl Into<Price>) -> u64 {
+        let now = self.clock.now();
+        let limit_price = match s {
+            Side::Bid => self.ticks_toward(limit_price.into(), TickRounding::Up),
+            Side::Ask => self.ticks_toward(limit_price.into(), TickRounding::Down),
+        };
+        match s {
+            Side::Bid => self.bid_book.cumulative_qty(limit_price, now),
+            Side::Ask => self.ask_book.cumulative_qty(limit_price, now),
+        }
+    }
+
+    // Queue position of a resting limit order, None once it is filled or cancelled.
+    // Displayed orders match before hidden ones at a price, so only orders that would
+    // match first count as ahead, at their current qty.
+    pub fn queue_position(&self, order_id: OrderId) -> Option<QueuePosition> {
+        let &(side, level, pos) = self.order_loc.get(&order_id)?;
+        let book = match side {
+            Side::Ask => &self.ask_book,
+            Side::Bid => &self.bid_book,
+        };
+        let now = self.clock.now();
+        let price = book.price_of_level(level)?;
+        let level = book.level(level)?;
+        let hidden = level.get(pos)?.hidden;
+        let ahead: Vec<_> = level
+            .positions()
+            .filter(|(i, o)| !o.is_expired(now) && if o.hidden { hidden && *i < pos } else { hidden || *i < pos })
+            .map(|(_, o)| o)
+            .collect();
+        let better_price = match side {
+            Side::Bid => price.checked_add(1),
+            Side::Ask => price.checked_sub(1),
+        };
+        Some(QueuePosition {
+            qty_ahead: saturating_sum(ahead.iter().filter(|o| !o.hidden).map(|o| o.qty)),
+            orders_ahead: ahead.len(),
+            better_price_qty: better_price.map_or(0, |p| book.cumulative_qty(p, now)),
+        })
+    }
+
+    // Displayed qty on side `s` priced within `bps` basis points of the mid price, so
+    // bids at or above mid * (1 - bps / 10000) and asks at or below mid * (1 + bps / 10000).
+    // The bound is rounded inward onto whole prices. 0 unless both sides have a price.
+    pub fn liquidity_within(&self, s: Side, bps: u32) -> u64 {
+        self.liquidity_bound(s, bps).map_or(0, |limit_price| match s {
+            Side::Bid => self.bid_book.cumulative_qty(limit_price, self.clock.now()),
+            Side::Ask => self.ask_book.cumulative_qty(limit_price, self.clock.now()),
+        })
+    }
+
+    // Price times displayed qty over the levels liquidity_within counts, saturating
+    pub fn liquidity_within_notional(&self, s: Side, bps: u32) -> u128 {
+        self.liquidity_bound(s, bps).map_or(0, |limit_price| match s {
+            Side::Bid => self.bid_book.cumulative_notional(limit_price, self.clock.now()),
+            Side::Ask => self.ask_book.cumulative_notional(limit_price, self.clock.now()),
+        })
+    }
+
+    // Worst price on side `s` still within `bps` of the mid, computed on twice the mid
+    // so it stays exact
+    fn liquidity_bound(&self, s: Side, bps: u32) -> Option<u64> {
+        let (best_bid, best_ask) = self.maintained_best_prices()?;
+        let twice_mid = best_bid as u128 + best_ask as u128;
+        let bound = match s {
+            Side::Bid => (twice_mid * 10_000u128.saturating_sub(bps as u128)).div_ceil(20_000),
+            Side::Ask => twice_mid * (10_000 + bps as u128) / 20_000,
+        };
+        Some(bound.min(u64::MAX as u128) as u64)
+    }
+
+    // Level 2 view of the book, at most `levels` prices per side
+    pub fn depth(&self, levels: usize) -> DepthSnapshot {
+        let (now, scale) = (self.clock.now(), self.config.price_scale);
+        DepthSnapshot {
+            bids: self.bid_book.depth(levels, now, scale),
+            asks: self.ask_book.depth(levels, now, scale),
+        }
+    }
+
+    // Depth of side `s` in up to `n_buckets` rows of `bucket_ticks` prices each, from the
+    // best price outward. Buckets are anchored at multiples of `bucket_ticks`, so with 5
+    // ticks prices 100 to 104 always share a row whatever the best price is, and only
+    // buckets with displayed qty are returned. A `bucket_ticks` of 0 counts as 1.
+    pub fn aggregated_depth(&self, s: Side, bucket_ticks: u64, n_buckets: usize) -> Vec<DepthBucket> {
+        let (now, scale) = (self.clock.now(), self.config.price_scale);
+        let bucket_ticks = bucket_ticks.max(1);
+        match s {
+            Side::Bid => self.bid_book.aggregated_depth(bucket_ticks, n_buckets, now, scale),
+            Side::Ask => self.ask_book.aggregated_depth(bucket_ticks, n_buckets, now, scale),
+        }
+    }
+
+    // (bid qty - ask qty) / (bid qty + ask qty) over the top `depth_levels` levels
+    // of each side, from -1 with only asks to 1 with only bids. None for an empty book.
+    pub fn imbalance(&self, depth_levels: usize) -> Option<f64> {
+        let depth = self.depth(depth_levels);
+        let bid_qty: u128 = depth.bids.iter().map(|l| l.qty as u128).sum();
+        let ask_qty: u128 = depth.asks.iter().map(|l| l.qty as u128).sum();
+        if bid_qty + ask_qty == 0 {
+            return None;
+        }
+        Some((bid_qty as f64 - ask_qty as f64) / (bid_qty + ask_qty) as f64)
+    }
+
+    // Best bid and ask with the displayed qty at each, from the maintained best prices
+    pub fn bbo(&self) -> Bbo {
+        Bbo {
+            bid: self.best_bid_price.map(|price| (self.price(price), self.bid_book.get_total_qty(price))),
+            ask: self.best_ask_price.map(|price| (self.price(price), self.ask_book.get_total_qty(price))),
+        }
+    }
+
+    pub fn print_bbo(&self) {
+        let bbo = self.bbo();
+        match bbo.bid {
+            Some((price, qty)) => println!("Best bid {}, qty {}", price, qty),
+            None => println!("No bid"),
+        }
+        match bbo.ask {
+            Some((price, qty)) => println!("Best ask {}, qty {}", price, qty),
+            None => println!("No ask"),
+        }
+        if let Some(spread) = bbo.spread() {
+            println!("Spread is {}", spread);
+        }
+    }
+
+    // Highest displayed bid price, None while no bid is displayed
+    pub fn best_bid(&self) -> Option<Price> {
+        self.best_bid_price.map(|price| self.price(price))
+    }
+
+    // Lowest displayed ask price, None while no ask is displayed
+    pub fn best_ask(&self) -> Option<Price> {
+        self.best_ask_price.map(|price| self.price(price))
+    }
+
+    // Displayed qty resting on side `s` at `price`, hidden orders and iceberg reserves
+    // excluded. 0 for a price nothing rests at, or one off the book's ticks.
+    pub fn qty_at(&self, s: Side, price: impl Into<Price>) -> u64 {
+        let Ok(price) = self.order_ticks(price.into()) else {
+            return 0;
+        };
+        match s {
+            Side::Bid => self.bid_book.get_total_qty(price),
+            Side::Ask => self.ask_book.get_total_qty(price),
+        }
+    }
+
+    // Best prices as update_bbo last left them, without rescanning the book
+    fn maintained_best_prices(&self) -> Option<(u64, u64)> {
+        Some((self.best_bid_price?, self.best_ask_price?))
+    }
+
+    // Exact middle of the best bid and ask, None unless both sides have a price
+    pub fn mid_price(&self) -> Option<f64> {
+        let (best_bid, best_ask) = self.maintained_best_prices()?;
+        Some((best_bid as f64 + best_ask as f64) / 2.0 * self.price(1).to_f64())
+    }
+
+    // Best ask minus best bid in ticks, None unless both sides have a price. 0 for a locked book,
+    // negative for a crossed one, which only rests like that during an auction.
+    pub fn spread(&self) -> Option<i64> {
+        let (best_bid, best_ask) = self.maintained_best_prices()?;
+        Some(signed_spread(best_bid, best_ask))
+    }
+
+    // Mid weighted by the displayed qty at the best prices, leaning towards the side
+    // with less qty as that is the one more likely to be taken out first
+    pub fn microprice(&self) -> Option<f64> {
+        let (best_bid, best_ask) = self.maintained_best_prices()?;
+        let bid_qty = self.bid_book.get_total_qty(best_bid) as f64;
+        let ask_qty = self.ask_book.get_total_qty(best_ask) as f64;
+        if bid_qty + ask_qty == 0.0 {
+            return self.mid_price();
+        }
+        Some((best_bid as f64 * ask_qty + best_ask as f64 * bid_qty) / (bid_qty + ask_qty) * self.price(1).to_f64())
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{AccountId, OrderId, PriceError, RejectReason, Side};
+
+// Why a call to the book did nothing, with the ids and values it was about
+#[derive(Debug, Clone, PartialEq, Eq, Hash, thiserror::Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Error {
+    // Never seen, or done too long ago for the order history to still know it
+    #[error("No valid order id {order_id}!")]
+    UnknownOrder { order_id: OrderId },
+    #[error("No order {client_order_id} for account {}!", owner.0)]
+    UnknownClientOrderId { owner: AccountId, client_order_id: String },
+    #[error("Order {order_id} already filled {filled_qty} qty!")]
+    AlreadyFilled { order_id: OrderId, filled_qty: u64 },
+    // Cancelled, killed or expired before, possibly after filling some
+    #[error("Order {order_id} already cancelled, {filled_qty} qty had filled!")]
+    AlreadyCancelled { order_id: OrderId, filled_qty: u64 },
+    // The order was refused before it reached the book
+    #[error("Order {order_id} was rejected!")]
+    WasRejected { order_id: OrderId },
+    // Refused as it entered the book, for an order or a reported trade
+    #[error("{0}")]
+    Rejected(RejectReason),
+    // Pegged orders follow the best price, only their qty can be amended
+    #[error("Order {order_id} is pegged, its price cannot be amended to {price}!")]
+    PeggedPriceAmended { order_id: OrderId, price: u64 },
+    // Nothing rests on the side to peg to
+    #[error("No best {side} price to peg to!")]
+    NoPegReference { side: Side },
+    #[error("{0}")]
+    InvalidPrice(PriceError),
+    // The op at `index` of an atomic batch failed, so none of the batch was applied
+    #[error("Batch rolled back, op {index} failed: {error}")]
+    BatchRolledBack { index: usize, error: Box<Error> },
+}
+
+impl From<RejectReason> for Error {
+    fn from(reason: RejectReason) -> Error {
+        Error::Rejected(reason)
+    }
+}
+
+impl From<PriceError> for Error {
+    fn from(e: PriceError) -> Error {
+        Error::InvalidPrice(e)
+    }
+}
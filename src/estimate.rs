@@ -0,0 +1,61 @@
+use crate::{OrderBook, Price, Side};
+
+// What a market order would do against the book as it is now
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FillEstimate {
+    // Fills (qty, price) from the best price outward, a midpoint fill first if any
+    pub fills: Vec<(u64, Price)>,
+    pub filled_qty: u64,
+    // Qty the book has no liquidity for, which the market order would cancel
+    pub remaining_qty: u64,
+    // None if nothing would fill
+    pub avg_price: Option<f64>,
+    pub worst_price: Option<Price>,
+}
+
+impl OrderBook {
+    // Walks the side opposite `s` like add_market_order for `qty` would, hidden,
+    // all-or-none, min-fill and expired orders included, without changing anything.
+    // Nothing fills during an auction, market orders are rejected then.
+    pub fn estimate_fill(&self, s: Side, qty: u64) -> FillEstimate {
+        let mut fills = Vec::new();
+        if !self.in_auction() {
+            let mut remaining_qty = qty;
+            if let Some(mid) = self.midpoint_for(s, None) {
+                let midpoint_qty = self.midpoint_book.qty(s.opposite()).min(qty);
+                if midpoint_qty > 0 {
+                    fills.push((midpoint_qty, mid));
+                    remaining_qty -= midpoint_qty;
+                }
+            }
+            let opposite_book = match s {
+                Side::Ask => &self.bid_book,
+                Side::Bid => &self.ask_book,
+            };
+            fills.extend(opposite_book.level_fills(
+                None,
+                remaining_qty,
+                self.clock.now(),
+                &self.fired_oca_groups,
+                None,
+                self.config.matching,
+                &self.positions,
+            ));
+        }
+
+        let filled_qty: u64 = fills.iter().map(|(q, _)| q).sum();
+        let notional: u128 = fills.iter().map(|(q, p)| *q as u128 * *p as u128).sum();
+        let worst_price = match s {
+            Side::Bid => fills.iter().map(|(_, p)| *p).max(),
+            Side::Ask => fills.iter().map(|(_, p)| *p).min(),
+        };
+        FillEstimate {
+            avg_price: (filled_qty > 0).then(|| notional as f64 / filled_qty as f64 * self.price(1).to_f64()),
+            worst_price: worst_price.map(|p| self.price(p)),
+            filled_qty,
+            remaining_qty: qty - filled_qty,
+            fills: fills.into_iter().map(|(q, p)| (q, self.price(p))).collect(),
+        }
+    }
+}
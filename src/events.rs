@@ -0,0 +1,219 @@
+use std::fmt;
+
+use crate::{CancelReport, GroupId, OrderBook, OrderId, Price, RejectReason, Side, Trade, TriggerKind, TriggerSource};
+
+// What sort of order an event is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OrderKind {
+    Limit,
+    PostOnly,
+    Market,
+    MarketToLimit,
+    Midpoint,
+    Stop,
+    OnClose,
+    ReduceOnly,
+    BlockTrade,
+}
+
+// Something the book did, reported to the observers registered with on_event as it
+// happens. The book prints nothing itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Event {
+    // An order came in, before anything is done with it. `price` is None without a limit.
+    OrderReceived { kind: OrderKind, side: Side, price: Option<Price>, qty: u64 },
+    OrderRejected { kind: OrderKind, price: Option<Price>, qty: u64, reason: RejectReason },
+    // Qty of the incoming order that traded at one price
+    Matched { price: Price, qty: u64, midpoint: bool },
+    // One execution between two orders, as it goes on the trade tape
+    Trade(Trade),
+    // What is left of a limit order rests at `price`
+    Rested { price: Price, qty: u64 },
+    // What is left of an order that may not rest is cancelled, `price` is None for market orders
+    RemainderCancelled { price: Option<Price>, qty: u64 },
+    // Fill-or-kill order that could not fill completely
+    Killed { price: Price, qty: u64 },
+    OrderCancelled { kind: OrderKind, report: CancelReport },
+    // Cancelled because `cause_order_id` executed or was cancelled, `group` is the
+    // one-cancels-all group they share, None for a linked pair
+    LinkedCancelled { order_id: OrderId, cause_order_id: OrderId, group: Option<GroupId> },
+    // Min-fill order cancelled with less than its minimum left
+    MinFillCancelled { order_id: OrderId, remaining_qty: u64, min_qty: u64 },
+    SelfTradePrevented { resting_order_id: OrderId, resting_cancelled_qty: u64, incoming_cancelled_qty: u64 },
+    Amended { order_id: OrderId, old_price: Price, old_qty: u64, new_price: Price, new_qty: u64, kept_priority: bool },
+    Replaced { order_id: OrderId, new_order_id: OrderId },
+    StopTriggered { order_id: OrderId, kind: TriggerKind, source: TriggerSource, trigger_price: Option<Price> },
+    TrailingStopTriggered { order_id: OrderId, stop_price: Option<Price> },
+    ReduceOnlyResized { order_id: OrderId, old_qty: u64, new_qty: u64 },
+    // Exits of a bracket spawned for the `qty` its entry filled, or grown by it
+    BracketExits { order_id: OrderId, qty: u64, grown: bool },
+    MassCancelled { count: usize },
+    AuctionStarted { closing: bool },
+    OnCloseStaged { order_id: OrderId, qty: u64, limit_price: Option<Price> },
+    Uncrossed { price: Option<Price>, qty: u64 },
+    BlockTradeReported { trade_id: u64, price: Price, qty: u64 },
+    TwapSlice { order_id: OrderId, slice: u64, num_slices: u64, qty: u64 },
+}
+
+impl fmt::Display for OrderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OrderKind::Limit => "limit",
+            OrderKind::PostOnly => "post-only",
+            OrderKind::Market => "market",
+            OrderKind::MarketToLimit => "market-to-limit",
+            OrderKind::Midpoint => "midpoint",
+            OrderKind::Stop => "stop",
+            OrderKind::OnClose => "on-close",
+            OrderKind::ReduceOnly => "reduce-only",
+            OrderKind::BlockTrade => "block trade",
+        })
+    }
+}
+
+// One line in the words the book used to print
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::OrderReceived { kind, side, price: Some(price), qty } => {
+                write!(f, "Got {} {:?} order with qty {}, at price {}", kind, side, qty, price)
+            }
+            Event::OrderReceived { kind, side, price: None, qty } => {
+                write!(f, "Got {} {:?} order with qty {}", kind, side, qty)
+            }
+            Event::OrderRejected { kind, price: Some(price), qty, reason } => {
+                write!(f, "Rejected {} order with qty {}, at price {}: {:?}", kind, qty, price, reason)
+            }
+            Event::OrderRejected { kind, price: None, qty, reason } => {
+                write!(f, "Rejected {} order with qty {}: {:?}", kind, qty, reason)
+            }
+            Event::Matched { price, qty, midpoint: false } => write!(f, "Matched {} qty at price {}", qty, price),
+            Event::Matched { price, qty, midpoint: true } => write!(f, "Matched {} qty at midpoint {}", qty, price),
+            Event::Trade(trade) => write!(
+                f,
+                "Trade {}: {} at {}, buy {} sell {}",
+                trade.trade_id,
+                trade.qty,
+                trade.price,
+                trade.buy_order_id.map_or("-".to_string(), |id| id.to_string()),
+                trade.sell_order_id.map_or("-".to_string(), |id| id.to_string()),
+            ),
+            Event::Rested { price, qty } => write!(f, "Still remaining qty {} at price level {}", qty, price),
+            Event::RemainderCancelled { price: Some(price), qty } => {
+                write!(f, "Cancelled remaining qty {} at price level {}", qty, price)
+            }
+            Event::RemainderCancelled { price: None, qty } => {
+                write!(f, "Book exhausted, cancelled remaining qty {}", qty)
+            }
+            Event::Killed { price, qty } => write!(f, "Killed order with qty {}, at price {}", qty, price),
+            Event::OrderCancelled { kind, report } => write!(
+                f,
+                "Successfully cancelled {} order {}, {} of {} had filled!",
+                kind,
+                report.order_id,
+                report.previously_filled_qty,
+                report.previously_filled_qty.saturating_add(report.cancelled_qty),
+            ),
+            Event::LinkedCancelled { order_id, cause_order_id, group: Some(group) } => {
+                write!(f, "Cancelled order {} in group {} after {} executed", order_id, group, cause_order_id)
+            }
+            Event::LinkedCancelled { order_id, cause_order_id, group: None } => {
+                write!(f, "Cancelled order {} linked to {}", order_id, cause_order_id)
+            }
+            Event::MinFillCancelled { order_id, remaining_qty, min_qty } => write!(
+                f,
+                "Cancelled order {} with {} left below its min fill qty {}",
+                order_id, remaining_qty, min_qty
+            ),
+            Event::SelfTradePrevented { resting_order_id, resting_cancelled_qty, incoming_cancelled_qty } => write!(
+                f,
+                "Prevented self trade with order {}, cancelled {} resting and {} incoming",
+                resting_order_id, resting_cancelled_qty, incoming_cancelled_qty
+            ),
+            Event::Amended { order_id, old_qty, new_qty, kept_priority: true, .. } => {
+                write!(f, "Amended order {} down from {} to {} in place", order_id, old_qty, new_qty)
+            }
+            Event::Amended { order_id, old_price, old_qty, new_price, new_qty, kept_priority: false } => write!(
+                f,
+                "Amended order {} from {} at {} to {} at {}",
+                order_id, old_qty, old_price, new_qty, new_price
+            ),
+            Event::Replaced { order_id, new_order_id } => write!(f, "Replacing order {} with {}", order_id, new_order_id),
+            Event::StopTriggered { order_id, kind, source, trigger_price } => {
+                write!(f, "Triggered {:?} order {} off {:?} at {:?}", kind, order_id, source, trigger_price)
+            }
+            Event::TrailingStopTriggered { order_id, stop_price } => {
+                write!(f, "Triggered trailing stop order {} at stop price {:?}", order_id, stop_price)
+            }
+            Event::ReduceOnlyResized { order_id, old_qty, new_qty } => {
+                write!(f, "Resized reduce-only order {} from {} to {}", order_id, old_qty, new_qty)
+            }
+            Event::BracketExits { order_id, qty, grown: true } => write!(f, "Growing exits of bracket {} by {}", order_id, qty),
+            Event::BracketExits { order_id, qty, grown: false } => {
+                write!(f, "Spawning exits of bracket {} for {}", order_id, qty)
+            }
+            Event::MassCancelled { count } => write!(f, "Cancelled {} resting orders", count),
+            Event::AuctionStarted { closing: false } => f.write_str("Starting auction"),
+            Event::AuctionStarted { closing: true } => f.write_str("Starting closing auction"),
+            Event::OnCloseStaged { order_id, qty, limit_price } => {
+                write!(f, "Staged on-close order {} with qty {} and limit {:?}", order_id, qty, limit_price)
+            }
+            Event::Uncrossed { price, qty } => write!(f, "Uncrossed at {:?} for {}", price, qty),
+            Event::BlockTradeReported { trade_id, price, qty } => {
+                write!(f, "Reported block trade {} of {} at {}", trade_id, qty, price)
+            }
+            Event::TwapSlice { order_id, slice, num_slices, qty } => {
+                write!(f, "TWAP {} sending slice {} of {} for {}", order_id, slice, num_slices, qty)
+            }
+        }
+    }
+}
+
+// Called with the sequence number the book was at and the event
+type EventCallback = dyn FnMut(u64, &Event);
+
+pub(crate) struct EventListener(Box<EventCallback>);
+
+impl fmt::Debug for EventListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EventListener")
+    }
+}
+
+impl OrderBook {
+    // Calls `callback` with every event from now on, in the order they happen, along
+    // with the sequence number of the last change to the book when it happened
+    pub fn on_event(&mut self, callback: impl FnMut(u64, &Event) + 'static) {
+        self.event_listeners.push(EventListener(Box::new(callback)));
+    }
+
+    pub(crate) fn emit(&mut self, event: Event) {
+        if self.event_batch > 0 {
+            if !self.event_listeners.is_empty() {
+                self.pending_events.push((self.last_seq, event));
+            }
+            return;
+        }
+        for listener in &mut self.event_listeners {
+            (listener.0)(self.last_seq, &event);
+        }
+    }
+
+    // Runs `op` holding its events back, then hands them all to the observers in the
+    // order they happened, each with the seq it had at the time
+    pub(crate) fn event_batch<R>(&mut self, op: impl FnOnce(&mut OrderBook) -> R) -> R {
+        self.event_batch += 1;
+        let result = op(self);
+        self.event_batch -= 1;
+        if self.event_batch == 0 {
+            for (seq, event) in std::mem::take(&mut self.pending_events) {
+                for listener in &mut self.event_listeners {
+                    (listener.0)(seq, &event);
+                }
+            }
+        }
+        result
+    }
+}
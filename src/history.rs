@@ -0,0 +1,198 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::owners::unindex_owner;
+use crate::{AccountId, AvgPrice, FillResult, Order, OrderBook, OrderId, OrderOptions, OrderStatus, Price, Side};
+
+// Terminal orders get_order remembers unless the book is configured otherwise
+pub(crate) const DEFAULT_ORDER_HISTORY_LEN: usize = 10_000;
+
+pub(crate) fn avg_fill_price(filled_notional: u128, filled_qty: u64) -> Option<f64> {
+    AvgPrice::new(filled_notional, filled_qty).map(|avg| avg.to_f64())
+}
+
+// What the book knows about a limit order, live or recently done
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderInfo {
+    pub order_id: OrderId,
+    pub owner: Option<AccountId>,
+    pub tag: Option<u64>,
+    // Id the owner submitted the order under, if it gave one
+    pub client_order_id: Option<String>,
+    pub side: Side,
+    pub price: Price,
+    pub original_qty: u64,
+    pub filled_qty: u64,
+    // None until something filled
+    pub avg_fill_price: Option<f64>,
+    // Qty still live in the book, 0 once the order is done
+    pub remaining_qty: u64,
+    // Orders ahead of it at its price, None once it is done
+    pub queue_position: Option<usize>,
+    // When the book accepted it, or got it if it never rested
+    pub entered_at: u64,
+    // Created or PartiallyFilled while resting. Done orders are Filled, Cancelled
+    // (self-trade prevented ones too), Expired, Killed or Rejected.
+    pub status: OrderStatus,
+}
+
+impl OrderInfo {
+    pub(crate) fn resting(o: &Order, side: Side, price: Price, queue_position: Option<usize>) -> OrderInfo {
+        let status = if o.filled_qty == 0 { OrderStatus::Created } else { OrderStatus::PartiallyFilled };
+        OrderInfo {
+            order_id: o.order_id,
+            owner: o.owner,
+            tag: o.tag,
+            client_order_id: None,
+            side,
+            price,
+            original_qty: o.original_qty,
+            filled_qty: o.filled_qty,
+            avg_fill_price: avg_fill_price(o.filled_notional, o.filled_qty),
+            remaining_qty: o.qty + o.reserve_qty,
+            queue_position,
+            entered_at: o.entered_at,
+            status,
+        }
+    }
+
+    pub(crate) fn done(o: &Order, side: Side, price: Price, status: OrderStatus) -> OrderInfo {
+        OrderInfo { remaining_qty: 0, status, ..OrderInfo::resting(o, side, price, None) }
+    }
+}
+
+// Most recently done orders, the oldest is forgotten once there are more than `capacity`.
+// Client order ids are held from the time their order is accepted until it is forgotten,
+// so a late duplicate of a done order is still caught.
+#[derive(Debug, Clone)]
+pub(crate) struct OrderHistory {
+    capacity: usize,
+    // in the order they were done
+    order_ids: VecDeque<OrderId>,
+    orders: HashMap<OrderId, OrderInfo>,
+    // (owner, client order id) -> order id
+    client_ids: HashMap<(AccountId, String), OrderId>,
+    // order id -> owner and client order id
+    client_id_of: HashMap<OrderId, (AccountId, String)>,
+}
+
+impl OrderHistory {
+    pub fn new(capacity: usize) -> OrderHistory {
+        OrderHistory {
+            capacity,
+            order_ids: VecDeque::new(),
+            orders: HashMap::new(),
+            client_ids: HashMap::new(),
+            client_id_of: HashMap::new(),
+        }
+    }
+
+    // Claims `client_order_id` for `owner`, false if it already holds it
+    pub fn claim_client_id(&mut self, owner: AccountId, client_order_id: &str, order_id: OrderId) -> bool {
+        let key = (owner, client_order_id.to_string());
+        if self.client_ids.contains_key(&key) {
+            return false;
+        }
+        self.client_ids.insert(key.clone(), order_id);
+        self.client_id_of.insert(order_id, key);
+        true
+    }
+
+    pub fn order_id_for_client_id(&self, owner: AccountId, client_order_id: &str) -> Option<OrderId> {
+        self.client_ids.get(&(owner, client_order_id.to_string())).copied()
+    }
+
+    pub fn client_order_id(&self, order_id: OrderId) -> Option<&str> {
+        self.client_id_of.get(&order_id).map(|(_, client_order_id)| client_order_id.as_str())
+    }
+
+    fn forget_client_id(&mut self, order_id: OrderId) {
+        if let Some(key) = self.client_id_of.remove(&order_id) {
+            self.client_ids.remove(&key);
+        }
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.trim();
+    }
+
+    pub fn record(&mut self, mut info: OrderInfo) {
+        if self.capacity == 0 {
+            self.forget_client_id(info.order_id);
+            return;
+        }
+        let order_id = info.order_id;
+        info.client_order_id = self.client_order_id(order_id).map(str::to_string);
+        if self.orders.insert(order_id, info).is_none() {
+            self.order_ids.push_back(order_id);
+        }
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.order_ids.len() > self.capacity {
+            if let Some(order_id) = self.order_ids.pop_front() {
+                self.orders.remove(&order_id);
+                self.forget_client_id(order_id);
+            }
+        }
+    }
+
+    pub fn get(&self, order_id: OrderId) -> Option<&OrderInfo> {
+        self.orders.get(&order_id)
+    }
+}
+
+impl OrderBook {
+    // Side, price, quantities and status of a limit order resting in the book, or of
+    // one that left it recently. None for ids the book never saw or has forgotten.
+    pub fn get_order(&self, order_id: OrderId) -> Option<OrderInfo> {
+        if let Some(&(side, level, pos)) = self.order_loc.get(&order_id) {
+            let book = match side {
+                Side::Ask => &self.ask_book,
+                Side::Bid => &self.bid_book,
+            };
+            let price = book.price_of_level(level)?;
+            let level = book.level(level)?;
+            let queue_position = level.rank(pos)?;
+            let info = OrderInfo::resting(&level[pos], side, self.price(price), Some(queue_position));
+            let client_order_id = self.order_history.client_order_id(order_id).map(str::to_string);
+            return Some(OrderInfo { client_order_id, ..info });
+        }
+        self.order_history.get(order_id).cloned()
+    }
+
+    // Remembers a limit order that is done without ever resting
+    pub(crate) fn record_unrested(
+        &mut self,
+        order_id: OrderId,
+        s: Side,
+        price: u64,
+        options: &OrderOptions,
+        fill_result: &FillResult,
+    ) {
+        let filled_qty = options.filled_qty + fill_result.total_filled_qty();
+        let filled_notional = options.filled_notional + fill_result.total_notional();
+        let status = match fill_result.status {
+            OrderStatus::PartiallyFilled => OrderStatus::Cancelled,
+            status => status,
+        };
+        unindex_owner(&mut self.owner_orders, options.owner, order_id);
+        self.order_history.record(OrderInfo {
+            order_id,
+            owner: options.owner,
+            tag: options.tag,
+            client_order_id: None,
+            side: s,
+            price: self.price(price),
+            original_qty: options.original_qty,
+            filled_qty,
+            avg_fill_price: avg_fill_price(filled_notional, filled_qty),
+            remaining_qty: 0,
+            queue_position: None,
+            entered_at: self.clock.now(),
+            status,
+        });
+    }
+}
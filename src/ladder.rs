@@ -0,0 +1,96 @@
+use crate::{OrderBook, OrderId, OrderOptions, Price, Side, TickRounding, TimeInForce};
+
+// How a ladder splits its total qty across its orders
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScaleDistribution {
+    Flat,
+    // Linearly more qty the closer an order is to `price_from`
+    WeightedTowardsFrom,
+    // Linearly more qty the closer an order is to `price_to`
+    WeightedTowardsTo,
+}
+
+impl OrderBook {
+    // Places `num_orders` limit orders evenly spaced from `price_from` to `price_to`
+    // (both included, rounded down to the book's ticks) and returns their ids in price order.
+    // Quantities always add up to `total_qty`, what integer division leaves over goes
+    // one lot at a time to the orders closest to `price_from`. Orders that would get
+    // no qty are not placed. The ladder can be cancelled at once with `cancel_orders`.
+    pub fn create_scaled_orders(
+        &mut self,
+        s: Side,
+        price_from: impl Into<Price>,
+        price_to: impl Into<Price>,
+        total_qty: u64,
+        num_orders: u64,
+        distribution: ScaleDistribution,
+    ) -> Vec<OrderId> {
+        if num_orders == 0 {
+            return Vec::new();
+        }
+        let price_from = self.ticks_toward(price_from.into(), TickRounding::Down);
+        let price_to = self.ticks_toward(price_to.into(), TickRounding::Down);
+
+        let weights: Vec<u64> = (0..num_orders)
+            .map(|i| match distribution {
+                ScaleDistribution::Flat => 1,
+                ScaleDistribution::WeightedTowardsFrom => num_orders - i,
+                ScaleDistribution::WeightedTowardsTo => i + 1,
+            })
+            .collect();
+        let total_weight: u64 = weights.iter().sum();
+        let mut qtys: Vec<u64> = weights
+            .iter()
+            .map(|w| (total_qty as u128 * *w as u128 / total_weight as u128) as u64)
+            .collect();
+        let leftover = total_qty - qtys.iter().sum::<u64>();
+        for q in qtys.iter_mut().take(leftover as usize) {
+            *q += 1;
+        }
+
+        self.bbo_batch(|book| {
+            let mut order_ids = Vec::new();
+            for (i, qty) in qtys.into_iter().enumerate() {
+                if qty == 0 {
+                    continue;
+                }
+                let price = ladder_price(price_from, price_to, i as u64, num_orders);
+                let order_id = book.new_order_id();
+                book.execute_limit_order(
+                    order_id,
+                    s,
+                    price,
+                    qty,
+                    TimeInForce::GoodTillCancel,
+                    OrderOptions::default(),
+                );
+                book.process_triggers();
+                order_ids.push(order_id);
+            }
+            order_ids
+        })
+    }
+
+    // Cancels every order in `order_ids` that is still live, returns how many were
+    pub fn cancel_orders(&mut self, order_ids: &[OrderId]) -> usize {
+        self.bbo_batch(|book| {
+            order_ids
+                .iter()
+                .filter(|id| book.cancel_order(**id).is_ok())
+                .count()
+        })
+    }
+}
+
+// Price of the `i`th of `n` evenly spaced orders between `from` and `to`
+fn ladder_price(from: u64, to: u64, i: u64, n: u64) -> u64 {
+    if n == 1 {
+        return from;
+    }
+    if to >= from {
+        from + (to - from) * i / (n - 1)
+    } else {
+        from - (from - to) * i / (n - 1)
+    }
+}
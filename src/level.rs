@@ -0,0 +1,162 @@
+use std::collections::VecDeque;
+use std::ops::{Index, IndexMut};
+
+use crate::Order;
+
+// Refers to one use of a level slot. Once the level is freed and its slot reused for
+// another price, handles to it no longer find anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LevelHandle {
+    pub idx: usize,
+    generation: u32,
+}
+
+// Orders resting at one price in time priority. An order keeps the position it was
+// pushed at for as long as it rests, so it is found and taken out in O(1) from that
+// position alone. Taking one out of the middle leaves a gap behind it, gaps at either
+// end are dropped straight away and the others once `compact` is called.
+// One slab per side with each level's orders linked through it was tried in place
+// of this and benchmarked slower: matching walks a level front to back, and linked
+// through a shared slab its orders are no longer next to each other in memory.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PriceLevel {
+    slots: VecDeque<Option<Order>>,
+    // Position of the first slot
+    head: u64,
+    // Orders resting, gaps not counted
+    len: usize,
+    // Times the slot holding the level was freed
+    generation: u32,
+}
+
+impl PriceLevel {
+    // Handle to the level in slot `idx`
+    pub fn handle(&self, idx: usize) -> LevelHandle {
+        LevelHandle { idx, generation: self.generation }
+    }
+
+    pub fn is_handled_by(&self, handle: LevelHandle) -> bool {
+        self.generation == handle.generation
+    }
+
+    // Frees the slot, which invalidates every handle to it
+    pub fn retire(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Position of the order at the front of the queue, or the next one pushed if it is empty
+    pub fn start(&self) -> u64 {
+        self.head
+    }
+
+    // Position the next order pushed gets
+    pub fn end(&self) -> u64 {
+        self.head + self.slots.len() as u64
+    }
+
+    // Adds an order to the back of the queue and returns its position
+    pub fn push_back(&mut self, order: Order) -> u64 {
+        self.slots.push_back(Some(order));
+        self.len += 1;
+        self.end() - 1
+    }
+
+    pub fn get(&self, pos: u64) -> Option<&Order> {
+        let i = usize::try_from(pos.checked_sub(self.head)?).ok()?;
+        self.slots.get(i)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, pos: u64) -> Option<&mut Order> {
+        let i = usize::try_from(pos.checked_sub(self.head)?).ok()?;
+        self.slots.get_mut(i)?.as_mut()
+    }
+
+    // Takes out the order at `pos`, the positions of the others stay the same
+    pub fn remove(&mut self, pos: u64) -> Option<Order> {
+        let i = usize::try_from(pos.checked_sub(self.head)?).ok()?;
+        let order = self.slots.get_mut(i)?.take()?;
+        self.len -= 1;
+        while self.slots.front().is_some_and(Option::is_none) {
+            self.slots.pop_front();
+            self.head += 1;
+        }
+        while self.slots.back().is_some_and(Option::is_none) {
+            self.slots.pop_back();
+        }
+        Some(order)
+    }
+
+    // Orders in queue order
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Order> + Clone {
+        self.slots.iter().flatten()
+    }
+
+    // Orders in queue order along with their positions
+    pub fn positions(&self) -> impl Iterator<Item = (u64, &Order)> {
+        let head = self.head;
+        self.slots.iter().enumerate().filter_map(move |(i, o)| Some((head + i as u64, o.as_ref()?)))
+    }
+
+    // Orders ahead of the one at `pos` in the queue, None if no order is there.
+    // Walks the queue up to it.
+    pub fn rank(&self, pos: u64) -> Option<usize> {
+        self.get(pos)?;
+        Some(self.positions().take_while(|(p, _)| *p < pos).count())
+    }
+
+    // Takes every order out in queue order. The level keeps the room they took up,
+    // so a price reusing the slot later grows into it without allocating.
+    pub fn drain(&mut self) -> impl Iterator<Item = Order> + '_ {
+        self.head = self.end();
+        self.len = 0;
+        self.slots.drain(..).flatten()
+    }
+
+    // Slots left empty by orders taken out of the middle of the queue
+    pub fn gaps(&self) -> usize {
+        self.slots.len() - self.len
+    }
+
+    // True once gaps outnumber the orders left, which is when closing them up
+    // costs no more than the removals that opened them
+    pub fn is_sparse(&self) -> bool {
+        self.gaps() > self.len
+    }
+
+    // Closes up every gap, which moves the orders behind one. `moved` is told the
+    // new position of every order.
+    pub fn compact(&mut self, mut moved: impl FnMut(&Order, u64)) {
+        self.slots.retain(Option::is_some);
+        self.head = 0;
+        for (pos, o) in self.slots.iter().flatten().enumerate() {
+            moved(o, pos as u64);
+        }
+    }
+}
+
+impl Index<u64> for PriceLevel {
+    type Output = Order;
+
+    fn index(&self, pos: u64) -> &Order {
+        self.get(pos).expect("No order rests at this position")
+    }
+}
+
+impl IndexMut<u64> for PriceLevel {
+    fn index_mut(&mut self, pos: u64) -> &mut Order {
+        self.get_mut(pos).expect("No order rests at this position")
+    }
+}
+
+impl IntoIterator for PriceLevel {
+    type Item = Order;
+    type IntoIter = std::iter::Flatten<std::collections::vec_deque::IntoIter<Option<Order>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.slots.into_iter().flatten()
+    }
+}
@@ -0,0 +1,2576 @@
+use std::collections::{VecDeque, HashMap};
+use std::fmt;
+use uuid::Uuid;
+
+mod amend;
+mod auction;
+mod batch;
+mod bbo_change;
+mod bracket;
+mod candles;
+mod checksum;
+mod client_ids;
+mod clock;
+mod depth;
+mod error;
+mod estimate;
+mod events;
+mod history;
+mod ladder;
+mod level;
+mod mass_cancel;
+mod midpoint;
+mod oco;
+mod order_map;
+mod owners;
+mod price;
+mod price_map;
+mod reduce_only;
+mod render;
+mod snapshot;
+mod stats;
+mod stops;
+mod tape;
+mod trade_stats;
+mod twap;
+mod validate;
+
+pub use amend::{AmendResult, ReplaceResult};
+pub use auction::{AuctionFill, AuctionReport, IndicativeAuction, TradingPhase};
+pub use batch::{BookOp, OpResult};
+pub use bbo_change::BboChange;
+use bbo_change::BboListener;
+pub use candles::{Candle, CandleBuilder, CandleGaps};
+pub use checksum::ChecksumScale;
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use depth::{Bbo, DepthBucket, DepthLevel, DepthSnapshot, LevelView, QueuePosition};
+pub use error::Error;
+pub use estimate::FillEstimate;
+use events::EventListener;
+pub use events::{Event, OrderKind};
+use history::{OrderHistory, DEFAULT_ORDER_HISTORY_LEN};
+pub use history::OrderInfo;
+pub use ladder::ScaleDistribution;
+use level::{LevelHandle, PriceLevel};
+pub use mass_cancel::CancelledOrder;
+use midpoint::MidpointBook;
+pub use midpoint::MidpointCross;
+use auction::CloseOrder;
+use bracket::Bracket;
+use oco::OcoLink;
+use order_map::{OrderIdMap, DEFAULT_EXPECTED_ORDERS};
+pub use order_map::OrderIdHasher;
+pub use oco::{GroupId, LinkedCancel, OcoTrigger, OrderSpec};
+use owners::{index_owner, unindex_owner, OwnerIndex};
+pub use price::{AvgPrice, Price, PriceError, PriceScale, TickRounding};
+use price_map::PriceMap;
+pub use price_map::PriceLadder;
+use reduce_only::{reducible_qty, update_position};
+pub use reduce_only::ReduceOnlyResize;
+pub use snapshot::{BookSnapshot, SnapshotOrder};
+use stats::Counters;
+pub use stats::{BookStats, SideStats};
+use stops::StopBook;
+use stops::TriggerPrices;
+pub use stops::{StopOrder, TrailingOffset, TrailingStopOrder, TriggerKind, TriggerSource, TriggeredStop};
+use tape::Counterparty;
+pub use tape::{PriceBand, Trade};
+pub use trade_stats::{StatsWindow, TradeStats};
+pub use twap::{TwapChild, TwapOrder, TwapStatus};
+pub use validate::ConsistencyError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    Ask, 
+    Bid
+}
+
+impl Side {
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Ask => Side::Bid,
+            Side::Bid => Side::Ask,
+        }
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Side::Ask => "Ask",
+            Side::Bid => "Bid",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OrderStatus {
+    Created, 
+    Filled, 
+    PartiallyFilled, 
+    // Nothing could be filled and nothing was rested, or cancelled while resting
+    Cancelled, 
+    // Good-till-date order that was still resting when its expiry passed
+    Expired, 
+    // Fill-or-kill order that could not be filled in full, the book is untouched
+    Killed, 
+    // Order refused before it reached the book
+    Rejected(RejectReason), 
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RejectReason {
+    // Post-only order would have taken liquidity
+    PostOnlyWouldCross, 
+    // Good-till-date order whose expiry is not in the future
+    ExpiryInPast, 
+    // Market-to-limit order with nothing on the opposite side to take its price from
+    NoOppositeLiquidity, 
+    // Market order while the book is in an auction
+    AuctionInProgress, 
+    // Market-on-close or limit-on-close order outside the closing auction
+    CloseOnlyOutsideClosingAuction, 
+    // Reported price too far from the last trade price
+    OutsidePriceBand, 
+    // Reduce-only order while its account's position is flat or on the same side
+    ReduceOnlyWouldIncrease, 
+    ZeroQty, 
+    // Limit price of 0 in a book whose order limits do not allow it
+    ZeroPrice, 
+    // Qty above the book's maximum order qty
+    QtyAboveMax, 
+    // Client order id the owner already used for an order the book still remembers
+    DuplicateClientOrderId, 
+    // All-or-none order that crosses the book but can't fill completely
+    AllOrNoneWouldCross, 
+    // Reported price with no last trade, reference price or midpoint to check it against
+    NoBandReference, 
+    // Price between two of the book's ticks
+    OffTick, 
+    // Price of more ticks of the book than it can hold
+    PriceOutOfRange, 
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderStatus::Created => write!(f, "Created"),
+            OrderStatus::Filled => write!(f, "Filled"),
+            OrderStatus::PartiallyFilled => write!(f, "Partially filled"),
+            OrderStatus::Cancelled => write!(f, "Cancelled"),
+            OrderStatus::Expired => write!(f, "Expired"),
+            OrderStatus::Killed => write!(f, "Killed"),
+            OrderStatus::Rejected(reason) => write!(f, "Rejected: {}", reason),
+        }
+    }
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RejectReason::PostOnlyWouldCross => "Post-only order would cross!",
+            RejectReason::ExpiryInPast => "Order expiry is not in the future!",
+            RejectReason::NoOppositeLiquidity => "Nothing on the opposite side to price the order!",
+            RejectReason::AuctionInProgress => "Market orders are not accepted during an auction!",
+            RejectReason::CloseOnlyOutsideClosingAuction => "On-close orders are only accepted in the closing auction!",
+            RejectReason::OutsidePriceBand => "Price is outside the price band!",
+            RejectReason::ReduceOnlyWouldIncrease => "Reduce-only order would increase the position!",
+            RejectReason::ZeroQty => "Order qty is 0!",
+            RejectReason::ZeroPrice => "Order price is 0!",
+            RejectReason::QtyAboveMax => "Order qty is above the maximum!",
+            RejectReason::DuplicateClientOrderId => "Client order id is already in use!",
+            RejectReason::AllOrNoneWouldCross => "All-or-none order would cross without filling!",
+            RejectReason::NoBandReference => "No reference price for the price band!",
+            RejectReason::OffTick => "Price is not a multiple of the tick size!",
+            RejectReason::PriceOutOfRange => "Price is out of range!",
+        })
+    }
+}
+
+// What cancel_order took out of the book
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CancelReport {
+    pub order_id: OrderId, 
+    // None for orders submitted without one, and midpoint, stop and on-close orders
+    pub owner: Option<AccountId>, 
+    // Tag the order was submitted with
+    pub tag: Option<u64>, 
+    pub side: Side, 
+    // Limit price of the order, where it rested for orders in the book. None for
+    // midpoint, plain stop, trailing stop and market-on-close orders.
+    pub price: Option<Price>, 
+    // Qty removed, iceberg reserve included
+    pub cancelled_qty: u64, 
+    // Qty the order had already filled
+    pub previously_filled_qty: u64, 
+    // When the order was accepted into the book, None for orders that wait outside it
+    pub entered_at: Option<u64>, 
+    pub cancelled_at: u64, 
+}
+
+
+// What a post-only order does when it would cross the opposite best price
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PostOnlyMode {
+    Reject, 
+    // Rest one tick away from the opposite best price instead
+    Reprice, 
+}
+
+// What happens to a min-fill order once a partial fill leaves less than its minimum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MinFillResidual {
+    // The residual keeps resting and can be hit for any qty
+    DropConstraint, 
+    // The residual is cancelled
+    Cancel, 
+}
+
+// A resting order is never hit for less than `qty` in a single match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MinFill {
+    pub qty: u64, 
+    pub residual: MinFillResidual, 
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccountId(pub u64);
+
+// Id the book gives an order, from 1 up in the order they come in. Unique within
+// one book only, ids a client picks go in client order ids instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderId(pub u64);
+
+impl fmt::Display for OrderId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// What happens instead of a trade when an incoming order would match a resting
+// order of the same account
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SelfTradePrevention {
+    // Cancel the whole resting order and keep matching
+    CancelResting, 
+    // Cancel what is left of the incoming order
+    #[default]
+    CancelIncoming, 
+    // Take the overlapping qty off both orders without trading it
+    DecrementBoth, 
+}
+
+// Qty cancelled by self-trade prevention, no trade took place for any of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelfTradePrevented {
+    pub resting_order_id: OrderId, 
+    pub resting_cancelled_qty: u64, 
+    pub incoming_cancelled_qty: u64, 
+}
+
+// How an incoming order's qty is shared among the resting orders at one price
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MatchingAlgorithm {
+    // Price-time priority, the front of the queue fills first
+    #[default]
+    Fifo, 
+    // In proportion to displayed size
+    ProRata, 
+}
+
+// When a level closes up the gaps left by orders taken out of the middle of its queue
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LevelCompaction {
+    // Once gaps outnumber the orders left, by the cancel or fill that tips it over
+    #[default]
+    Automatic, 
+    // Only on OrderBook::compact, so no cancel ever moves the orders behind it. Gaps
+    // build up in the meantime, emptied levels are still freed straight away.
+    Manual, 
+}
+
+// Settings that apply to every order in a book unless the order overrides them
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BookConfig {
+    pub self_trade_prevention: SelfTradePrevention, 
+    pub matching: MatchingAlgorithm, 
+    // Reported block trades must be inside this band, None accepts any price
+    pub price_band: Option<PriceBand>, 
+    // Price the band is around until the book's first trade, e.g. the previous
+    // close. None uses the midpoint then.
+    pub band_reference_price: Option<Price>, 
+    pub block_trades_trigger_stops: bool, 
+    // What stop and MIT orders trigger off unless the order says otherwise
+    pub stop_trigger_source: TriggerSource, 
+    // Done orders get_order still knows about, None keeps the last 10000
+    pub order_history_len: Option<usize>, 
+    // Trades the tape keeps, None keeps the last 10000
+    pub trade_tape_len: Option<usize>, 
+    pub order_limits: OrderLimits, 
+    // Decimal value of the book's integer prices
+    pub price_scale: PriceScale, 
+    // Orders the book expects to rest at once, so it can size for them up front.
+    // None sizes for 5000, more than that still fit at the cost of growing.
+    pub expected_orders: Option<usize>, 
+    pub compaction: LevelCompaction, 
+    // Keeps the levels priced on the ladder in an array instead of a tree, None for
+    // books trading over too wide or sparse a range of prices for one
+    pub price_ladder: Option<PriceLadder>, 
+}
+
+// Bounds on the price and qty of every order entering the book, orders outside them
+// are rejected. Orders for no qty always are.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderLimits {
+    // Lets limit orders rest and trade at a price of 0
+    pub allow_zero_price: bool, 
+    // Largest qty of a single order, None for no maximum
+    pub max_qty: Option<u64>, 
+}
+
+// How long the unfilled remainder of a limit order stays live
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeInForce {
+    // Rest the remainder in the book until it fills or is cancelled
+    GoodTillCancel, 
+    // Match what is possible now and discard the remainder
+    ImmediateOrCancel, 
+    // Fill the whole order now or reject it without matching anything
+    FillOrKill, 
+    // Rest the remainder until the clock reaches this timestamp (nanoseconds)
+    GoodTillDate(u64), 
+}
+
+// One trade of an incoming order against a resting one
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fill {
+    pub maker_order_id: OrderId, 
+    // None for makers submitted without one and midpoint orders
+    pub maker_owner: Option<AccountId>, 
+    // Tag the maker was submitted with
+    pub maker_tag: Option<u64>, 
+    pub price: Price, 
+    pub qty: u64, 
+    // When the trade happened, by the book's clock
+    pub timestamp: u64, 
+    // Sequence number of the trade, the book change that recorded it
+    pub seq: u64, 
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FillResult {
+    // Resting orders traded with, in the order they were hit
+    pub filled_orders: Vec<Fill>, 
+    pub remaining_qty: u64, 
+    pub status: OrderStatus, 
+    // Price the remainder rests at, if any was added to the book
+    pub resting_price: Option<Price>, 
+    // Id the remainder rests under, the one the order was submitted with, to cancel
+    // or amend it by
+    pub resting_order_id: Option<OrderId>, 
+    // Unfilled qty cancelled instead of traded against the same account,
+    // included in remaining_qty but never rested
+    pub self_trades_prevented: Vec<SelfTradePrevented>, 
+}
+
+impl FillResult {
+    // An accepted order nothing of has filled yet
+    fn unfilled(order_qty: u64) -> FillResult {
+        FillResult {
+            filled_orders: Vec::new(), 
+            remaining_qty: order_qty, 
+            status: OrderStatus::Created, 
+            resting_price: None, 
+            resting_order_id: None, 
+            self_trades_prevented: Vec::new(), 
+        }
+    }
+
+    fn rejected(order_qty: u64, reason: RejectReason) -> FillResult {
+        FillResult { status: OrderStatus::Rejected(reason), ..FillResult::unfilled(order_qty) }
+    }
+
+    pub fn total_filled_qty(&self) -> u64 {
+        self.filled_orders.iter().map(|f| f.qty).sum()
+    }
+
+    // Price in ticks times qty summed over the fills
+    pub fn total_notional(&self) -> u128 {
+        self.filled_orders.iter().map(|f| f.qty as u128 * f.price.ticks() as u128).sum()
+    }
+
+    // Incoming qty cancelled by self-trade prevention
+    pub fn self_trade_cancelled_qty(&self) -> u64 {
+        self.self_trades_prevented.iter().map(|p| p.incoming_cancelled_qty).sum()
+    }
+
+    // In ticks, None if nothing filled
+    pub fn avg_price_exact(&self) -> Option<AvgPrice> {
+        AvgPrice::new(self.total_notional(), self.total_filled_qty())
+    }
+
+    // Decimal value of avg_price_exact as the nearest f64
+    pub fn avg_fill_price(&self) -> Option<f64> {
+        let tick = self.filled_orders.first()?.price.scale().price(1).to_f64();
+        self.avg_price_exact().map(|avg| avg.to_f64() * tick)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Order {
+    pub order_id: OrderId, 
+    // Displayed qty, for icebergs only the current slice
+    pub qty: u64, 
+    // Iceberg qty not yet displayed
+    pub reserve_qty: u64, 
+    // Size of each iceberg slice
+    pub display_qty: u64, 
+    // Matches like any other order but never shows in displayed qty
+    pub hidden: bool, 
+    // Good-till-date orders are dead from this timestamp on
+    pub expires_at: Option<u64>, 
+    // Only matched by an incoming order large enough to fill it completely
+    pub all_or_none: bool, 
+    // Repriced by the book to follow the best price on its side
+    pub pegged: bool, 
+    pub min_fill: Option<MinFill>, 
+    // One-cancels-all group, the first member to trade cancels the others
+    pub oca_group: Option<GroupId>, 
+    pub owner: Option<AccountId>, 
+    // Opaque value of the submitter's, echoed back on everything reported about the order
+    pub tag: Option<u64>, 
+    // Never trades more than it takes to bring its owner's position to flat
+    pub reduce_only: bool, 
+    // Qty the order was entered with, filled qty included
+    pub original_qty: u64, 
+    pub filled_qty: u64, 
+    // Price times qty summed over its fills
+    pub filled_notional: u128, 
+    // When the book accepted the order, by its clock. Re-entering it at a new price
+    // after an amend counts as a new acceptance.
+    pub entered_at: u64, 
+}
+
+impl Order {
+    // An order expiring at t is no longer live at t
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|t| t <= now)
+    }
+
+    // A min-fill order can always be taken out completely, even below its minimum
+    fn below_min_fill(&self, incoming_order_qty: u64) -> bool {
+        self.min_fill.is_some_and(|m| incoming_order_qty < m.qty.min(self.qty + self.reserve_qty))
+    }
+}
+
+// Registry entry for an order that follows the best price on its side
+#[derive(Debug, Clone)]
+struct PeggedOrder {
+    order_id: OrderId, 
+    side: Side, 
+    // Ticks towards the spread from the best unpegged price, negative sits behind it
+    offset: i64, 
+    price: u64, 
+}
+
+// Good-till-date order removed from the book once its expiry passed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExpiredOrder {
+    pub order_id: OrderId, 
+    pub side: Side, 
+    pub price: Price, 
+    // Remaining qty including any iceberg reserve
+    pub qty: u64, 
+    pub tag: Option<u64>, 
+}
+
+// Attributes of the part of an order that rests in the book
+#[derive(Debug, Default)]
+struct OrderOptions {
+    // Iceberg slice size, None displays the whole qty
+    display_qty: Option<u64>, 
+    hidden: bool, 
+    expires_at: Option<u64>, 
+    all_or_none: bool, 
+    pegged: bool, 
+    min_fill: Option<MinFill>, 
+    oca_group: Option<GroupId>, 
+    owner: Option<AccountId>, 
+    tag: Option<u64>, 
+    reduce_only: bool, 
+    // Qty the order was entered with and what of it filled before it rests
+    original_qty: u64, 
+    filled_qty: u64, 
+    filled_notional: u128, 
+    // Overrides the book's policy while this order is the incoming one
+    self_trade_prevention: Option<SelfTradePrevention>, 
+    // Id the owner gave the order, unique among its orders the book remembers
+    client_order_id: Option<String>, 
+    // Accepted before, as a stop that triggered or an order that was amended, so it
+    // is not received and counted again as a new order
+    reentered: bool, 
+}
+
+// Side, level and queue position of a resting order
+type OrderLoc = (Side, LevelHandle, u64);
+
+#[derive(Debug, Clone)]
+struct HalfBook {
+    s: Side, 
+    price_map: PriceMap, 
+    price_levels: Vec<PriceLevel>, 
+    // Price each slot of price_levels was last used for, so a level's price is found
+    // without walking price_map
+    level_prices: Vec<u64>, 
+    // Slots of price_levels no price points at any more, reused before new ones are added
+    free_levels: Vec<usize>, 
+    compaction: LevelCompaction, 
+}
+
+impl HalfBook {
+    pub fn new(s: Side) -> HalfBook {
+        HalfBook {
+            s, 
+            price_map: PriceMap::new(None), 
+            price_levels: Vec::with_capacity(5000), // Pre-alloc
+            level_prices: Vec::with_capacity(5000), 
+            free_levels: Vec::new(), 
+            compaction: LevelCompaction::default(), 
+        }
+    }
+
+    // Best displayed price, highest for bids and lowest for asks.
+    // Levels holding only hidden orders are not part of the BBO.
+    pub fn best_price(&self) -> Option<u64> {
+        self.best_price_where(|o| !o.hidden)
+    }
+
+    // Best displayed price ignoring pegged orders, which is what pegs track
+    pub fn best_unpegged_price(&self) -> Option<u64> {
+        self.best_price_where(|o| !o.hidden && !o.pegged)
+    }
+
+    // Best price at which any order rests, hidden or not
+    pub fn best_resting_price(&self) -> Option<u64> {
+        self.best_price_where(|_| true)
+    }
+
+    // Best displayed price no better than `from`, which skips the levels in front of
+    // it. The whole side for None.
+    fn best_price_from(&self, from: Option<u64>) -> Option<u64> {
+        let Some(from) = from else {
+            return self.best_price();
+        };
+        let displayed = |(p, u): (&u64, &usize)| self.price_levels[*u].iter().any(|o| !o.hidden).then_some(*p);
+        match self.s {
+            Side::Bid => self.price_map.range(..=from).rev().find_map(displayed), 
+            Side::Ask => self.price_map.range(from..).find_map(displayed), 
+        }
+    }
+
+    fn best_price_where(&self, counts: impl Fn(&Order) -> bool) -> Option<u64> {
+        let non_empty = |(p, u): (&u64, &usize)| self.price_levels[*u].iter().any(&counts).then_some(*p);
+        match self.s {
+            Side::Bid => self.price_map.iter().rev().find_map(non_empty),
+            Side::Ask => self.price_map.iter().find_map(non_empty),
+        }
+    }
+
+    // Qty an incoming order for `qty` limited at `limit` would fill at `now`
+    #[allow(clippy::too_many_arguments)]
+    pub fn executable_qty(
+        &self, 
+        limit: Option<u64>, 
+        qty: u64, 
+        now: u64, 
+        fired_groups: &HashMap<GroupId, OrderId>, 
+        taker: Option<(AccountId, SelfTradePrevention)>, 
+        algorithm: MatchingAlgorithm, 
+        positions: &HashMap<AccountId, i64>, 
+    ) -> u64 {
+        self.level_fills(limit, qty, now, fired_groups, taker, algorithm, positions)
+            .iter()
+            .map(|(q, _)| q)
+            .sum()
+    }
+
+    // (qty, price) an incoming order for `qty` limited at `limit` would fill at each level
+    // at `now`. Runs the real level matching on copies so it can never disagree with it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn level_fills(
+        &self, 
+        limit: Option<u64>, 
+        qty: u64, 
+        now: u64, 
+        fired_groups: &HashMap<GroupId, OrderId>, 
+        taker: Option<(AccountId, SelfTradePrevention)>, 
+        algorithm: MatchingAlgorithm, 
+        positions: &HashMap<AccountId, i64>, 
+    ) -> Vec<(u64, u64)> {
+        let eligible: Box<dyn Iterator<Item = (&u64, &usize)>> = match (self.s, limit) {
+            (Side::Ask, Some(limit)) => Box::new(self.price_map.range(..=limit)),
+            (Side::Ask, None) => Box::new(self.price_map.iter()),
+            (Side::Bid, Some(limit)) => Box::new(self.price_map.range(limit..).rev()),
+            (Side::Bid, None) => Box::new(self.price_map.iter().rev()),
+        };
+
+        let mut fills = Vec::new();
+        let mut remaining_qty = qty;
+        let mut fired_groups = fired_groups.clone();
+        let mut positions = positions.clone();
+        for (price, u) in eligible {
+            if remaining_qty == 0 {
+                break;
+            }
+            let mut level = self.price_levels[*u].clone();
+            let mut ctx = MatchContext {
+                now, 
+                order_loc: &mut OrderIdMap::default(), 
+                expired: &mut Vec::new(), 
+                maker_fills: &mut Vec::new(), 
+                fired_groups: &mut fired_groups, 
+                taker, 
+                prevented: &mut Vec::new(), 
+                algorithm, 
+                auction_price: None, 
+                positions: &mut positions, 
+                history: &mut OrderHistory::new(0), 
+                owner_orders: &mut OwnerIndex::new(), 
+                events: &mut Vec::new(), 
+                last_seq: &mut 0, 
+                scale: PriceScale::default(), 
+            };
+            let before = remaining_qty;
+            match_at_price_level(&mut level, (self.s, *price), &mut remaining_qty, &mut ctx);
+            if remaining_qty < before {
+                fills.push((before - remaining_qty, *price));
+            }
+        }
+        fills
+    }
+
+    // The level a handle refers to, None once it was freed
+    fn level(&self, handle: LevelHandle) -> Option<&PriceLevel> {
+        self.price_levels.get(handle.idx).filter(|l| l.is_handled_by(handle))
+    }
+
+    fn level_mut(&mut self, handle: LevelHandle) -> Option<&mut PriceLevel> {
+        self.price_levels.get_mut(handle.idx).filter(|l| l.is_handled_by(handle))
+    }
+
+    // None once the level was freed
+    fn price_of_level(&self, handle: LevelHandle) -> Option<u64> {
+        self.level(handle)?;
+        self.level_prices.get(handle.idx).copied()
+    }
+
+    // Appends to the back of the level at `price`, creating it if needed,
+    // and returns a handle to the level and the order's position in it
+    fn push_order(&mut self, price: u64, order: Order) -> (LevelHandle, u64) {
+        let idx = if let Some(price_level_idx) = self.price_map.get(&price) {
+            *price_level_idx
+        } else if let Some(free_loc) = self.free_levels.pop() {
+            self.price_map.insert(price, free_loc);
+            self.level_prices[free_loc] = price;
+            free_loc
+        } else {
+            self.price_map.insert(price, self.price_levels.len());
+            self.price_levels.push(PriceLevel::default());
+            self.level_prices.push(price);
+            self.price_levels.len() - 1
+        };
+        let level = &mut self.price_levels[idx];
+        let pos = level.push_back(order);
+        (level.handle(idx), pos)
+    }
+
+    // Drops the levels at `prices` that no order rests in any more and frees their slots,
+    // and closes up the gaps in those left mostly gaps, moving order_loc along.
+    // Handles to the freed levels stop finding them, no order_loc entry should hold one.
+    fn reclaim_levels(&mut self, prices: impl IntoIterator<Item = u64>, order_loc: &mut OrderIdMap<OrderLoc>) {
+        for price in prices {
+            let Some(&u) = self.price_map.get(&price) else {
+                continue;
+            };
+            let level = &mut self.price_levels[u];
+            if level.is_empty() {
+                level.retire();
+                self.price_map.remove(&price);
+                self.free_levels.push(u);
+            } else if self.compaction == LevelCompaction::Automatic && level.is_sparse() {
+                compact_level(level, order_loc);
+            }
+        }
+    }
+
+    // Closes up the gaps in every level, returning how many there were
+    fn compact_levels(&mut self, order_loc: &mut OrderIdMap<OrderLoc>) -> usize {
+        let mut gaps = 0;
+        for &u in self.price_map.values() {
+            let level = &mut self.price_levels[u];
+            if level.gaps() > 0 {
+                gaps += level.gaps();
+                compact_level(level, order_loc);
+            }
+        }
+        gaps
+    }
+
+    // Slots allocated for levels, free ones included
+    pub fn allocated_levels(&self) -> usize {
+        self.price_levels.len()
+    }
+
+    // Order slots the levels in use hold, gaps not closed up yet included
+    pub fn order_slots(&self) -> usize {
+        self.price_map.values().map(|&u| (self.price_levels[u].end() - self.price_levels[u].start()) as usize).sum()
+    }
+
+    // Removes every order expired at `now`, in price then queue order
+    fn remove_expired(&mut self, now: u64, order_loc: &mut OrderIdMap<OrderLoc>) -> Vec<(Order, u64)> {
+        let mut expired = Vec::new();
+        for (price, u) in self.price_map.iter() {
+            let level = &mut self.price_levels[*u];
+            let positions: Vec<u64> = level.positions().filter(|(_, o)| o.is_expired(now)).map(|(pos, _)| pos).collect();
+            for pos in positions {
+                expired.extend(level.remove(pos).map(|o| (o, *price)));
+            }
+        }
+        let prices: Vec<u64> = expired.iter().map(|(_, price)| *price).collect();
+        self.reclaim_levels(prices, order_loc);
+        expired
+    }
+
+    // Displayed qty at a price, hidden orders are excluded. 0 for a price nothing rests at.
+    pub fn get_total_qty(&self, price: u64) -> u64 {
+        let Some(price_level_idx) = self.price_map.get(&price) else {
+            return 0;
+        };
+        saturating_sum(self.price_levels[*price_level_idx]
+            .iter()
+            .filter(|s| !s.hidden)
+            .map(|s| s.qty))
+    }
+}
+
+fn expired_order(o: Order, side: Side, price: Price) -> ExpiredOrder {
+    ExpiredOrder { qty: o.qty + o.reserve_qty, tag: o.tag, order_id: o.order_id, side, price }
+}
+
+// Resting order that traded, reported so linked orders can react
+#[derive(Debug, Clone)]
+struct MakerFill {
+    order_id: OrderId, 
+    owner: Option<AccountId>, 
+    tag: Option<u64>, 
+    qty: u64, 
+    price: u64, 
+    // Nothing of the order is left in the book
+    done: bool, 
+}
+
+// Book state that level matching updates besides the level itself
+struct MatchContext<'a> {
+    now: u64, 
+    order_loc: &'a mut OrderIdMap<OrderLoc>, 
+    expired: &'a mut Vec<ExpiredOrder>, 
+    maker_fills: &'a mut Vec<MakerFill>, 
+    // group -> id of the member that traded first, until the group is cancelled
+    fired_groups: &'a mut HashMap<GroupId, OrderId>, 
+    // Account of the incoming order and the self-trade policy that applies to it
+    taker: Option<(AccountId, SelfTradePrevention)>, 
+    prevented: &'a mut Vec<SelfTradePrevented>, 
+    algorithm: MatchingAlgorithm, 
+    // Price every fill executes at during an uncross, None trades at the level's price
+    auction_price: Option<u64>, 
+    // Signed net position per account, moved by every fill
+    positions: &'a mut HashMap<AccountId, i64>, 
+    // Orders that left the book
+    history: &'a mut OrderHistory, 
+    owner_orders: &'a mut OwnerIndex, 
+    // Reported once matching the level is done
+    events: &'a mut Vec<Event>, 
+    // The book's, for the orders matching cancels or expires
+    last_seq: &'a mut u64, 
+    // The book's, for the prices it reports
+    scale: PriceScale, 
+}
+
+impl MatchContext<'_> {
+    // Forgets an order that left the book, remembering how it ended
+    fn finish_order(&mut self, o: &Order, (side, price): (Side, u64), status: OrderStatus) {
+        self.order_loc.remove(&o.order_id);
+        unindex_owner(self.owner_orders, o.owner, o.order_id);
+        self.history.record(OrderInfo::done(o, side, self.scale.price(price), status));
+        // Filled ones went with the trade that filled them
+        if status != OrderStatus::Filled {
+            advance_seq(self.last_seq);
+        }
+    }
+
+    // Sends an order to the back of its level, where order_loc has to find it from now on
+    fn requeue(&mut self, price_level: &mut PriceLevel, o: Order) {
+        let order_id = o.order_id;
+        let pos = price_level.push_back(o);
+        if let Some(loc) = self.order_loc.get_mut(&order_id) {
+            loc.2 = pos;
+        }
+    }
+}
+
+// Closes up the gaps in `level`, moving order_loc along with the orders
+fn compact_level(level: &mut PriceLevel, order_loc: &mut OrderIdMap<OrderLoc>) {
+    level.compact(|o, pos| {
+        if let Some(loc) = order_loc.get_mut(&o.order_id) {
+            loc.2 = pos;
+        }
+    });
+}
+
+// Displayed orders trade before hidden ones at the same price, each in time priority.
+// Orders found expired at `now` are purged on the way instead of matched, and
+// all-or-none orders larger than what is left of the incoming order are skipped,
+// as are min-fill orders whose minimum is more than what is left and orders
+// whose one-cancels-all group another member already traded for.
+// Reduce-only orders fill at most what their owner's position still allows.
+// In pro-rata mode the plain displayed orders are allocated first, see allocate_pro_rata.
+fn match_at_price_level(
+    price_level: &mut PriceLevel, 
+    (side, price): (Side, u64), 
+    incoming_order_qty: &mut u64, 
+    ctx: &mut MatchContext, 
+) -> u64 {
+    let mut done_qty = 0;
+    if let MatchingAlgorithm::ProRata = ctx.algorithm {
+        done_qty += allocate_pro_rata(price_level, (side, price), incoming_order_qty, ctx);
+    }
+    for hidden_pass in [false, true] {
+        let mut i = price_level.start();
+        while *incoming_order_qty > 0 && i < price_level.end() {
+            let Some(o) = price_level.get_mut(i) else {
+                i += 1;
+                continue;
+            };
+            if o.is_expired(ctx.now) {
+                let o = price_level.remove(i).unwrap();
+                ctx.finish_order(&o, (side, price), OrderStatus::Expired);
+                ctx.expired.push(expired_order(o, side, ctx.scale.price(price)));
+                continue;
+            }
+            // Orders behind a skipped all-or-none or min-fill order can still trade
+            if o.hidden != hidden_pass
+                || (o.all_or_none && o.qty > *incoming_order_qty)
+                || o.below_min_fill(*incoming_order_qty)
+                || o.oca_group.as_ref().is_some_and(|g| {
+                    ctx.fired_groups.get(g).is_some_and(|first| *first != o.order_id)
+                })
+            {
+                i += 1;
+                continue;
+            }
+            if let Some((_, policy)) = ctx.taker.filter(|(owner, _)| o.owner == Some(*owner)) {
+                if prevent_self_trade(price_level, (side, price), i, policy, incoming_order_qty, ctx) {
+                    i += 1;
+                }
+                continue;
+            }
+            if let Some(g) = &o.oca_group {
+                ctx.fired_groups.entry(g.clone()).or_insert_with(|| o.order_id);
+            }
+
+            let mut fill_qty = o.qty.min(*incoming_order_qty);
+            if let Some(owner) = o.owner.filter(|_| o.reduce_only) {
+                fill_qty = fill_qty.min(reducible_qty(ctx.positions, owner, side));
+                if fill_qty == 0 {
+                    i += 1;
+                    continue;
+                }
+            }
+            done_qty += fill_qty;
+            *incoming_order_qty -= fill_qty;
+            if fill_resting_order(price_level, (side, price), i, fill_qty, ctx) {
+                i += 1;
+            }
+        }
+    }
+
+    done_qty
+}
+
+// Fills `qty` of the resting order at `i`, at most its displayed qty.
+// True if the order is still at `i` afterwards.
+fn fill_resting_order(
+    price_level: &mut PriceLevel, 
+    (side, price): (Side, u64), 
+    i: u64, 
+    qty: u64, 
+    ctx: &mut MatchContext, 
+) -> bool {
+    let fill_price = ctx.auction_price.unwrap_or(price);
+    let o = &mut price_level[i];
+    debug_assert!(qty <= o.qty, "Filling {} of order {} showing only {}", qty, o.order_id, o.qty);
+    o.filled_qty += qty;
+    o.filled_notional += qty as u128 * fill_price as u128;
+    if let Some(owner) = o.owner {
+        update_position(ctx.positions, owner, side, qty);
+    }
+    if qty < o.qty {
+        o.qty -= qty;
+        let cancelled = apply_min_fill_residual(o, ctx.events);
+        ctx.maker_fills.push(MakerFill { order_id: o.order_id, owner: o.owner, tag: o.tag, qty, price: fill_price, done: cancelled });
+        if cancelled {
+            let o = price_level.remove(i).unwrap();
+            ctx.finish_order(&o, (side, price), OrderStatus::Cancelled);
+        }
+        return !cancelled;
+    }
+
+    let mut o = price_level.remove(i).unwrap();
+    if o.reserve_qty == 0 {
+        ctx.finish_order(&o, (side, price), OrderStatus::Filled);
+        ctx.maker_fills.push(MakerFill { order_id: o.order_id, owner: o.owner, tag: o.tag, qty, price: fill_price, done: true });
+        return false;
+    }
+    // Iceberg refresh goes to the back of the queue, losing time priority
+    o.qty = o.display_qty.min(o.reserve_qty);
+    o.reserve_qty -= o.qty;
+    if apply_min_fill_residual(&mut o, ctx.events) {
+        ctx.finish_order(&o, (side, price), OrderStatus::Cancelled);
+        ctx.maker_fills.push(MakerFill { order_id: o.order_id, owner: o.owner, tag: o.tag, qty, price: fill_price, done: true });
+    } else {
+        ctx.maker_fills.push(MakerFill { order_id: o.order_id, owner: o.owner, tag: o.tag, qty, price: fill_price, done: false });
+        ctx.requeue(price_level, o);
+    }
+    false
+}
+
+// Splits the incoming qty across the plain displayed orders of the level in proportion
+// to their displayed qty, rounding down. Each order then gets at least one lot while
+// any is left, and what rounding leaves over goes to the earliest orders first.
+// Orders with constraints, and hidden ones, are left to the time priority passes.
+fn allocate_pro_rata(
+    price_level: &mut PriceLevel, 
+    level: (Side, u64), 
+    incoming_order_qty: &mut u64, 
+    ctx: &mut MatchContext, 
+) -> u64 {
+    let eligible: Vec<u64> = price_level
+        .positions()
+        .filter(|(_, o)| {
+            o.qty > 0
+                && !o.is_expired(ctx.now)
+                && !o.hidden
+                && !o.all_or_none
+                && o.min_fill.is_none()
+                && o.oca_group.is_none()
+                && !o.reduce_only
+                && ctx.taker.is_none_or(|(owner, _)| o.owner != Some(owner))
+        })
+        .map(|(i, _)| i)
+        .collect();
+    // Wide enough that the shares below always add up to at most `qty`
+    let total: u128 = eligible.iter().map(|&i| price_level[i].qty as u128).sum();
+    if total == 0 {
+        return 0;
+    }
+
+    let qty = (*incoming_order_qty).min(total.min(u64::MAX as u128) as u64);
+    let mut allocations: Vec<u64> = eligible
+        .iter()
+        .map(|&i| (qty as u128 * price_level[i].qty as u128 / total) as u64)
+        .collect();
+    let mut leftover = qty - allocations.iter().sum::<u64>();
+    for a in allocations.iter_mut().filter(|a| **a == 0) {
+        if leftover == 0 {
+            break;
+        }
+        *a = 1;
+        leftover -= 1;
+    }
+    for (k, &i) in eligible.iter().enumerate() {
+        let extra = (price_level[i].qty - allocations[k]).min(leftover);
+        allocations[k] += extra;
+        leftover -= extra;
+    }
+
+    // Back to front, refreshed icebergs join the queue behind every order still to fill
+    for (k, &i) in eligible.iter().enumerate().rev() {
+        if allocations[k] > 0 {
+            fill_resting_order(price_level, level, i, allocations[k], ctx);
+        }
+    }
+    *incoming_order_qty -= qty;
+    qty
+}
+
+// Applies `policy` to the resting order at `i` and the incoming order of the same
+// account, true if the resting order is still at `i` afterwards
+fn prevent_self_trade(
+    price_level: &mut PriceLevel, 
+    (side, price): (Side, u64), 
+    i: u64, 
+    policy: SelfTradePrevention, 
+    incoming_order_qty: &mut u64, 
+    ctx: &mut MatchContext, 
+) -> bool {
+    let o = &mut price_level[i];
+    let (resting_cancelled_qty, incoming_cancelled_qty) = match policy {
+        SelfTradePrevention::CancelResting => (o.qty + o.reserve_qty, 0), 
+        SelfTradePrevention::CancelIncoming => (0, *incoming_order_qty), 
+        SelfTradePrevention::DecrementBoth => {
+            let overlap = o.qty.min(*incoming_order_qty);
+            (overlap, overlap)
+        }
+    };
+    ctx.events.push(Event::SelfTradePrevented {
+        resting_order_id: o.order_id, 
+        resting_cancelled_qty, 
+        incoming_cancelled_qty, 
+    });
+    ctx.prevented.push(SelfTradePrevented {
+        resting_order_id: o.order_id, 
+        resting_cancelled_qty, 
+        incoming_cancelled_qty, 
+    });
+    *incoming_order_qty -= incoming_cancelled_qty;
+
+    if resting_cancelled_qty == 0 {
+        return true;
+    }
+    if resting_cancelled_qty < o.qty {
+        o.qty -= resting_cancelled_qty;
+        return true;
+    }
+    let mut o = price_level.remove(i).unwrap();
+    o.reserve_qty -= resting_cancelled_qty - o.qty;
+    if o.reserve_qty > 0 {
+        o.qty = o.display_qty.min(o.reserve_qty);
+        o.reserve_qty -= o.qty;
+        ctx.requeue(price_level, o);
+    } else {
+        ctx.finish_order(&o, (side, price), OrderStatus::Cancelled);
+    }
+    false
+}
+
+// Handles a min-fill order left below its minimum, true if its residual is cancelled
+fn apply_min_fill_residual(o: &mut Order, events: &mut Vec<Event>) -> bool {
+    let Some(min_fill) = o.min_fill else {
+        return false;
+    };
+    if o.qty + o.reserve_qty >= min_fill.qty {
+        return false;
+    }
+    match min_fill.residual {
+        MinFillResidual::DropConstraint => {
+            o.min_fill = None;
+            false
+        }
+        MinFillResidual::Cancel => {
+            events.push(Event::MinFillCancelled {
+                order_id: o.order_id, 
+                remaining_qty: o.qty + o.reserve_qty, 
+                min_qty: min_fill.qty, 
+            });
+            true
+        }
+    }
+}
+
+// Panics once u64::MAX is used up rather than wrap and break the ordering,
+// which at a billion changes a second takes over 500 years
+fn advance_seq(last_seq: &mut u64) -> u64 {
+    *last_seq = last_seq.checked_add(1).expect("Sequence numbers exhausted");
+    *last_seq
+}
+
+fn new_group_id() -> GroupId {
+    Uuid::new_v4().to_string()
+}
+
+// Sums quantities, saturating at u64::MAX where plain addition would wrap in release builds
+pub(crate) fn saturating_sum(qtys: impl IntoIterator<Item = u64>) -> u64 {
+    qtys.into_iter().fold(0, u64::saturating_add)
+}
+
+// Status of an order whose unfilled remainder is discarded instead of rested
+fn cancelled_remainder_status(remaining_qty: u64, order_qty: u64) -> OrderStatus {
+    if remaining_qty == order_qty {
+        OrderStatus::Cancelled
+    } else {
+        OrderStatus::PartiallyFilled
+    }
+}
+
+#[derive(Debug)]
+pub struct OrderBook {
+    symbol: String, 
+    // None while the side has no displayed order
+    best_ask_price: Option<u64>, 
+    best_bid_price: Option<u64>, 
+    ask_book: HalfBook,
+    bid_book: HalfBook,
+     // for fast cancel, id -> (side, price_level)
+    order_loc: OrderIdMap<OrderLoc>,
+    stop_book: StopBook, 
+    last_trade_price: Option<u64>, 
+    last_trade_qty: u64, 
+    // Range and volume of every trade recorded on the tape, block trades included
+    session_high: Option<u64>, 
+    session_low: Option<u64>, 
+    session_volume: u64, 
+    // Last trade price stops and MITs trigger off, block trades may not move it
+    stop_trigger_price: Option<u64>, 
+    // Results of stops and MITs triggered since the last take_triggered_stops
+    triggered_stops: Vec<TriggeredStop>, 
+    clock: Box<dyn Clock>, 
+    // Expired orders purged during matching, reported by the next expire_orders
+    expired_orders: Vec<ExpiredOrder>, 
+    // in arrival order, entries for orders no longer resting are dropped lazily
+    pegged_orders: Vec<PeggedOrder>, 
+    midpoint_book: MidpointBook, 
+    // Midpoint orders crossed against each other since the last take_midpoint_crosses
+    midpoint_crosses: Vec<MidpointCross>, 
+    // Resting orders that traded since linked orders last looked at them
+    maker_fills: Vec<MakerFill>, 
+    // one entry per leg, pointing at its sibling
+    oco_links: HashMap<OrderId, OcoLink>, 
+    // Orders cancelled through a link since the last take_linked_cancels
+    linked_cancels: Vec<LinkedCancel>, 
+    // keyed by entry order id, dropped once the entry is done or cancelled
+    brackets: HashMap<OrderId, Bracket>, 
+    // bracket take-profit id -> stop-loss id, so the stop shrinks as the take-profit fills
+    take_profit_stops: HashMap<OrderId, OrderId>, 
+    // One-cancels-all members, in submission order
+    oca_groups: HashMap<GroupId, Vec<OrderId>>, 
+    oca_group_of: HashMap<OrderId, GroupId>, 
+    // Groups a member traded for whose other members are not cancelled yet
+    fired_oca_groups: HashMap<GroupId, OrderId>, 
+    config: BookConfig, 
+    phase: TradingPhase, 
+    // On-close orders staged for the closing uncross, in arrival order
+    close_orders: Vec<CloseOrder>, 
+    closing_price: Option<u64>, 
+    closing_volume: u64, 
+    // Most recent trades, oldest first
+    trade_tape: VecDeque<Trade>, 
+    // Fed every trade recorded on the tape once enabled
+    candles: Option<CandleBuilder>, 
+    trade_stats: Option<TradeStats>, 
+    // Sequence number of the latest accepted order, trade, cancel or amend
+    last_seq: u64, 
+    // Id of the latest trade recorded, block trades included
+    last_trade_id: u64, 
+    // Id given to the latest order
+    last_order_id: u64, 
+    // Signed net position per account, from book and block trades
+    positions: HashMap<AccountId, i64>, 
+    // in arrival order, entries for orders no longer resting are dropped lazily
+    reduce_only_orders: Vec<OrderId>, 
+    // Reduce-only orders resized since the last take_reduce_only_resizes
+    reduce_only_resizes: Vec<ReduceOnlyResize>, 
+    order_history: OrderHistory, 
+    counters: Counters, 
+    owner_orders: OwnerIndex, 
+    bbo_listeners: Vec<BboListener>, 
+    event_listeners: Vec<EventListener>, 
+    // Top of the book the listeners last heard about
+    last_bbo: Bbo, 
+    bbo_seq: u64, 
+    // Operations in progress that notify BBO listeners once they are done
+    bbo_batch: u32, 
+    // Batches in progress holding back events, and what they held back with its seq
+    event_batch: u32, 
+    pending_events: Vec<(u64, Event)>, 
+    // Kept between calls to match_incoming, so matching allocates nothing once they
+    // have grown to fit
+    visited_levels: Vec<u64>, 
+    match_events: Vec<Event>, 
+    // Fills of an add_limit_order_with order, until they are handed to its callback
+    fill_buffer: Vec<Fill>, 
+}
+
+impl OrderBook {
+    pub fn new(symbol: String) -> OrderBook {
+        OrderBook::with_clock(symbol, SystemClock)
+    }
+
+    pub fn with_clock(symbol: String, clock: impl Clock + 'static) -> OrderBook {
+        OrderBook {
+            symbol, 
+            best_ask_price: None, 
+            best_bid_price: None, 
+            bid_book: HalfBook::new(Side::Bid), 
+            ask_book: HalfBook::new(Side::Ask), 
+            order_loc: OrderIdMap::with_capacity_and_hasher(DEFAULT_EXPECTED_ORDERS, Default::default()), 
+            stop_book: StopBook::new(), 
+            last_trade_price: None, 
+            last_trade_qty: 0, 
+            session_high: None, 
+            session_low: None, 
+            session_volume: 0, 
+            stop_trigger_price: None, 
+            triggered_stops: Vec::new(), 
+            clock: Box::new(clock), 
+            expired_orders: Vec::new(), 
+            pegged_orders: Vec::new(), 
+            midpoint_book: MidpointBook::new(), 
+            midpoint_crosses: Vec::new(), 
+            maker_fills: Vec::new(), 
+            oco_links: HashMap::new(), 
+            linked_cancels: Vec::new(), 
+            brackets: HashMap::new(), 
+            take_profit_stops: HashMap::new(), 
+            oca_groups: HashMap::new(), 
+            oca_group_of: HashMap::new(), 
+            fired_oca_groups: HashMap::new(), 
+            config: BookConfig::default(), 
+            phase: TradingPhase::Continuous, 
+            close_orders: Vec::new(), 
+            closing_price: None, 
+            closing_volume: 0, 
+            trade_tape: VecDeque::new(), 
+            candles: None, 
+            trade_stats: None, 
+            last_seq: 0, 
+            last_trade_id: 0, 
+            last_order_id: 0, 
+            positions: HashMap::new(), 
+            reduce_only_orders: Vec::new(), 
+            reduce_only_resizes: Vec::new(), 
+            order_history: OrderHistory::new(DEFAULT_ORDER_HISTORY_LEN), 
+            counters: Counters::default(), 
+            owner_orders: OwnerIndex::new(), 
+            bbo_listeners: Vec::new(), 
+            event_listeners: Vec::new(), 
+            last_bbo: Bbo::default(), 
+            bbo_seq: 0, 
+            bbo_batch: 0, 
+            event_batch: 0, 
+            pending_events: Vec::new(), 
+            visited_levels: Vec::new(), 
+            match_events: Vec::new(), 
+            fill_buffer: Vec::new(), 
+        }
+    }
+
+    pub fn set_config(&mut self, config: BookConfig) {
+        self.order_history.set_capacity(config.order_history_len.unwrap_or(DEFAULT_ORDER_HISTORY_LEN));
+        let expected_orders = config.expected_orders.unwrap_or(DEFAULT_EXPECTED_ORDERS);
+        self.order_loc.reserve(expected_orders.saturating_sub(self.order_loc.len()));
+        self.bid_book.compaction = config.compaction;
+        self.ask_book.compaction = config.compaction;
+        if config.price_ladder != self.config.price_ladder {
+            self.bid_book.price_map.rebuild(config.price_ladder);
+            self.ask_book.price_map.rebuild(config.price_ladder);
+        }
+        self.config = config;
+        self.trim_trade_tape();
+    }
+
+    // Closes up the gaps orders taken out of the middle of their queues left behind,
+    // which is only ever needed with LevelCompaction::Manual. Returns how many gaps
+    // were closed. Queue order and every order id stay the same.
+    pub fn compact(&mut self) -> usize {
+        let gaps = self.bid_book.compact_levels(&mut self.order_loc);
+        gaps + self.ask_book.compact_levels(&mut self.order_loc)
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    // Sequence number of the latest change to the book, 0 before the first. Every accepted
+    // order, trade, cancel and amend takes the next one.
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    fn next_seq(&mut self) -> u64 {
+        advance_seq(&mut self.last_seq)
+    }
+
+    fn new_order_id(&mut self) -> OrderId {
+        self.last_order_id += 1;
+        OrderId(self.last_order_id)
+    }
+
+    fn accept_order(&mut self) {
+        self.counters.orders_accepted += 1;
+        self.next_seq();
+    }
+
+    // Cancelling one leg of a linked group also cancels the orders linked to it,
+    // cancelling a bracket entry stops it from spawning further exits. Stops the
+    // new BBO reaches are triggered.
+    pub fn cancel_order(&mut self, order_id: OrderId) -> Result<CancelReport, Error> {
+        let report = self.cancel_without_triggers(order_id)?;
+        self.process_triggers();
+        Ok(report)
+    }
+
+    // Cancels like cancel_order but leaves triggering to the caller, for a cancel
+    // that is one step of a larger operation
+    pub(crate) fn cancel_without_triggers(&mut self, order_id: OrderId) -> Result<CancelReport, Error> {
+        let (report, kind) = self.remove_order(order_id)?;
+        self.emit(Event::OrderCancelled { kind, report: report.clone() });
+        self.counters.orders_cancelled += 1;
+        self.cancel_linked(order_id);
+        self.leave_oca_group(order_id);
+        self.brackets.remove(&order_id);
+        Ok(report)
+    }
+
+    // What was cancelled and what sort of order it was
+    fn remove_order(&mut self, order_id: OrderId) -> Result<(CancelReport, OrderKind), Error> {
+        let now = self.clock.now();
+        let removed = if let Some((o, side, price)) = self.take_resting_order_at(order_id) {
+            self.finish_order(&o, side, price, OrderStatus::Cancelled);
+            self.pegged_orders.retain(|p| p.order_id != order_id);
+            self.reprice_pegs();
+            self.update_bbo();
+            let report = CancelReport {
+                cancelled_qty: o.qty + o.reserve_qty, 
+                previously_filled_qty: o.filled_qty, 
+                order_id: o.order_id, 
+                owner: o.owner, 
+                tag: o.tag, 
+                side, 
+                price: Some(self.price(price)), 
+                entered_at: Some(o.entered_at), 
+                cancelled_at: now, 
+            };
+            Ok((report, OrderKind::Limit))
+        } else if let Some(report) = self.midpoint_book.cancel(order_id, now) {
+            Ok((report, OrderKind::Midpoint))
+        } else if let Some(report) = self.stop_book.cancel(order_id, now, self.config.price_scale) {
+            Ok((report, OrderKind::Stop))
+        } else if let Some(i) = self.close_orders.iter().position(|o| o.order_id == order_id) {
+            let o = self.close_orders.remove(i);
+            let report = CancelReport {
+                order_id: o.order_id, 
+                owner: None, 
+                tag: None, 
+                side: o.side, 
+                price: o.limit_price.map(|p| self.price(p)), 
+                cancelled_qty: o.qty, 
+                previously_filled_qty: 0, 
+                entered_at: None, 
+                cancelled_at: now, 
+            };
+            Ok((report, OrderKind::OnClose))
+        } else {
+            Err(self.cancel_error(order_id))
+        };
+        if removed.is_ok() {
+            self.next_seq();
+        }
+        removed
+    }
+
+    // What became of an order there is nothing left of to cancel
+    fn cancel_error(&self, order_id: OrderId) -> Error {
+        let Some(info) = self.order_history.get(order_id) else {
+            return Error::UnknownOrder { order_id };
+        };
+        match info.status {
+            OrderStatus::Filled => Error::AlreadyFilled { order_id, filled_qty: info.filled_qty },
+            OrderStatus::Cancelled | OrderStatus::Killed | OrderStatus::Expired => {
+                Error::AlreadyCancelled { order_id, filled_qty: info.filled_qty }
+            }
+            OrderStatus::Rejected(_) => Error::WasRejected { order_id },
+            OrderStatus::Created | OrderStatus::PartiallyFilled => {
+                Error::UnknownOrder { order_id }
+            }
+        }
+    }
+
+    // Qty still resting for a live order in the book, including any iceberg reserve
+    pub fn resting_qty(&self, order_id: OrderId) -> Option<u64> {
+        self.resting_order(order_id).map(|o| o.qty + o.reserve_qty)
+    }
+
+    fn resting_order(&self, order_id: OrderId) -> Option<&Order> {
+        let &(side, level, pos) = self.order_loc.get(&order_id)?;
+        let book = match side {
+            Side::Ask => &self.ask_book, 
+            Side::Bid => &self.bid_book, 
+        };
+        book.level(level)?.get(pos)
+    }
+
+    fn resting_price(&self, order_id: OrderId) -> Option<u64> {
+        let &(side, level, _) = self.order_loc.get(&order_id)?;
+        match side {
+            Side::Ask => self.ask_book.price_of_level(level), 
+            Side::Bid => self.bid_book.price_of_level(level), 
+        }
+    }
+
+    fn resting_order_mut(&mut self, order_id: OrderId) -> Option<&mut Order> {
+        let &(side, level, pos) = self.order_loc.get(&order_id)?;
+        let book = match side {
+            Side::Ask => &mut self.ask_book, 
+            Side::Bid => &mut self.bid_book, 
+        };
+        book.level_mut(level)?.get_mut(pos)
+    }
+
+    // Forgets an order that left the book, remembering how it ended
+    fn finish_order(&mut self, o: &Order, side: Side, price: u64, status: OrderStatus) {
+        self.order_loc.remove(&o.order_id);
+        unindex_owner(&mut self.owner_orders, o.owner, o.order_id);
+        self.order_history.record(OrderInfo::done(o, side, self.price(price), status));
+    }
+
+    // Takes an order out of the book without cancelling anything linked to it,
+    // along with the side and price it rested at
+    fn take_resting_order_at(&mut self, order_id: OrderId) -> Option<(Order, Side, u64)> {
+        let (side, level, pos) = self.order_loc.remove(&order_id)?;
+        let book = match side {
+            Side::Ask => &mut self.ask_book, 
+            Side::Bid => &mut self.bid_book, 
+        };
+        let price = book.price_of_level(level)?;
+        let o = book.level_mut(level)?.remove(pos)?;
+        book.reclaim_levels([price], &mut self.order_loc);
+        unindex_owner(&mut self.owner_orders, o.owner, order_id);
+        Some((o, side, price))
+    }
+
+    // Cuts a resting order down to `new_qty` in place, reserve first, so it keeps its place in the queue
+    fn shrink_resting_order(&mut self, order_id: OrderId, new_qty: u64) {
+        let Some(o) = self.resting_order_mut(order_id) else {
+            return;
+        };
+        let excess = (o.qty + o.reserve_qty).saturating_sub(new_qty);
+        let from_reserve = excess.min(o.reserve_qty);
+        o.reserve_qty -= from_reserve;
+        o.qty -= excess - from_reserve;
+    }
+
+    // Rests an order without matching it, unless it is outside the book's order limits
+    // or off its ticks
+    pub fn create_new_limit_order(&mut self, s: Side, price: impl Into<Price>, qty: u64) -> Result<OrderId, Error> {
+        let price = self.order_ticks(price.into())?;
+        Ok(self.rest_new_order(s, price, qty)?)
+    }
+
+    fn rest_new_order(&mut self, s: Side, price: u64, qty: u64) -> Result<OrderId, RejectReason> {
+        self.check_order_limits(Some(price), qty)?;
+        let order_id = self.new_order_id();
+        self.accept_order();
+        self.create_resting_order(order_id, s, price, qty, &OrderOptions::default());
+        Ok(order_id)
+    }
+
+    // Checks an order against the book's order limits, `price` is None for market orders
+    fn check_order_limits(&self, price: Option<u64>, qty: u64) -> Result<(), RejectReason> {
+        let limits = self.config.order_limits;
+        if qty == 0 {
+            return Err(RejectReason::ZeroQty);
+        }
+        if price == Some(0) && !limits.allow_zero_price {
+            return Err(RejectReason::ZeroPrice);
+        }
+        if limits.max_qty.is_some_and(|max_qty| qty > max_qty) {
+            return Err(RejectReason::QtyAboveMax);
+        }
+        Ok(())
+    }
+
+    // Ticks of an incoming order's price, or the order rejected for a price the book
+    // can't rest it at, reported like any other rejection
+    fn limit_ticks(&mut self, kind: OrderKind, price: Price, qty: u64) -> Result<u64, FillResult> {
+        self.order_ticks(price).map_err(|reason| {
+            self.emit(Event::OrderRejected { kind, price: Some(price), qty, reason });
+            FillResult::rejected(qty, reason)
+        })
+    }
+
+    // Checks a limit order against the book's order limits and a good-till-date one
+    // against the clock
+    fn check_limit_order(&self, price: u64, qty: u64, tif: TimeInForce) -> Result<(), RejectReason> {
+        self.check_order_limits(Some(price), qty)?;
+        match tif {
+            TimeInForce::GoodTillDate(expires_at) if expires_at <= self.clock.now() => Err(RejectReason::ExpiryInPast),
+            _ => Ok(()),
+        }
+    }
+
+    fn create_resting_order(&mut self, order_id: OrderId, s: Side, price: u64, qty: u64, options: &OrderOptions) {
+        let book = match s {
+            Side::Ask => &mut self.ask_book, 
+            Side::Bid => &mut self.bid_book, 
+        };
+        let display_qty = options.display_qty.map_or(qty, |d| d.max(1).min(qty));
+        let order = Order {
+            order_id, 
+            qty: display_qty, 
+            reserve_qty: qty - display_qty, 
+            display_qty, 
+            hidden: options.hidden, 
+            expires_at: options.expires_at, 
+            all_or_none: options.all_or_none, 
+            pegged: options.pegged, 
+            min_fill: options.min_fill, 
+            oca_group: options.oca_group.clone(), 
+            owner: options.owner, 
+            tag: options.tag, 
+            reduce_only: options.reduce_only, 
+            original_qty: options.original_qty.max(options.filled_qty.saturating_add(qty)), 
+            filled_qty: options.filled_qty, 
+            filled_notional: options.filled_notional, 
+            entered_at: self.clock.now(), 
+        };
+
+        let (level, pos) = book.push_order(price, order);
+        index_owner(&mut self.owner_orders, options.owner, order_id);
+        self.order_loc.insert(order_id, (s, level, pos));
+        if !options.hidden {
+            self.improve_bbo(s, price);
+        }
+    }
+
+    // Catches the BBO up with orders that left the book. Nothing displayed rests in
+    // front of the cached best prices, every order resting there went through
+    // improve_bbo, so each side is searched from its cached price outward and that
+    // is usually the first level looked at.
+    fn update_bbo(&mut self) {
+        self.best_bid_price = self.bid_book.best_price_from(self.best_bid_price);
+        self.best_ask_price = self.ask_book.best_price_from(self.best_ask_price);
+    }
+
+    // A displayed order now rests at `price` on side `s`
+    fn improve_bbo(&mut self, s: Side, price: u64) {
+        let best = match s {
+            Side::Bid => &mut self.best_bid_price, 
+            Side::Ask => &mut self.best_ask_price, 
+        };
+        let improves = match (s, *best) {
+            (_, None) => true, 
+            (Side::Bid, Some(best)) => price > best, 
+            (Side::Ask, Some(best)) => price < best, 
+        };
+        if improves {
+            *best = Some(price);
+        }
+    }
+
+    // Walks the opposite book from the best price outward until the incoming
+    // order is exhausted or the next level is beyond `limit`.
+    // A `limit` of None means every level is eligible (market order).
+    fn match_incoming(
+        &mut self, 
+        s: Side, 
+        limit: Option<u64>, 
+        remaining_order_qty: &mut u64, 
+        fill_result: &mut FillResult, 
+        taker: Option<(AccountId, SelfTradePrevention)>, 
+        taker_side: Counterparty, 
+    ) {
+        let fills_before = self.maker_fills.len();
+        // Resting midpoint orders give price improvement over the lit book, so go first
+        if let Some(mid) = self.midpoint_for(s, limit) {
+            let fills = self.midpoint_book.match_side(s.opposite(), remaining_order_qty);
+            self.counters.trades += fills.len() as u64;
+            let matched_qty: u64 = fills.iter().map(|(_, q)| q).sum();
+            for (maker_order_id, qty) in fills {
+                let maker_side = Counterparty { order_id: Some(maker_order_id), ..Default::default() };
+                let trade = self.record_match(mid, qty, s, maker_side, taker_side.clone());
+                fill_result.filled_orders.push(Fill { maker_order_id, maker_owner: None, maker_tag: None, price: self.price(mid), qty, timestamp: trade.timestamp, seq: trade.seq });
+            }
+            if matched_qty != 0 {
+                self.emit(Event::Matched { price: self.price(mid), qty: matched_qty, midpoint: true });
+                self.stop_trigger_price = Some(mid);
+            }
+        }
+
+        let now = self.clock.now();
+        let mut visited = std::mem::take(&mut self.visited_levels);
+        let mut events = std::mem::take(&mut self.match_events);
+        match s {
+            Side::Bid => {
+                let askbook = &mut self.ask_book;
+                for (x, curr_level) in askbook.price_map.iter() {
+                    if *remaining_order_qty == 0 {
+                        break;
+                    }
+                    if limit.is_some_and(|price| price < *x) {
+                        break;
+                    }
+                    let mut ctx = MatchContext {
+                        now,
+                        order_loc: &mut self.order_loc,
+                        expired: &mut self.expired_orders,
+                        maker_fills: &mut self.maker_fills,
+                        fired_groups: &mut self.fired_oca_groups,
+                        taker,
+                        prevented: &mut fill_result.self_trades_prevented,
+                        algorithm: self.config.matching,
+                        auction_price: None,
+                        positions: &mut self.positions,
+                        history: &mut self.order_history,
+                        owner_orders: &mut self.owner_orders,
+                        events: &mut events,
+                        last_seq: &mut self.last_seq,
+                        scale: self.config.price_scale,
+                    };
+                    let matched_qty = match_at_price_level(
+                        &mut askbook.price_levels[*curr_level],
+                        (askbook.s, *x),
+                        remaining_order_qty,
+                        &mut ctx,
+                    );
+                    visited.push(*x);
+
+                    if matched_qty != 0 {
+                        events.push(Event::Matched { price: self.config.price_scale.price(*x), qty: matched_qty, midpoint: false });
+                        self.stop_trigger_price = Some(*x);
+                    }
+                }
+            }
+
+            Side::Ask => {
+                let bidbook = &mut self.bid_book;
+                for (x, curr_level) in bidbook.price_map.iter().rev() {
+                    if *remaining_order_qty == 0 {
+                        break;
+                    }
+                    if limit.is_some_and(|price| price > *x) {
+                        break;
+                    }
+                    let mut ctx = MatchContext {
+                        now,
+                        order_loc: &mut self.order_loc,
+                        expired: &mut self.expired_orders,
+                        maker_fills: &mut self.maker_fills,
+                        fired_groups: &mut self.fired_oca_groups,
+                        taker,
+                        prevented: &mut fill_result.self_trades_prevented,
+                        algorithm: self.config.matching,
+                        auction_price: None,
+                        positions: &mut self.positions,
+                        history: &mut self.order_history,
+                        owner_orders: &mut self.owner_orders,
+                        events: &mut events,
+                        last_seq: &mut self.last_seq,
+                        scale: self.config.price_scale,
+                    };
+                    let matched_qty = match_at_price_level(
+                        &mut bidbook.price_levels[*curr_level],
+                        (bidbook.s, *x),
+                        remaining_order_qty,
+                        &mut ctx,
+                    );
+                    visited.push(*x);
+
+                    if matched_qty != 0 {
+                        events.push(Event::Matched { price: self.config.price_scale.price(*x), qty: matched_qty, midpoint: false });
+                        self.stop_trigger_price = Some(*x);
+                    }
+                }
+            }
+        }
+
+        match s {
+            Side::Bid => self.ask_book.reclaim_levels(visited.drain(..), &mut self.order_loc),
+            Side::Ask => self.bid_book.reclaim_levels(visited.drain(..), &mut self.order_loc),
+        }
+        self.visited_levels = visited;
+        for event in events.drain(..) {
+            self.emit(event);
+        }
+        self.match_events = events;
+
+        self.counters.trades += (self.maker_fills.len() - fills_before) as u64;
+        for k in fills_before..self.maker_fills.len() {
+            let f = &self.maker_fills[k];
+            let (maker_order_id, price, qty, maker_owner, maker_tag) = (f.order_id, f.price, f.qty, f.owner, f.tag);
+            let maker_side = Counterparty { order_id: Some(maker_order_id), owner: maker_owner, tag: maker_tag };
+            let trade = self.record_match(price, qty, s, maker_side, taker_side.clone());
+            let fill = Fill { maker_order_id, maker_owner, maker_tag, price: self.price(price), qty, timestamp: trade.timestamp, seq: trade.seq };
+            fill_result.filled_orders.push(fill);
+        }
+        let filled_qty = fill_result.total_filled_qty();
+        self.counters.matched_qty = self.counters.matched_qty.saturating_add(filled_qty);
+        if let Some((owner, _)) = taker {
+            update_position(&mut self.positions, owner, s, filled_qty);
+        }
+    }
+
+    pub fn add_limit_order(&mut self, s: Side, price: impl Into<Price>, order_qty: u64) -> FillResult {
+        self.add_limit_order_with_tif(s, price, order_qty, TimeInForce::GoodTillCancel)
+    }
+
+    pub fn add_limit_order_with_tif(
+        &mut self, 
+        s: Side, 
+        price: impl Into<Price>, 
+        order_qty: u64, 
+        tif: TimeInForce, 
+    ) -> FillResult {
+        let mut fills = Vec::new();
+        let mut fill_result = self.add_limit_order_into(s, price, order_qty, tif, &mut fills);
+        fill_result.filled_orders = fills;
+        fill_result
+    }
+
+    // As add_limit_order_with_tif, but the fills go to `fills`, cleared first, and
+    // the result's filled_orders is left empty. A caller reusing the same Vec for
+    // every order has orders that rest, or fill against a single level, go through
+    // without allocating once the book's own buffers have grown to fit.
+    pub fn add_limit_order_into(
+        &mut self, 
+        s: Side, 
+        price: impl Into<Price>, 
+        order_qty: u64, 
+        tif: TimeInForce, 
+        fills: &mut Vec<Fill>, 
+    ) -> FillResult {
+        let order_id = self.new_order_id();
+        let fill_result = match self.limit_ticks(OrderKind::Limit, price.into(), order_qty) {
+            Ok(price) => self.execute_limit_order_into(order_id, s, price, order_qty, tif, OrderOptions::default(), fills),
+            Err(rejected) => {
+                fills.clear();
+                rejected
+            }
+        };
+        self.process_triggers();
+        fill_result
+    }
+
+    // As add_limit_order_into, handing `on_fill` each fill in the order they happened
+    // once the order is done, from a buffer the book keeps for it
+    pub fn add_limit_order_with(
+        &mut self, 
+        s: Side, 
+        price: impl Into<Price>, 
+        order_qty: u64, 
+        tif: TimeInForce, 
+        mut on_fill: impl FnMut(Fill), 
+    ) -> FillResult {
+        let mut fills = std::mem::take(&mut self.fill_buffer);
+        let fill_result = self.add_limit_order_into(s, price, order_qty, tif, &mut fills);
+        fills.drain(..).for_each(&mut on_fill);
+        self.fill_buffer = fills;
+        fill_result
+    }
+
+    fn execute_limit_order(
+        &mut self, 
+        order_id: OrderId, 
+        s: Side, 
+        price: u64, 
+        order_qty: u64, 
+        tif: TimeInForce, 
+        options: OrderOptions, 
+    ) -> FillResult {
+        self.run_limit_order(order_id, s, price, order_qty, tif, options, FillResult::unfilled(order_qty))
+    }
+
+    // Submits a limit order under a new id, rejecting it if its price is off the
+    // book's ticks, and then triggers what it moved
+    fn submit_limit_order(&mut self, s: Side, price: Price, order_qty: u64, tif: TimeInForce, options: OrderOptions) -> FillResult {
+        let order_id = self.new_order_id();
+        let fill_result = match self.limit_ticks(OrderKind::Limit, price, order_qty) {
+            Ok(price) => self.execute_limit_order(order_id, s, price, order_qty, tif, options),
+            Err(rejected) => rejected,
+        };
+        self.process_triggers();
+        fill_result
+    }
+
+    // As execute_limit_order, with the fills put in `fills` instead, cleared first
+    #[allow(clippy::too_many_arguments)]
+    fn execute_limit_order_into(
+        &mut self, 
+        order_id: OrderId, 
+        s: Side, 
+        price: u64, 
+        order_qty: u64, 
+        tif: TimeInForce, 
+        options: OrderOptions, 
+        fills: &mut Vec<Fill>, 
+    ) -> FillResult {
+        fills.clear();
+        let start = FillResult { filled_orders: std::mem::take(fills), ..FillResult::unfilled(order_qty) };
+        let mut fill_result = self.run_limit_order(order_id, s, price, order_qty, tif, options, start);
+        *fills = std::mem::take(&mut fill_result.filled_orders);
+        fill_result
+    }
+
+    // Matches, rests or cancels a limit order, adding its fills to those `fill_result`
+    // starts with, which are none
+    #[allow(clippy::too_many_arguments)]
+    fn run_limit_order(
+        &mut self, 
+        order_id: OrderId, 
+        s: Side, 
+        price: u64, 
+        order_qty: u64, 
+        tif: TimeInForce, 
+        mut options: OrderOptions, 
+        mut fill_result: FillResult, 
+    ) -> FillResult {
+        let mut remaining_order_qty = order_qty;
+        if !options.reentered {
+            self.emit(Event::OrderReceived { kind: OrderKind::Limit, side: s, price: Some(self.price(price)), qty: order_qty });
+        }
+
+        options.original_qty = options.filled_qty.saturating_add(order_qty);
+        if let Err(reason) = self.check_limit_order(price, order_qty, tif) {
+            self.emit(Event::OrderRejected { kind: OrderKind::Limit, price: Some(self.price(price)), qty: order_qty, reason });
+            fill_result.status = OrderStatus::Rejected(reason);
+            self.record_unrested(order_id, s, price, &options, &fill_result);
+            return fill_result;
+        }
+        if let TimeInForce::GoodTillDate(expires_at) = tif {
+            options.expires_at = Some(expires_at);
+        }
+        if let (Some(owner), Some(client_order_id)) = (options.owner, &options.client_order_id) {
+            if !self.order_history.claim_client_id(owner, client_order_id, order_id) {
+                let reason = RejectReason::DuplicateClientOrderId;
+                self.emit(Event::OrderRejected { kind: OrderKind::Limit, price: Some(self.price(price)), qty: order_qty, reason });
+                fill_result.status = OrderStatus::Rejected(reason);
+                self.record_unrested(order_id, s, price, &options, &fill_result);
+                return fill_result;
+            }
+        }
+        let taker = options.owner.map(|owner| {
+            (owner, options.self_trade_prevention.unwrap_or(self.config.self_trade_prevention))
+        });
+        // Most orders find nothing on the other side to trade with. Those skip matching
+        // and rest as they are, their own price all the BBO has to take in.
+        let crosses = self.can_cross(s, price);
+        let fully_executable = match (&tif, options.all_or_none) {
+            (TimeInForce::FillOrKill, _) | (_, true) => {
+                crosses && self.executable_qty_for(s, Some(price), order_qty, taker) == order_qty
+            }
+            _ => true,
+        };
+        let rests = matches!(tif, TimeInForce::GoodTillCancel | TimeInForce::GoodTillDate(_));
+        if options.all_or_none && rests && self.all_or_none_would_cross(s, price, order_qty, taker) {
+            let reason = RejectReason::AllOrNoneWouldCross;
+            self.emit(Event::OrderRejected { kind: OrderKind::Limit, price: Some(self.price(price)), qty: order_qty, reason });
+            fill_result.status = OrderStatus::Rejected(reason);
+            self.record_unrested(order_id, s, price, &options, &fill_result);
+            return fill_result;
+        }
+        if !options.reentered {
+            self.accept_order();
+        }
+
+        if let TimeInForce::FillOrKill = tif {
+            if !fully_executable {
+                self.emit(Event::Killed { price: self.price(price), qty: order_qty });
+                fill_result.status = OrderStatus::Killed;
+                self.record_unrested(order_id, s, price, &options, &fill_result);
+                return fill_result;
+            }
+        }
+
+        // An all-or-none order that can't fill completely now rests untouched, as it
+        // does not cross, during an auction every order does
+        if crosses && fully_executable && !self.in_auction() {
+            let taker_side = Counterparty { order_id: Some(order_id), owner: options.owner, tag: options.tag };
+            self.match_incoming(s, Some(price), &mut remaining_order_qty, &mut fill_result, taker, taker_side);
+        }
+
+        let self_trade_cancelled_qty = fill_result.self_trade_cancelled_qty();
+        fill_result.remaining_qty = remaining_order_qty + self_trade_cancelled_qty;
+        if remaining_order_qty == 0 {
+            fill_result.status = if self_trade_cancelled_qty == 0 {
+                OrderStatus::Filled
+            } else {
+                cancelled_remainder_status(fill_result.remaining_qty, order_qty)
+            };
+            self.record_unrested(order_id, s, price, &options, &fill_result);
+        } else {
+            // Matching skips resting orders it may not trade with, min-fill and
+            // all-or-none orders it is too small for among them. What is left may not
+            // rest at or through their price, that would cross the book. Reduce-only
+            // orders the match left nothing to reduce are resized first, or they would
+            // count as skipped.
+            if crosses && !self.reduce_only_orders.is_empty() {
+                self.resize_reduce_only_orders();
+            }
+            let skipped = crosses && !self.in_auction() && self.crosses_resting(s, price);
+            match tif {
+                TimeInForce::GoodTillCancel | TimeInForce::GoodTillDate(_) if !skipped => {
+                    self.emit(Event::Rested { price: self.price(price), qty: remaining_order_qty });
+
+                    if fill_result.remaining_qty == order_qty {
+                        fill_result.status = OrderStatus::Created;
+                    } else {
+                        fill_result.status = OrderStatus::PartiallyFilled;
+                    }
+
+                    options.filled_qty += order_qty - fill_result.remaining_qty;
+                    options.filled_notional += fill_result.total_notional();
+                    self.create_resting_order(order_id, s, price, remaining_order_qty, &options);
+                    fill_result.resting_price = Some(self.price(price));
+                    fill_result.resting_order_id = Some(order_id);
+                }
+                _ => {
+                    self.emit(Event::RemainderCancelled { price: Some(self.price(price)), qty: remaining_order_qty });
+                    fill_result.status = cancelled_remainder_status(fill_result.remaining_qty, order_qty);
+                    self.record_unrested(order_id, s, price, &options, &fill_result);
+                }
+            }
+        }
+
+        if crosses {
+            self.update_bbo();
+        }
+
+        fill_result
+    }
+
+    // Whether an order on side `s` limited at `price` has anything resting to trade
+    // with, hidden and midpoint orders included
+    fn can_cross(&self, s: Side, price: u64) -> bool {
+        if self.midpoint_book.has_orders(s.opposite()) && self.midpoint_for(s, Some(price)).is_some() {
+            return true;
+        }
+        self.crosses_resting(s, price)
+    }
+
+    // An all-or-none order that can't fill completely would rest crossing the
+    // orders it could not take, outside an auction it is refused instead
+    fn all_or_none_would_cross(
+        &self, 
+        s: Side, 
+        price: u64, 
+        qty: u64, 
+        taker: Option<(AccountId, SelfTradePrevention)>, 
+    ) -> bool {
+        !self.in_auction() && self.can_cross(s, price) && self.executable_qty_for(s, Some(price), qty, taker) < qty
+    }
+
+    // Whether an order on side `s` resting at `price` would cross an order resting
+    // on the other side, hidden ones included
+    fn crosses_resting(&self, s: Side, price: u64) -> bool {
+        match s {
+            Side::Bid => self.ask_book.best_resting_price().is_some_and(|best| best <= price), 
+            Side::Ask => self.bid_book.best_resting_price().is_some_and(|best| best >= price), 
+        }
+    }
+
+    // Matches like a limit order, any remainder rests showing only `display_qty`
+    // at a time and is replenished from the reserve as each slice fills
+    pub fn add_iceberg_order(&mut self, s: Side, price: impl Into<Price>, order_qty: u64, display_qty: u64) -> FillResult {
+        let options = OrderOptions { display_qty: Some(display_qty), ..Default::default() };
+        self.submit_limit_order(s, price.into(), order_qty, TimeInForce::GoodTillCancel, options)
+    }
+
+    // Matches like a limit order, any remainder rests without being displayed.
+    // It still trades, but only after all displayed qty at its price.
+    pub fn add_hidden_order(&mut self, s: Side, price: impl Into<Price>, order_qty: u64) -> FillResult {
+        let options = OrderOptions { hidden: true, ..Default::default() };
+        self.submit_limit_order(s, price.into(), order_qty, TimeInForce::GoodTillCancel, options)
+    }
+
+    // Rests until a single incoming order can fill it completely. On entry it
+    // only matches if the whole qty can execute immediately, and is rejected if it
+    // would cross the book without that.
+    pub fn add_all_or_none_order(&mut self, s: Side, price: impl Into<Price>, order_qty: u64) -> FillResult {
+        let options = OrderOptions { all_or_none: true, ..Default::default() };
+        self.submit_limit_order(s, price.into(), order_qty, TimeInForce::GoodTillCancel, options)
+    }
+
+    // Limit order for `owner`, never trading against that owner's resting orders.
+    // `self_trade_prevention` overrides the book's policy for this order.
+    pub fn add_owned_limit_order(
+        &mut self, 
+        owner: AccountId, 
+        s: Side, 
+        price: impl Into<Price>, 
+        order_qty: u64, 
+        self_trade_prevention: Option<SelfTradePrevention>, 
+    ) -> FillResult {
+        let options = OrderOptions { owner: Some(owner), self_trade_prevention, ..Default::default() };
+        self.submit_limit_order(s, price.into(), order_qty, TimeInForce::GoodTillCancel, options)
+    }
+
+    // Limit order carrying `tag`, which is echoed back on its fills, trades, cancel
+    // and expiry and kept when it is amended
+    pub fn add_tagged_limit_order(&mut self, s: Side, price: impl Into<Price>, order_qty: u64, tif: TimeInForce, tag: u64) -> FillResult {
+        let options = OrderOptions { tag: Some(tag), ..Default::default() };
+        self.submit_limit_order(s, price.into(), order_qty, tif, options)
+    }
+
+    // Rests an order that is never hit for less than `min_fill.qty` in a single match.
+    // An incoming order too small for it trades with the orders behind it, and what it
+    // has left is cancelled rather than resting through it.
+    pub fn add_min_fill_order(&mut self, s: Side, price: impl Into<Price>, order_qty: u64, min_fill: MinFill) -> FillResult {
+        let options = OrderOptions { min_fill: Some(min_fill), ..Default::default() };
+        self.submit_limit_order(s, price.into(), order_qty, TimeInForce::GoodTillCancel, options)
+    }
+
+    // Qty an incoming order on side `s` would fill immediately, taking all-or-none,
+    // hidden and expired orders into account. A `limit` of None walks the whole book,
+    // one between ticks the ticks within it.
+    pub fn executable_qty(&self, s: Side, limit: Option<Price>, qty: u64) -> u64 {
+        let limit = limit.map(|limit| match s {
+            Side::Bid => self.ticks_toward(limit, TickRounding::Down),
+            Side::Ask => self.ticks_toward(limit, TickRounding::Up),
+        });
+        self.executable_qty_for(s, limit, qty, None)
+    }
+
+    fn executable_qty_for(
+        &self, 
+        s: Side, 
+        limit: Option<u64>, 
+        qty: u64, 
+        taker: Option<(AccountId, SelfTradePrevention)>, 
+    ) -> u64 {
+        let opposite_book = match s {
+            Side::Ask => &self.bid_book,
+            Side::Bid => &self.ask_book,
+        };
+        let midpoint_qty = self.midpoint_for(s, limit)
+            .map_or(0, |_| self.midpoint_book.qty(s.opposite()).min(qty));
+        midpoint_qty + opposite_book.executable_qty(
+            limit, 
+            qty - midpoint_qty, 
+            self.clock.now(), 
+            &self.fired_oca_groups, 
+            taker, 
+            self.config.matching, 
+            &self.positions, 
+        )
+    }
+
+    // Midpoint of the lit BBO rounded down to the tick, None if either side is empty
+    // None during an auction, the book may be crossed
+    fn midpoint(&self) -> Option<u64> {
+        if self.in_auction() {
+            return None;
+        }
+        let best_bid = self.bid_book.best_price()?;
+        let best_ask = self.ask_book.best_price()?;
+        Some(((best_bid as u128 + best_ask as u128) / 2) as u64)
+    }
+
+    // Midpoint, if an incoming order on side `s` limited at `limit` may trade there
+    fn midpoint_for(&self, s: Side, limit: Option<u64>) -> Option<u64> {
+        let mid = self.midpoint()?;
+        let willing = match s {
+            Side::Bid => limit.is_none_or(|price| price >= mid),
+            Side::Ask => limit.is_none_or(|price| price <= mid),
+        };
+        willing.then_some(mid)
+    }
+
+    // Rests a non-displayed order that only executes at the midpoint of the lit BBO,
+    // against other midpoint orders or incoming orders willing to trade there.
+    // While either side of the lit book is empty there is no midpoint and it just waits.
+    pub fn add_midpoint_order(&mut self, s: Side, order_qty: u64) -> (OrderId, FillResult) {
+        let order_id = self.new_order_id();
+        let mut remaining_order_qty = order_qty;
+        self.emit(Event::OrderReceived { kind: OrderKind::Midpoint, side: s, price: None, qty: order_qty });
+
+        let mut fill_result = FillResult::unfilled(order_qty);
+        if let Err(reason) = self.check_order_limits(None, order_qty) {
+            self.emit(Event::OrderRejected { kind: OrderKind::Midpoint, price: None, qty: order_qty, reason });
+            fill_result.status = OrderStatus::Rejected(reason);
+            return (order_id, fill_result);
+        }
+        self.accept_order();
+        if let Some(mid) = self.midpoint() {
+            let fills = self.midpoint_book.match_side(s.opposite(), &mut remaining_order_qty);
+            self.counters.trades += fills.len() as u64;
+            let matched_qty: u64 = fills.iter().map(|(_, q)| q).sum();
+            self.counters.matched_qty = self.counters.matched_qty.saturating_add(matched_qty);
+            for (maker_order_id, qty) in fills {
+                let maker_side = Counterparty { order_id: Some(maker_order_id), ..Default::default() };
+                let taker_side = Counterparty { order_id: Some(order_id), ..Default::default() };
+                let trade = self.record_match(mid, qty, s, maker_side, taker_side);
+                fill_result.filled_orders.push(Fill { maker_order_id, maker_owner: None, maker_tag: None, price: self.price(mid), qty, timestamp: trade.timestamp, seq: trade.seq });
+            }
+            if matched_qty != 0 {
+                self.emit(Event::Matched { price: self.price(mid), qty: matched_qty, midpoint: true });
+                self.stop_trigger_price = Some(mid);
+            }
+        }
+
+        fill_result.remaining_qty = remaining_order_qty;
+        if remaining_order_qty == 0 {
+            fill_result.status = OrderStatus::Filled;
+        } else {
+            if remaining_order_qty == order_qty {
+                fill_result.status = OrderStatus::Created;
+            } else {
+                fill_result.status = OrderStatus::PartiallyFilled;
+            }
+            self.midpoint_book.insert(s, order_id, remaining_order_qty);
+        }
+
+        self.process_triggers();
+        (order_id, fill_result)
+    }
+
+    // Midpoint orders that crossed each other since the previous call, which happens
+    // when a midpoint appears while both sides had orders waiting
+    pub fn take_midpoint_crosses(&mut self) -> Vec<MidpointCross> {
+        std::mem::take(&mut self.midpoint_crosses)
+    }
+
+    fn cross_midpoint_orders(&mut self) {
+        let Some(mid) = self.midpoint() else {
+            return;
+        };
+        let crosses = self.midpoint_book.cross(self.price(mid));
+        self.counters.trades += crosses.len() as u64;
+        for c in &crosses {
+            let buy = Counterparty { order_id: Some(c.buy_order_id), ..Default::default() };
+            let sell = Counterparty { order_id: Some(c.sell_order_id), ..Default::default() };
+            self.record_trade(c.price.ticks(), c.qty, buy, sell, None, false);
+        }
+        let crossed_qty = saturating_sum(crosses.iter().map(|c| c.qty));
+        self.counters.matched_qty = self.counters.matched_qty.saturating_add(crossed_qty);
+        if !crosses.is_empty() {
+            self.stop_trigger_price = Some(mid);
+            self.midpoint_crosses.extend(crosses);
+        }
+    }
+
+    // Takes whatever liquidity the opposite side has, the remainder is never rested
+    pub fn add_market_order(&mut self, s: Side, order_qty: u64) -> FillResult {
+        let order_id = self.new_order_id();
+        let fill_result = self.execute_market_order(order_id, s, order_qty);
+        self.process_triggers();
+        fill_result
+    }
+
+    fn execute_market_order(&mut self, order_id: OrderId, s: Side, order_qty: u64) -> FillResult {
+        self.emit(Event::OrderReceived { kind: OrderKind::Market, side: s, price: None, qty: order_qty });
+
+        let mut fill_result = FillResult::unfilled(order_qty);
+        if let Err(reason) = self.check_order_limits(None, order_qty) {
+            self.emit(Event::OrderRejected { kind: OrderKind::Market, price: None, qty: order_qty, reason });
+            fill_result.status = OrderStatus::Rejected(reason);
+            return fill_result;
+        }
+        if self.in_auction() {
+            let reason = RejectReason::AuctionInProgress;
+            self.emit(Event::OrderRejected { kind: OrderKind::Market, price: None, qty: order_qty, reason });
+            fill_result.status = OrderStatus::Rejected(reason);
+            return fill_result;
+        }
+        self.accept_order();
+        self.match_market_order(order_id, s, order_qty)
+    }
+
+    // Matches a market order already accepted, cancelling what it can't fill. Its
+    // trades go on the tape with `order_id` as the taker.
+    fn match_market_order(&mut self, order_id: OrderId, s: Side, order_qty: u64) -> FillResult {
+        let mut remaining_order_qty = order_qty;
+        let mut fill_result = FillResult::unfilled(order_qty);
+        let taker_side = Counterparty { order_id: Some(order_id), ..Default::default() };
+        self.match_incoming(s, None, &mut remaining_order_qty, &mut fill_result, None, taker_side);
+
+        fill_result.remaining_qty = remaining_order_qty;
+        if remaining_order_qty == 0 {
+            fill_result.status = OrderStatus::Filled;
+        } else {
+            self.emit(Event::RemainderCancelled { price: None, qty: remaining_order_qty });
+            fill_result.status = cancelled_remainder_status(remaining_order_qty, order_qty);
+        }
+
+        self.update_bbo();
+
+        fill_result
+    }
+
+    // Executes against the best opposite price level only and rests any remainder
+    // as a limit at that price, reported in `resting_price`
+    pub fn add_market_to_limit_order(&mut self, s: Side, order_qty: u64) -> FillResult {
+        let best_opposite = match s {
+            Side::Bid => self.ask_book.best_resting_price(),
+            Side::Ask => self.bid_book.best_resting_price(),
+        };
+        let Some(price) = best_opposite else {
+            let reason = RejectReason::NoOppositeLiquidity;
+            self.emit(Event::OrderRejected { kind: OrderKind::MarketToLimit, price: None, qty: order_qty, reason });
+            return FillResult::rejected(order_qty, reason);
+        };
+
+        let order_id = self.new_order_id();
+        let fill_result = self.execute_limit_order(
+            order_id, 
+            s, 
+            price, 
+            order_qty, 
+            TimeInForce::GoodTillCancel, 
+            OrderOptions::default(), 
+        );
+        self.process_triggers();
+        fill_result
+    }
+
+    // Parks a stop outside the visible book, it becomes a market order once
+    // the book's trigger source, by default the last trade price, reaches the stop price
+    pub fn create_stop_order(&mut self, s: Side, stop_price: impl Into<Price>, qty: u64) -> Result<OrderId, Error> {
+        let trigger_source = self.config.stop_trigger_source;
+        self.park_stop(s, TriggerKind::Stop, stop_price.into(), None, qty, trigger_source)
+    }
+
+    // Mirror of a stop, parked until the last trade price reaches `trigger_price`
+    // from the favorable side (a buy at or below it, a sell at or above it),
+    // then entered as a market order
+    pub fn create_mit_order(&mut self, s: Side, trigger_price: impl Into<Price>, qty: u64) -> Result<OrderId, Error> {
+        let trigger_source = self.config.stop_trigger_source;
+        self.park_stop(s, TriggerKind::MarketIfTouched, trigger_price.into(), None, qty, trigger_source)
+    }
+
+    // Like a stop, but once triggered it is submitted as a limit order at `limit_price`
+    // and rests in the book if that price is no longer marketable
+    pub fn create_stop_limit_order(
+        &mut self, 
+        s: Side, 
+        stop_price: impl Into<Price>, 
+        limit_price: impl Into<Price>, 
+        qty: u64, 
+    ) -> Result<OrderId, Error> {
+        let trigger_source = self.config.stop_trigger_source;
+        self.park_stop(s, TriggerKind::Stop, stop_price.into(), Some(limit_price.into()), qty, trigger_source)
+    }
+
+    // Stop, stop-limit or MIT order triggered off `trigger_source` instead of the book's default
+    pub fn create_stop_order_triggered_by(
+        &mut self, 
+        s: Side, 
+        kind: TriggerKind, 
+        stop_price: impl Into<Price>, 
+        limit_price: Option<Price>, 
+        qty: u64, 
+        trigger_source: TriggerSource, 
+    ) -> Result<OrderId, Error> {
+        self.park_stop(s, kind, stop_price.into(), limit_price, qty, trigger_source)
+    }
+
+    // Checks and accepts a stop, stop-limit or MIT order, then parks it until it triggers.
+    // Both prices have to be on the book's ticks.
+    fn park_stop(
+        &mut self, 
+        s: Side, 
+        kind: TriggerKind, 
+        stop_price: Price, 
+        limit_price: Option<Price>, 
+        qty: u64, 
+        trigger_source: TriggerSource, 
+    ) -> Result<OrderId, Error> {
+        let stop_price = self.order_ticks(stop_price)?;
+        let limit_price = limit_price.map(|p| self.order_ticks(p)).transpose()?;
+        self.check_order_limits(Some(stop_price), qty)?;
+        if limit_price.is_some() {
+            self.check_order_limits(limit_price, qty)?;
+        }
+        let order_id = self.new_order_id();
+        self.accept_order();
+        self.stop_book.insert(StopOrder {
+            order_id, 
+            side: s, 
+            kind, 
+            trigger_source, 
+            stop_price, 
+            limit_price, 
+            qty, 
+        });
+        self.process_triggers();
+        Ok(order_id)
+    }
+
+    // Sell trailing stops trigger once the best bid falls `offset_ticks` below the
+    // highest best bid seen since creation, buy trailing stops mirror this on the ask
+    pub fn create_trailing_stop_order(&mut self, s: Side, offset_ticks: u64, qty: u64) -> Result<OrderId, Error> {
+        self.insert_trailing_stop(s, TrailingOffset::Ticks(offset_ticks), qty)
+    }
+
+    // Trailing stop whose offset is a percentage of the best price seen
+    pub fn create_trailing_stop_order_pct(&mut self, s: Side, offset_pct: f64, qty: u64) -> Result<OrderId, Error> {
+        self.insert_trailing_stop(s, TrailingOffset::Percent(offset_pct), qty)
+    }
+
+    // Enters the book as a market order when it triggers, only its qty is checked
+    fn insert_trailing_stop(&mut self, s: Side, offset: TrailingOffset, qty: u64) -> Result<OrderId, Error> {
+        self.check_order_limits(None, qty)?;
+        let order_id = self.new_order_id();
+        self.accept_order();
+        self.stop_book.insert_trailing(TrailingStopOrder {
+            order_id, 
+            side: s, 
+            offset, 
+            qty, 
+            reference_price: None, 
+        });
+        self.process_triggers();
+        Ok(order_id)
+    }
+
+    // Removes every good-till-date order whose expiry is at or before `now`, along
+    // with any expired orders matching already skipped, and returns them
+    pub fn expire_orders(&mut self, now: u64) -> Vec<ExpiredOrder> {
+        let mut expired = std::mem::take(&mut self.expired_orders);
+        for s in [Side::Bid, Side::Ask] {
+            let book = match s {
+                Side::Ask => &mut self.ask_book, 
+                Side::Bid => &mut self.bid_book, 
+            };
+            for (o, price) in book.remove_expired(now, &mut self.order_loc) {
+                self.finish_order(&o, s, price, OrderStatus::Expired);
+                self.next_seq();
+                expired.push(expired_order(o, s, self.price(price)));
+            }
+        }
+
+        self.update_bbo();
+        self.process_triggers();
+
+        expired
+    }
+
+    // Rests an order pegged to the best unpegged price on side `s`, `offset` ticks
+    // towards the spread (negative to sit behind it). It never crosses the opposite
+    // best price and loses time priority every time it is repriced.
+    pub fn create_pegged_order(&mut self, s: Side, offset: i64, qty: u64) -> Result<OrderId, Error> {
+        let Some(price) = self.peg_price(s, offset) else {
+            return Err(Error::NoPegReference { side: s });
+        };
+        self.check_order_limits(Some(price), qty)?;
+
+        let options = OrderOptions { pegged: true, ..Default::default() };
+        let order_id = self.new_order_id();
+        self.accept_order();
+        self.create_resting_order(order_id, s, price, qty, &options);
+        self.pegged_orders.push(PeggedOrder { order_id, side: s, offset, price });
+
+        self.update_bbo();
+        self.process_triggers();
+        Ok(order_id)
+    }
+
+    fn peg_price(&self, s: Side, offset: i64) -> Option<u64> {
+        let (own_book, opposite_book) = match s {
+            Side::Bid => (&self.bid_book, &self.ask_book),
+            Side::Ask => (&self.ask_book, &self.bid_book),
+        };
+        let reference = own_book.best_unpegged_price()? as i128;
+        let opposite_best = opposite_book.best_resting_price();
+
+        let price = match s {
+            Side::Bid => {
+                let capped = opposite_best.map_or(i128::MAX, |p| p as i128 - 1);
+                (reference + offset as i128).min(capped)
+            }
+            Side::Ask => {
+                let capped = opposite_best.map_or(i128::MIN, |p| p as i128 + 1);
+                (reference - offset as i128).max(capped)
+            }
+        };
+        Some(price.clamp(0, u64::MAX as i128) as u64)
+    }
+
+    // Moves every pegged order whose target price changed to the back of its new level.
+    // Pegs keep their price while there is nothing to peg to.
+    fn reprice_pegs(&mut self) {
+        self.pegged_orders.retain(|p| self.order_loc.contains_key(&p.order_id));
+
+        let mut moved = false;
+        for i in 0..self.pegged_orders.len() {
+            let (side, offset, price) = {
+                let p = &self.pegged_orders[i];
+                (p.side, p.offset, p.price)
+            };
+            let Some(new_price) = self.peg_price(side, offset) else {
+                continue;
+            };
+            if new_price == price {
+                continue;
+            }
+
+            let order_id = &self.pegged_orders[i].order_id;
+            let book = match side {
+                Side::Ask => &mut self.ask_book,
+                Side::Bid => &mut self.bid_book,
+            };
+            let (_, level, pos) = self.order_loc[order_id];
+            let Some(order) = book.level_mut(level).and_then(|l| l.remove(pos)) else {
+                continue;
+            };
+            book.reclaim_levels([price], &mut self.order_loc);
+            let hidden = order.hidden;
+            let (new_level, new_pos) = book.push_order(new_price, order);
+            self.order_loc.insert(*order_id, (side, new_level, new_pos));
+            if !hidden {
+                self.improve_bbo(side, new_price);
+            }
+            self.pegged_orders[i].price = new_price;
+            moved = true;
+        }
+
+        if moved {
+            self.update_bbo();
+        }
+    }
+
+    // Results of every stop and market-if-touched order triggered since the previous call
+    pub fn take_triggered_stops(&mut self) -> Vec<TriggeredStop> {
+        std::mem::take(&mut self.triggered_stops)
+    }
+
+    // Fills from triggered stops move the last trade price and the BBO, so keep
+    // going until no more stops are eligible. Every stop fires at most once.
+    fn process_triggers(&mut self) {
+        // Nothing trades during an auction, triggers catch up after the uncross
+        if self.in_auction() {
+            self.notify_bbo_change();
+            return;
+        }
+        loop {
+            self.reprice_pegs();
+            self.cross_midpoint_orders();
+            let mut fired = self.process_maker_fills();
+            self.resize_reduce_only_orders();
+
+            let prices = TriggerPrices {
+                last_trade: self.stop_trigger_price, 
+                best_bid: self.bid_book.best_price(), 
+                best_ask: self.ask_book.best_price(), 
+                midpoint: self.midpoint(), 
+            };
+            for stop in self.stop_book.take_triggered(prices) {
+                fired = true;
+                self.on_linked_execution(stop.order_id, stop.qty, true);
+                self.emit(Event::StopTriggered {
+                    order_id: stop.order_id, 
+                    kind: stop.kind, 
+                    source: stop.trigger_source, 
+                    trigger_price: prices.get(stop.trigger_source).map(|p| self.price(p)), 
+                });
+                let fill_result = match stop.limit_price {
+                    // Enters the book under the stop's own id, so it can still be
+                    // cancelled, amended and looked up by it. It was accepted when
+                    // it was parked.
+                    Some(limit_price) => {
+                        self.execute_limit_order(
+                            stop.order_id, 
+                            stop.side, 
+                            limit_price, 
+                            stop.qty, 
+                            TimeInForce::GoodTillCancel, 
+                            OrderOptions { reentered: true, ..Default::default() }, 
+                        )
+                    }
+                    None => self.match_market_order(stop.order_id, stop.side, stop.qty),
+                };
+                self.triggered_stops.push(TriggeredStop { order_id: stop.order_id, fill_result });
+            }
+
+            let best_bid = self.bid_book.best_price();
+            let best_ask = self.ask_book.best_price();
+            for stop in self.stop_book.take_triggered_trailing(best_bid, best_ask) {
+                fired = true;
+                self.emit(Event::TrailingStopTriggered { order_id: stop.order_id, stop_price: stop.stop_price().map(|p| self.price(p)) });
+                let fill_result = self.match_market_order(stop.order_id, stop.side, stop.qty);
+                self.triggered_stops.push(TriggeredStop { order_id: stop.order_id, fill_result });
+            }
+
+            if !fired {
+                break;
+            }
+        }
+        self.debug_validate();
+        self.notify_bbo_change();
+    }
+
+    // Rests the order without ever taking liquidity
+    pub fn add_post_only_order(
+        &mut self, 
+        s: Side, 
+        price: impl Into<Price>, 
+        order_qty: u64, 
+        mode: PostOnlyMode, 
+    ) -> FillResult {
+        let price = price.into();
+        self.emit(Event::OrderReceived { kind: OrderKind::PostOnly, side: s, price: Some(price), qty: order_qty });
+        let price = match self.limit_ticks(OrderKind::PostOnly, price, order_qty) {
+            Ok(price) => price,
+            Err(rejected) => return rejected,
+        };
+
+        let mut fill_result = FillResult::unfilled(order_qty);
+
+        // Hidden liquidity counts too, otherwise the book could end up crossed
+        let crossed_best = match s {
+            Side::Bid => self.ask_book.best_resting_price().filter(|p| price >= *p),
+            Side::Ask => self.bid_book.best_resting_price().filter(|p| price <= *p),
+        };
+
+        let mut resting_price = price;
+        if let Some(best) = crossed_best {
+            let repriced = match (&mode, &s) {
+                (PostOnlyMode::Reprice, Side::Bid) => best.checked_sub(1),
+                (PostOnlyMode::Reprice, Side::Ask) => best.checked_add(1),
+                (PostOnlyMode::Reject, _) => None,
+            };
+            match repriced {
+                Some(p) => resting_price = p,
+                None => {
+                    let reason = RejectReason::PostOnlyWouldCross;
+                    self.emit(Event::OrderRejected { kind: OrderKind::PostOnly, price: Some(self.price(price)), qty: order_qty, reason });
+                    fill_result.status = OrderStatus::Rejected(reason);
+                    return fill_result;
+                }
+            }
+        }
+
+        let order_id = match self.rest_new_order(s, resting_price, order_qty) {
+            Ok(order_id) => order_id,
+            Err(reason) => {
+                self.emit(Event::OrderRejected { kind: OrderKind::PostOnly, price: Some(self.price(resting_price)), qty: order_qty, reason });
+                fill_result.status = OrderStatus::Rejected(reason);
+                return fill_result;
+            }
+        };
+        fill_result.status = OrderStatus::Created;
+        fill_result.resting_price = Some(self.price(resting_price));
+        fill_result.resting_order_id = Some(order_id);
+
+        self.update_bbo();
+        self.process_triggers();
+
+        fill_result
+    }
+
+    // Former name of print_bbo
+    pub fn get_bbo(&self) {
+        self.print_bbo();
+    }
+
+}
+
+
@@ -0,0 +1,129 @@
+use crate::{AccountId, Event, HalfBook, Order, OrderBook, OrderId, OrderIdMap, OrderLoc, OrderStatus, Price, Side, TickRounding};
+
+// Resting order taken out of the book by a mass cancel
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CancelledOrder {
+    pub order_id: OrderId,
+    pub side: Side,
+    pub price: Price,
+    // Qty it still had resting, iceberg reserve included
+    pub remaining_qty: u64,
+}
+
+impl HalfBook {
+    // Empties the side, handing back every order with its price, best price first and
+    // in queue order within a price. Its level slots are freed for reuse.
+    fn take_all(&mut self, order_loc: &mut OrderIdMap<OrderLoc>) -> Vec<(Order, u64)> {
+        self.take_range(0, u64::MAX, order_loc)
+    }
+
+    // Empties the levels priced from `low` to `high` inclusive, handing back their orders
+    // best price first like take_all
+    fn take_range(&mut self, low: u64, high: u64, order_loc: &mut OrderIdMap<OrderLoc>) -> Vec<(Order, u64)> {
+        let prices: Box<dyn Iterator<Item = (&u64, &usize)>> = match self.s {
+            Side::Bid => Box::new(self.price_map.range(low..=high).rev()),
+            Side::Ask => Box::new(self.price_map.range(low..=high)),
+        };
+        let levels: Vec<(u64, usize)> = prices.map(|(p, u)| (*p, *u)).collect();
+        let mut taken = Vec::new();
+        for &(price, u) in &levels {
+            taken.extend(self.price_levels[u].drain().map(|o| (o, price)));
+        }
+        self.reclaim_levels(levels.into_iter().map(|(p, _)| p), order_loc);
+        taken
+    }
+}
+
+impl OrderBook {
+    // Cancels every order resting in the book in one go, bids then asks, leaving both
+    // sides empty. Orders linked to them are cancelled like
+    // cancel_order would, stop, midpoint and on-close orders are not touched.
+    pub fn cancel_all(&mut self) -> Vec<CancelledOrder> {
+        let mut taken = self.take_side(Side::Bid);
+        taken.extend(self.take_side(Side::Ask));
+        let cancelled = self.cancel_taken(taken);
+        self.emit(Event::MassCancelled { count: cancelled.len() });
+        self.process_triggers();
+        cancelled
+    }
+
+    // Cancels every order resting on side `s`, best price first, like cancel_all does
+    // for both. The side is left with no best price.
+    pub fn cancel_side(&mut self, s: Side) -> Vec<CancelledOrder> {
+        let taken = self.take_side(s);
+        let cancelled = self.cancel_taken(taken);
+        self.update_bbo();
+        self.emit(Event::MassCancelled { count: cancelled.len() });
+        self.process_triggers();
+        cancelled
+    }
+
+    // Cancels every order `owner` has resting, bids then asks, each in priority order.
+    // Only that owner's orders are looked at, nothing is returned if it has none.
+    pub fn cancel_all_for(&mut self, owner: AccountId) -> Vec<CancelledOrder> {
+        let taken: Vec<_> = self
+            .orders_for(owner)
+            .into_iter()
+            .filter_map(|info| self.take_resting_order_at(info.order_id))
+            .collect();
+        let cancelled = self.cancel_taken(taken);
+        self.update_bbo();
+        self.emit(Event::MassCancelled { count: cancelled.len() });
+        self.process_triggers();
+        cancelled
+    }
+
+    // Cancels every order resting on side `s` priced from `price_from` to `price_to`,
+    // both included and given either way round, best price first. Bounds between ticks
+    // take in the ticks within them.
+    pub fn cancel_range(&mut self, s: Side, price_from: impl Into<Price>, price_to: impl Into<Price>) -> Vec<CancelledOrder> {
+        let (price_from, price_to) = (price_from.into(), price_to.into());
+        let low = self.ticks_toward(price_from.min(price_to), TickRounding::Up);
+        let high = self.ticks_toward(price_from.max(price_to), TickRounding::Down);
+        let book = match s {
+            Side::Ask => &mut self.ask_book,
+            Side::Bid => &mut self.bid_book,
+        };
+        let taken = if low <= high { book.take_range(low, high, &mut self.order_loc) } else { Vec::new() };
+        let taken = taken.into_iter().map(|(o, price)| (o, s, price)).collect();
+        let cancelled = self.cancel_taken(taken);
+        self.update_bbo();
+        self.emit(Event::MassCancelled { count: cancelled.len() });
+        self.process_triggers();
+        cancelled
+    }
+
+    // Empties one side without finishing the orders taken out of it yet
+    fn take_side(&mut self, s: Side) -> Vec<(Order, Side, u64)> {
+        let book = match s {
+            Side::Ask => &mut self.ask_book,
+            Side::Bid => &mut self.bid_book,
+        };
+        let taken: Vec<_> = book.take_all(&mut self.order_loc).into_iter().map(|(o, price)| (o, s, price)).collect();
+        match s {
+            Side::Ask => self.best_ask_price = None,
+            Side::Bid => self.best_bid_price = None,
+        }
+        taken
+    }
+
+    // Finishes orders already taken out of the book as cancelled, only once they are all
+    // out so none of them is reported as a linked cancel of another
+    fn cancel_taken(&mut self, taken: Vec<(Order, Side, u64)>) -> Vec<CancelledOrder> {
+        for (o, side, price) in &taken {
+            self.finish_order(o, *side, *price, OrderStatus::Cancelled);
+            self.counters.orders_cancelled += 1;
+            self.next_seq();
+        }
+        taken
+            .into_iter()
+            .map(|(o, side, price)| {
+                self.cancel_linked(o.order_id);
+                self.leave_oca_group(o.order_id);
+                self.brackets.remove(&o.order_id);
+                CancelledOrder { remaining_qty: o.qty + o.reserve_qty, order_id: o.order_id, side, price: self.price(price) }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+
+use crate::{saturating_sum, CancelReport, OrderId, Price, Side};
+
+#[derive(Debug, Clone)]
+struct MidpointOrder {
+    order_id: OrderId,
+    qty: u64,
+    filled_qty: u64,
+}
+
+// Two midpoint orders executed against each other once a midpoint existed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MidpointCross {
+    pub buy_order_id: OrderId,
+    pub sell_order_id: OrderId,
+    pub qty: u64,
+    pub price: Price,
+}
+
+// Non-displayed orders that only ever execute at the midpoint of the lit BBO,
+// each side in time priority
+#[derive(Debug, Clone)]
+pub(crate) struct MidpointBook {
+    buys: VecDeque<MidpointOrder>,
+    sells: VecDeque<MidpointOrder>,
+}
+
+impl MidpointBook {
+    pub fn new() -> MidpointBook {
+        MidpointBook {
+            buys: VecDeque::new(),
+            sells: VecDeque::new(),
+        }
+    }
+
+    fn side_mut(&mut self, s: Side) -> &mut VecDeque<MidpointOrder> {
+        match s {
+            Side::Bid => &mut self.buys,
+            Side::Ask => &mut self.sells,
+        }
+    }
+
+    pub fn insert(&mut self, s: Side, order_id: OrderId, qty: u64) {
+        self.side_mut(s).push_back(MidpointOrder { order_id, qty, filled_qty: 0 });
+    }
+
+    // None if no order has this id
+    pub fn cancel(&mut self, order_id: OrderId, now: u64) -> Option<CancelReport> {
+        for (side, orders) in [(Side::Bid, &mut self.buys), (Side::Ask, &mut self.sells)] {
+            if let Some(i) = orders.iter().position(|o| o.order_id == order_id) {
+                let o = orders.remove(i)?;
+                return Some(CancelReport {
+                    order_id: o.order_id,
+                    owner: None,
+                    tag: None,
+                    side,
+                    price: None,
+                    cancelled_qty: o.qty,
+                    previously_filled_qty: o.filled_qty,
+                    entered_at: None,
+                    cancelled_at: now,
+                });
+            }
+        }
+        None
+    }
+
+    pub fn has_orders(&self, s: Side) -> bool {
+        match s {
+            Side::Bid => !self.buys.is_empty(),
+            Side::Ask => !self.sells.is_empty(),
+        }
+    }
+
+    // Total resting qty on side `s`
+    pub fn qty(&self, s: Side) -> u64 {
+        let orders = match s {
+            Side::Bid => &self.buys,
+            Side::Ask => &self.sells,
+        };
+        saturating_sum(orders.iter().map(|o| o.qty))
+    }
+
+    // Fills up to `incoming_order_qty` against resting orders on side `s` and
+    // returns the (order id, qty) of each order it traded with
+    pub fn match_side(&mut self, s: Side, incoming_order_qty: &mut u64) -> Vec<(OrderId, u64)> {
+        let orders = self.side_mut(s);
+        let mut fills = Vec::new();
+        while *incoming_order_qty > 0 {
+            let Some(o) = orders.front_mut() else {
+                break;
+            };
+            let qty = o.qty.min(*incoming_order_qty);
+            o.qty -= qty;
+            o.filled_qty += qty;
+            *incoming_order_qty -= qty;
+            fills.push((o.order_id, qty));
+            if o.qty == 0 {
+                orders.pop_front();
+            }
+        }
+        fills
+    }
+
+    // Executes resting buys against resting sells at `price` until one side runs out
+    pub fn cross(&mut self, price: Price) -> Vec<MidpointCross> {
+        let mut crosses = Vec::new();
+        while let (Some(buy), Some(sell)) = (self.buys.front_mut(), self.sells.front_mut()) {
+            let qty = buy.qty.min(sell.qty);
+            buy.qty -= qty;
+            sell.qty -= qty;
+            buy.filled_qty += qty;
+            sell.filled_qty += qty;
+            crosses.push(MidpointCross {
+                buy_order_id: buy.order_id,
+                sell_order_id: sell.order_id,
+                qty,
+                price,
+            });
+            if buy.qty == 0 {
+                self.buys.pop_front();
+            }
+            if sell.qty == 0 {
+                self.sells.pop_front();
+            }
+        }
+        crosses
+    }
+}
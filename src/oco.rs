@@ -0,0 +1,234 @@
+use crate::{new_group_id, Error, Event, OrderBook, OrderId, OrderOptions, OrderStatus, Price, Side, StopOrder, TimeInForce, TriggerKind};
+
+// One leg of a linked order group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OrderSpec {
+    Limit { side: Side, price: Price, qty: u64 },
+    Stop { side: Side, stop_price: Price, qty: u64 },
+    StopLimit { side: Side, stop_price: Price, limit_price: Price, qty: u64 },
+}
+
+// Shared id of a one-cancels-all group
+pub type GroupId = String;
+
+// Which execution of one leg cancels the other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OcoTrigger {
+    FirstFill,
+    CompleteFill,
+}
+
+// Order cancelled because an order linked to it executed or was cancelled
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinkedCancel {
+    pub order_id: OrderId,
+    pub cause_order_id: OrderId,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct OcoLink {
+    sibling: OrderId,
+    trigger: OcoTrigger,
+}
+
+impl OrderBook {
+    // Submits two orders where an execution of either one cancels the other, as
+    // selected by `trigger`. A triggered stop counts as an execution of that leg.
+    // If the first leg already executes on entry the second one is never submitted.
+    // Neither is if either leg is refused.
+    pub fn create_oco(&mut self, a: OrderSpec, b: OrderSpec, trigger: OcoTrigger) -> Result<(OrderId, OrderId), Error> {
+        let ids = self.submit_oco(a, b, trigger)?;
+        self.process_triggers();
+        Ok(ids)
+    }
+
+    pub(crate) fn submit_oco(
+        &mut self,
+        a: OrderSpec,
+        b: OrderSpec,
+        trigger: OcoTrigger,
+    ) -> Result<(OrderId, OrderId), Error> {
+        let (a, b) = (self.check_leg(a)?, self.check_leg(b)?);
+        let (id_a, id_b) = (self.new_order_id(), self.new_order_id());
+        self.oco_links.insert(id_a, OcoLink { sibling: id_b, trigger });
+        self.oco_links.insert(id_b, OcoLink { sibling: id_a, trigger });
+
+        self.submit_leg(id_a, a, OrderOptions::default());
+        if self.oco_links.contains_key(&id_a) {
+            self.submit_leg(id_b, b, OrderOptions::default());
+        } else {
+            self.linked_cancels.push(LinkedCancel { order_id: id_b, cause_order_id: id_a });
+        }
+
+        Ok((id_a, id_b))
+    }
+
+    // Submits `orders` as one group where the first execution of any member cancels
+    // all the others. Members still unsubmitted when that happens are never submitted.
+    // None is if any member is refused.
+    pub fn create_oca_group(&mut self, orders: Vec<OrderSpec>) -> Result<(GroupId, Vec<OrderId>), Error> {
+        let orders = orders.into_iter().map(|spec| self.check_leg(spec)).collect::<Result<Vec<_>, _>>()?;
+        let group_id = new_group_id();
+        let ids: Vec<OrderId> = orders.iter().map(|_| self.new_order_id()).collect();
+        for id in &ids {
+            self.oca_group_of.insert(*id, group_id.clone());
+        }
+        self.oca_groups.insert(group_id.clone(), ids.clone());
+
+        for (i, spec) in orders.into_iter().enumerate() {
+            let options = OrderOptions { oca_group: Some(group_id.clone()), ..Default::default() };
+            self.submit_leg(ids[i], spec, options);
+            if !self.oca_groups.contains_key(&group_id) {
+                for id in &ids[i + 1..] {
+                    self.linked_cancels.push(LinkedCancel { order_id: *id, cause_order_id: ids[i] });
+                }
+                break;
+            }
+        }
+
+        self.process_triggers();
+        Ok((group_id, ids))
+    }
+
+    // Checks a leg against the book's ticks and order limits, before any leg of its
+    // group is submitted, and returns it with its prices on the book's scale
+    fn check_leg(&self, spec: OrderSpec) -> Result<OrderSpec, Error> {
+        let leg_price = |price: Price, qty: u64| -> Result<Price, Error> {
+            let ticks = self.order_ticks(price)?;
+            self.check_order_limits(Some(ticks), qty)?;
+            Ok(self.price(ticks))
+        };
+        Ok(match spec {
+            OrderSpec::Limit { side, price, qty } => OrderSpec::Limit { side, price: leg_price(price, qty)?, qty },
+            OrderSpec::Stop { side, stop_price, qty } => OrderSpec::Stop { side, stop_price: leg_price(stop_price, qty)?, qty },
+            OrderSpec::StopLimit { side, stop_price, limit_price, qty } => OrderSpec::StopLimit {
+                side,
+                stop_price: leg_price(stop_price, qty)?,
+                limit_price: leg_price(limit_price, qty)?,
+                qty,
+            },
+        })
+    }
+
+    // Limit legs are accepted as they enter the book, stop legs as they are parked.
+    // The leg's prices are on the book's scale, as check_leg returns them.
+    fn submit_leg(&mut self, order_id: OrderId, spec: OrderSpec, options: OrderOptions) {
+        match spec {
+            OrderSpec::Limit { side, price, qty } => {
+                let fill_result = self.execute_limit_order(
+                    order_id,
+                    side,
+                    price.ticks(),
+                    qty,
+                    TimeInForce::GoodTillCancel,
+                    options,
+                );
+                if fill_result.remaining_qty < qty {
+                    let done = matches!(fill_result.status, OrderStatus::Filled);
+                    self.on_linked_execution(order_id, qty - fill_result.remaining_qty, done);
+                }
+            }
+            OrderSpec::Stop { side, stop_price, qty } => {
+                self.accept_order();
+                let trigger_source = self.config.stop_trigger_source;
+                self.stop_book.insert(StopOrder { order_id, side, kind: TriggerKind::Stop, trigger_source, stop_price: stop_price.ticks(), limit_price: None, qty });
+            }
+            OrderSpec::StopLimit { side, stop_price, limit_price, qty } => {
+                self.accept_order();
+                let trigger_source = self.config.stop_trigger_source;
+                self.stop_book.insert(StopOrder { order_id, side, kind: TriggerKind::Stop, trigger_source, stop_price: stop_price.ticks(), limit_price: Some(limit_price.ticks()), qty });
+            }
+        }
+    }
+
+    // Called whenever `qty` of `order_id` executed, `done` once nothing of it is left
+    pub(crate) fn on_linked_execution(&mut self, order_id: OrderId, qty: u64, done: bool) {
+        self.on_bracket_execution(order_id, qty, done);
+        self.cancel_oca_group(order_id);
+
+        let Some(link) = self.oco_links.get(&order_id) else {
+            return;
+        };
+        if matches!(link.trigger, OcoTrigger::CompleteFill) && !done {
+            return;
+        }
+        self.cancel_linked(order_id);
+    }
+
+    // Drops the link of `order_id` and cancels its sibling if that is still live
+    pub(crate) fn cancel_linked(&mut self, order_id: OrderId) {
+        let Some(link) = self.oco_links.remove(&order_id) else {
+            return;
+        };
+        self.oco_links.remove(&link.sibling);
+        self.take_profit_stops.remove(&order_id);
+        self.take_profit_stops.remove(&link.sibling);
+        if self.remove_order(link.sibling).is_ok() {
+            self.emit(Event::LinkedCancelled {
+                order_id: link.sibling,
+                cause_order_id: order_id,
+                group: None,
+            });
+            self.linked_cancels.push(LinkedCancel { order_id: link.sibling, cause_order_id: order_id });
+        }
+    }
+
+    // Cancels every other member of the group `order_id` belongs to
+    fn cancel_oca_group(&mut self, order_id: OrderId) {
+        let Some(group_id) = self.oca_group_of.remove(&order_id) else {
+            return;
+        };
+        self.fired_oca_groups.remove(&group_id);
+        for member in self.oca_groups.remove(&group_id).unwrap_or_default() {
+            if member == order_id {
+                continue;
+            }
+            self.oca_group_of.remove(&member);
+            if self.remove_order(member).is_ok() {
+                self.emit(Event::LinkedCancelled {
+                    order_id: member,
+                    cause_order_id: order_id,
+                    group: Some(group_id.clone()),
+                });
+                self.linked_cancels.push(LinkedCancel { order_id: member, cause_order_id: order_id });
+            }
+        }
+    }
+
+    // Drops a cancelled order from its group, the rest of the group stays live
+    pub(crate) fn leave_oca_group(&mut self, order_id: OrderId) {
+        let Some(group_id) = self.oca_group_of.remove(&order_id) else {
+            return;
+        };
+        if let Some(members) = self.oca_groups.get_mut(&group_id) {
+            members.retain(|m| *m != order_id);
+            if members.is_empty() {
+                self.oca_groups.remove(&group_id);
+                self.fired_oca_groups.remove(&group_id);
+            }
+        }
+    }
+
+    // Lets linked orders react to resting orders that traded, true if any did
+    pub(crate) fn process_maker_fills(&mut self) -> bool {
+        let mut maker_fills = std::mem::take(&mut self.maker_fills);
+        for f in &maker_fills {
+            self.on_linked_execution(f.order_id, f.qty, f.done);
+        }
+        let any = !maker_fills.is_empty();
+        // Handed back for the next match to fill, unless linked orders traded meanwhile
+        if self.maker_fills.is_empty() {
+            maker_fills.clear();
+            self.maker_fills = maker_fills;
+        }
+        any
+    }
+
+    // Orders cancelled through a link or group since the previous call
+    pub fn take_linked_cancels(&mut self) -> Vec<LinkedCancel> {
+        std::mem::take(&mut self.linked_cancels)
+    }
+}
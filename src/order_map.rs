@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+use crate::OrderId;
+
+// Map keyed by order id, looked up on every add, cancel and maker fill
+pub(crate) type OrderIdMap<V> = HashMap<OrderId, V, BuildHasherDefault<OrderIdHasher>>;
+
+// Orders expected to rest at once when the config does not say
+pub(crate) const DEFAULT_EXPECTED_ORDERS: usize = 5000;
+
+// Hashes an order id with a single multiply. Ids are a counter the book controls, so
+// there is no one to pick keys that collide and SipHash buys nothing. The odd constant
+// spreads the counter's low bits into the high ones the table also looks at. Public
+// so that benchmarks can set it against other hashers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrderIdHasher(u64);
+
+const SEED: u64 = 0x517c_c1b7_2722_0a95;
+
+impl Hasher for OrderIdHasher {
+    fn write_u64(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(SEED);
+    }
+
+    // Only reached by keys that are not an OrderId
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.write_u64(u64::from(b));
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
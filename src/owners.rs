@@ -0,0 +1,42 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{AccountId, OrderBook, OrderId, OrderInfo, Side};
+
+// owner -> ids of its orders resting in the book
+pub(crate) type OwnerIndex = HashMap<AccountId, HashSet<OrderId>>;
+
+pub(crate) fn index_owner(index: &mut OwnerIndex, owner: Option<AccountId>, order_id: OrderId) {
+    if let Some(owner) = owner {
+        index.entry(owner).or_default().insert(order_id);
+    }
+}
+
+pub(crate) fn unindex_owner(index: &mut OwnerIndex, owner: Option<AccountId>, order_id: OrderId) {
+    let Some(owner) = owner else {
+        return;
+    };
+    if let Some(order_ids) = index.get_mut(&owner) {
+        order_ids.remove(&order_id);
+        if order_ids.is_empty() {
+            index.remove(&owner);
+        }
+    }
+}
+
+impl OrderBook {
+    // Every order `owner` has resting in the book, bids then asks, each in priority order
+    pub fn orders_for(&self, owner: AccountId) -> Vec<OrderInfo> {
+        let Some(order_ids) = self.owner_orders.get(&owner) else {
+            return Vec::new();
+        };
+        let mut orders: Vec<OrderInfo> = order_ids.iter().filter_map(|id| self.get_order(*id)).collect();
+        orders.sort_by_key(|o| {
+            let price_priority = match o.side {
+                Side::Bid => u64::MAX - o.price.ticks(),
+                Side::Ask => o.price.ticks(),
+            };
+            (o.side == Side::Ask, price_priority, o.queue_position)
+        });
+        orders
+    }
+}
@@ -0,0 +1,372 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
+
+use crate::{Error, OrderBook, RejectReason};
+
+// How the integer prices of a book map to decimal ones: a price of n ticks is worth
+// n * tick_size * 10^exponent. The default has every tick worth 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PriceScale {
+    pub tick_size: u64,
+    pub exponent: i32,
+}
+
+impl Default for PriceScale {
+    fn default() -> PriceScale {
+        PriceScale { tick_size: 1, exponent: 0 }
+    }
+}
+
+// What to do with a decimal price between two ticks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TickRounding {
+    Reject,
+    // To the nearest tick, halfway goes up
+    HalfUp,
+    // To the tick below
+    Down,
+    // To the tick above
+    Up,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PriceError {
+    // Not a plain decimal number like 12 or 12.05
+    Malformed,
+    Negative,
+    // Between two ticks and rounding was not asked for
+    OffTick,
+    // More ticks than a u64 holds, or a tick size of 0
+    OutOfRange,
+}
+
+impl fmt::Display for PriceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PriceError::Malformed => "Price is not a decimal number!",
+            PriceError::Negative => "Price is negative!",
+            PriceError::OffTick => "Price is not a multiple of the tick size!",
+            PriceError::OutOfRange => "Price is out of range!",
+        })
+    }
+}
+
+impl PriceScale {
+    // Price of `ticks` ticks on this scale
+    pub fn price(&self, ticks: u64) -> Price {
+        Price { ticks, scale: *self }
+    }
+
+    // Reads a decimal like "101.25" as a whole number of ticks
+    pub fn parse(&self, s: &str, rounding: TickRounding) -> Result<Price, PriceError> {
+        if s.starts_with('-') {
+            return Err(PriceError::Negative);
+        }
+        let (whole, frac) = s.split_once('.').unwrap_or((s, ""));
+        let digits = || whole.chars().chain(frac.chars());
+        if (whole.is_empty() && frac.is_empty()) || !digits().all(|c| c.is_ascii_digit()) {
+            return Err(PriceError::Malformed);
+        }
+        // Leading and trailing zeros change nothing but could overflow the mantissa
+        let frac = frac.trim_end_matches('0');
+        let mut mantissa: u128 = 0;
+        for c in whole.trim_start_matches('0').chars().chain(frac.chars()) {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(u128::from(c as u8 - b'0')))
+                .ok_or(PriceError::OutOfRange)?;
+        }
+        let frac_digits = i32::try_from(frac.len()).map_err(|_| PriceError::OutOfRange)?;
+        self.round_to_ticks(mantissa, frac_digits, rounding)
+    }
+
+    // Like parse, from the shortest decimal that reads back as `value`
+    pub fn parse_f64(&self, value: f64, rounding: TickRounding) -> Result<Price, PriceError> {
+        if value.is_nan() || value.is_infinite() {
+            return Err(PriceError::Malformed);
+        }
+        if value < 0.0 {
+            return Err(PriceError::Negative);
+        }
+        self.parse(&format!("{}", value), rounding)
+    }
+
+    // Like parse, from a Decimal
+    #[cfg(feature = "decimal")]
+    pub fn from_decimal(&self, value: Decimal, rounding: TickRounding) -> Result<Price, PriceError> {
+        if value.is_sign_negative() && !value.is_zero() {
+            return Err(PriceError::Negative);
+        }
+        let frac_digits = i32::try_from(value.scale()).map_err(|_| PriceError::OutOfRange)?;
+        self.round_to_ticks(value.mantissa().unsigned_abs(), frac_digits, rounding)
+    }
+
+    // Ticks on this scale of a price, which can be on another scale. Prices on this
+    // one are returned as they are.
+    pub fn ticks(&self, price: Price, rounding: TickRounding) -> Result<u64, PriceError> {
+        if price.scale == *self {
+            return Ok(price.ticks);
+        }
+        let frac_digits = price.scale.exponent.checked_neg().ok_or(PriceError::OutOfRange)?;
+        Ok(self.round_to_ticks(price.mantissa(), frac_digits, rounding)?.ticks)
+    }
+
+    // Ticks in mantissa * 10^-frac_digits
+    fn round_to_ticks(&self, mantissa: u128, frac_digits: i32, rounding: TickRounding) -> Result<Price, PriceError> {
+        if self.tick_size == 0 {
+            return Err(PriceError::OutOfRange);
+        }
+        let shift = self.exponent.checked_add(frac_digits).ok_or(PriceError::OutOfRange)?;
+        let (dividend, divisor) = if shift >= 0 {
+            match pow10(shift).and_then(|p| p.checked_mul(u128::from(self.tick_size))) {
+                Some(divisor) => (mantissa, divisor),
+                // Worth less than one tick
+                None => (mantissa, u128::MAX),
+            }
+        } else {
+            let dividend = pow10(-shift).and_then(|p| p.checked_mul(mantissa)).ok_or(PriceError::OutOfRange)?;
+            (dividend, u128::from(self.tick_size))
+        };
+        let (mut ticks, rem) = (dividend / divisor, dividend % divisor);
+        if rem != 0 {
+            match rounding {
+                TickRounding::Reject => return Err(PriceError::OffTick),
+                TickRounding::HalfUp if rem >= divisor - rem => ticks += 1,
+                TickRounding::HalfUp | TickRounding::Down => {}
+                TickRounding::Up => ticks += 1,
+            }
+        }
+        let ticks = u64::try_from(ticks).map_err(|_| PriceError::OutOfRange)?;
+        Ok(self.price(ticks))
+    }
+}
+
+fn pow10(n: i32) -> Option<u128> {
+    10u128.checked_pow(u32::try_from(n).ok()?)
+}
+
+// A price in ticks along with the scale that gives it its decimal value.
+// Prices compare by that value, whatever their scales. A plain integer is a price
+// of that many whole units, which a book with a different scale takes at its value.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Price {
+    ticks: u64,
+    scale: PriceScale,
+}
+
+impl Price {
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn scale(&self) -> PriceScale {
+        self.scale
+    }
+
+    // Nearest f64 to the decimal value
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    // The exact decimal value, None if a Decimal can't hold it
+    #[cfg(feature = "decimal")]
+    pub fn to_decimal(&self) -> Option<Decimal> {
+        let exponent = self.scale.exponent;
+        let mantissa = match u32::try_from(exponent) {
+            Ok(exponent) => 10u128.checked_pow(exponent)?.checked_mul(self.mantissa())?,
+            Err(_) => self.mantissa(),
+        };
+        let decimals = exponent.min(0).unsigned_abs();
+        Decimal::try_from_i128_with_scale(i128::try_from(mantissa).ok()?, decimals).ok()
+    }
+
+    // ticks * tick_size, the value in units of 10^exponent
+    fn mantissa(&self) -> u128 {
+        u128::from(self.ticks) * u128::from(self.scale.tick_size)
+    }
+}
+
+impl PartialEq for Price {
+    fn eq(&self, other: &Price) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Price {}
+
+impl From<u64> for Price {
+    fn from(units: u64) -> Price {
+        PriceScale::default().price(units)
+    }
+}
+
+impl PartialEq<u64> for Price {
+    fn eq(&self, units: &u64) -> bool {
+        *self == Price::from(*units)
+    }
+}
+
+// By value like Eq, so the mantissa loses its trailing zeros to the exponent first
+impl Hash for Price {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let (mut mantissa, mut exponent) = (self.mantissa(), i64::from(self.scale.exponent));
+        if mantissa == 0 {
+            exponent = 0;
+        }
+        while mantissa != 0 && mantissa % 10 == 0 {
+            mantissa /= 10;
+            exponent += 1;
+        }
+        (mantissa, exponent).hash(state);
+    }
+}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Price) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Price) -> Ordering {
+        let (a, b) = (self.mantissa(), other.mantissa());
+        if a == 0 || b == 0 {
+            return a.cmp(&b);
+        }
+        // Bring the one with the larger exponent down to the other's. If that overflows
+        // it is larger than anything a u128 holds, the other side included.
+        let (ea, eb) = (self.scale.exponent, other.scale.exponent);
+        let shift = (i64::from(ea) - i64::from(eb)).unsigned_abs();
+        let scaled = |m: u128| u32::try_from(shift).ok().and_then(|s| 10u128.checked_pow(s)).and_then(|p| m.checked_mul(p));
+        match ea.cmp(&eb) {
+            Ordering::Equal => a.cmp(&b),
+            Ordering::Greater => scaled(a).map_or(Ordering::Greater, |a| a.cmp(&b)),
+            Ordering::Less => scaled(b).map_or(Ordering::Less, |b| a.cmp(&b)),
+        }
+    }
+}
+
+// The decimal value with as many decimals as the scale has, 1.50 for 150 ticks of 0.01
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa().to_string();
+        let exponent = self.scale.exponent;
+        if exponent >= 0 {
+            let zeros = if digits == "0" { 0 } else { exponent as usize };
+            return write!(f, "{}{}", digits, "0".repeat(zeros));
+        }
+        let decimals = exponent.unsigned_abs() as usize;
+        let digits = format!("{:0>width$}", digits, width = decimals + 1);
+        let (whole, frac) = digits.split_at(digits.len() - decimals);
+        write!(f, "{}.{}", whole, frac)
+    }
+}
+
+// Exact average price of a set of fills, `notional` (price times qty summed over them)
+// divided by `qty`. Two averages compare by value, 3/2 equals 6/4.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AvgPrice {
+    pub notional: u128,
+    pub qty: u64,
+}
+
+impl AvgPrice {
+    // None for no qty, which has no average
+    pub fn new(notional: u128, qty: u64) -> Option<AvgPrice> {
+        (qty > 0).then_some(AvgPrice { notional, qty })
+    }
+
+    // Whole ticks and the remainder, which is below qty
+    fn split(&self) -> (u128, u128) {
+        let qty = u128::from(self.qty);
+        (self.notional / qty, self.notional % qty)
+    }
+
+    // Nearest f64, for display. The whole part and the fraction are converted apart so
+    // a large notional loses no more than the f64 itself can't hold.
+    pub fn to_f64(&self) -> f64 {
+        let (whole, rem) = self.split();
+        whole as f64 + rem as f64 / self.qty as f64
+    }
+
+    // The average in units of 10^-decimals of a tick, halfway rounds up. None if that
+    // does not fit a u128.
+    pub fn to_fixed(&self, decimals: u32) -> Option<u128> {
+        let (whole, rem) = self.split();
+        let scale = 10u128.checked_pow(decimals)?;
+        let qty = u128::from(self.qty);
+        // rem * scale can overflow where the result does not, so go one digit at a time
+        let mut frac: u128 = 0;
+        let mut rem = rem;
+        for _ in 0..decimals {
+            rem *= 10;
+            frac = frac * 10 + rem / qty;
+            rem %= qty;
+        }
+        let round_up = rem >= qty - rem;
+        whole.checked_mul(scale)?.checked_add(frac)?.checked_add(u128::from(round_up))
+    }
+
+    // On the book's price scale, rounded to the nearest tick with halfway going up
+    pub fn to_price(&self, scale: PriceScale) -> Option<Price> {
+        let ticks = u64::try_from(self.to_fixed(0)?).ok()?;
+        Some(scale.price(ticks))
+    }
+}
+
+impl PartialEq for AvgPrice {
+    fn eq(&self, other: &AvgPrice) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for AvgPrice {}
+
+impl PartialOrd for AvgPrice {
+    fn partial_cmp(&self, other: &AvgPrice) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AvgPrice {
+    fn cmp(&self, other: &AvgPrice) -> Ordering {
+        // Remainders are below their qty so the cross products fit a u128
+        let ((a, ra), (b, rb)) = (self.split(), other.split());
+        a.cmp(&b).then_with(|| (ra * u128::from(other.qty)).cmp(&(rb * u128::from(self.qty))))
+    }
+}
+
+impl OrderBook {
+    // A price of `ticks` of the book, by the price scale it is configured with
+    pub fn price(&self, ticks: u64) -> Price {
+        self.config.price_scale.price(ticks)
+    }
+
+    // A decimal price on the book's scale, e.g. to pass to add_limit_order
+    pub fn parse_price(&self, s: &str, rounding: TickRounding) -> Result<Price, Error> {
+        Ok(self.config.price_scale.parse(s, rounding)?)
+    }
+
+    // Ticks of the book an order's price comes to, which has to be a whole number of them
+    pub(crate) fn order_ticks(&self, price: Price) -> Result<u64, RejectReason> {
+        self.config.price_scale.ticks(price, TickRounding::Reject).map_err(|e| match e {
+            PriceError::OffTick => RejectReason::OffTick,
+            _ => RejectReason::PriceOutOfRange,
+        })
+    }
+
+    // Ticks of the book nearest `price` in the direction given, saturating, for a
+    // price bounding a query
+    pub(crate) fn ticks_toward(&self, price: Price, rounding: TickRounding) -> u64 {
+        self.config.price_scale.ticks(price, rounding).unwrap_or(u64::MAX)
+    }
+}
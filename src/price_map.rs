@@ -0,0 +1,256 @@
+use std::collections::{btree_map, BTreeMap};
+use std::ops::{Bound, RangeBounds};
+
+// Prices from `low` up to `low + ticks - 1` a book's levels are kept in an array for,
+// indexed by the price itself. Finding the next price in use is a scan of a bitset
+// instead of a walk down a tree, which pays off for instruments trading in a narrow
+// band of densely used ticks. Prices outside it are still taken, as with no ladder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PriceLadder {
+    pub low: u64,
+    pub ticks: usize,
+}
+
+// Level slot of every price in use on one side, iterated in price order. With a ladder
+// the prices on it are kept there and the rest in the tree, so the two never overlap
+// and the tree's prices below the ladder come before it and those above after.
+#[derive(Debug, Clone)]
+pub(crate) struct PriceMap {
+    ladder: Option<Ladder>,
+    tree: BTreeMap<u64, usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Ladder {
+    low: u64,
+    // Price and level slot of `low + i` at i. The price is kept so iteration can
+    // hand out references to it like the tree does.
+    slots: Vec<Option<(u64, usize)>>,
+    // Bit i set when slots[i] is used
+    occupied: Vec<u64>,
+}
+
+impl Ladder {
+    fn new(ladder: PriceLadder) -> Ladder {
+        Ladder {
+            low: ladder.low,
+            slots: vec![None; ladder.ticks],
+            occupied: vec![0; ladder.ticks.div_ceil(64)],
+        }
+    }
+
+    // Highest price on the ladder
+    fn high(&self) -> u64 {
+        self.low.saturating_add(self.slots.len() as u64 - 1)
+    }
+
+    fn index(&self, price: u64) -> Option<usize> {
+        let i = usize::try_from(price.checked_sub(self.low)?).ok()?;
+        (i < self.slots.len()).then_some(i)
+    }
+
+    // First used slot in from..to
+    fn next_used(&self, from: usize, to: usize) -> Option<usize> {
+        if from >= to {
+            return None;
+        }
+        let mut word = from / 64;
+        let mut bits = self.occupied[word] & (!0u64 << (from % 64));
+        loop {
+            if bits != 0 {
+                let i = word * 64 + bits.trailing_zeros() as usize;
+                return (i < to).then_some(i);
+            }
+            word += 1;
+            if word * 64 >= to {
+                return None;
+            }
+            bits = self.occupied[word];
+        }
+    }
+
+    // Last used slot in from..to
+    fn prev_used(&self, from: usize, to: usize) -> Option<usize> {
+        if from >= to {
+            return None;
+        }
+        let last = to - 1;
+        let mut word = last / 64;
+        let mut bits = self.occupied[word] & (!0u64 >> (63 - last % 64));
+        loop {
+            if bits != 0 {
+                let i = word * 64 + 63 - bits.leading_zeros() as usize;
+                return (i >= from).then_some(i);
+            }
+            if word * 64 <= from {
+                return None;
+            }
+            word -= 1;
+            bits = self.occupied[word];
+        }
+    }
+}
+
+impl PriceMap {
+    pub fn new(ladder: Option<PriceLadder>) -> PriceMap {
+        PriceMap {
+            ladder: ladder.filter(|l| l.ticks > 0).map(Ladder::new),
+            tree: BTreeMap::new(),
+        }
+    }
+
+    // Moves every price over to a map with `ladder`, keeping their slots
+    pub fn rebuild(&mut self, ladder: Option<PriceLadder>) {
+        let entries: Vec<(u64, usize)> = self.iter().map(|(p, u)| (*p, *u)).collect();
+        *self = PriceMap::new(ladder);
+        for (price, u) in entries {
+            self.insert(price, u);
+        }
+    }
+
+    pub fn get(&self, price: &u64) -> Option<&usize> {
+        match &self.ladder {
+            Some(ladder) => match ladder.index(*price) {
+                Some(i) => ladder.slots[i].as_ref().map(|(_, u)| u),
+                None => self.tree.get(price),
+            },
+            None => self.tree.get(price),
+        }
+    }
+
+    pub fn insert(&mut self, price: u64, level_idx: usize) {
+        let Some((ladder, i)) = self.on_ladder(price) else {
+            self.tree.insert(price, level_idx);
+            return;
+        };
+        ladder.slots[i] = Some((price, level_idx));
+        ladder.occupied[i / 64] |= 1 << (i % 64);
+    }
+
+    pub fn remove(&mut self, price: &u64) -> Option<usize> {
+        let Some((ladder, i)) = self.on_ladder(*price) else {
+            return self.tree.remove(price);
+        };
+        ladder.occupied[i / 64] &= !(1 << (i % 64));
+        ladder.slots[i].take().map(|(_, u)| u)
+    }
+
+    // The ladder and the slot `price` has on it, if it has one
+    fn on_ladder(&mut self, price: u64) -> Option<(&mut Ladder, usize)> {
+        let ladder = self.ladder.as_mut()?;
+        let i = ladder.index(price)?;
+        Some((ladder, i))
+    }
+
+    pub fn iter(&self) -> Range<'_> {
+        self.range(..)
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &u64> {
+        self.iter().map(|(p, _)| p)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &usize> {
+        self.iter().map(|(_, u)| u)
+    }
+
+    // Prices in `range` and their slots, in price order either way round
+    pub fn range(&self, range: impl RangeBounds<u64>) -> Range<'_> {
+        let low = match range.start_bound() {
+            Bound::Included(&p) => Some(p),
+            Bound::Excluded(&p) => p.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let high = match range.end_bound() {
+            Bound::Included(&p) => Some(p),
+            Bound::Excluded(&p) => p.checked_sub(1),
+            Bound::Unbounded => Some(u64::MAX),
+        };
+        let (Some(low), Some(high)) = (low, high) else {
+            return self.empty_range();
+        };
+        if low > high {
+            return self.empty_range();
+        }
+        let Some(ladder) = &self.ladder else {
+            return Range {
+                below: self.tree.range(low..=high),
+                ladder: LadderRange { ladder: None, front: 0, back: 0 },
+                above: self.tree.range(0..0),
+            };
+        };
+        let (ladder_low, ladder_high) = (ladder.low, ladder.high());
+        let below = match ladder_low.checked_sub(1) {
+            Some(below_high) if low <= below_high => self.tree.range(low..=high.min(below_high)),
+            _ => self.tree.range(0..0),
+        };
+        let above = match ladder_high.checked_add(1) {
+            Some(above_low) if high >= above_low => self.tree.range(low.max(above_low)..=high),
+            _ => self.tree.range(0..0),
+        };
+        let (front, back) = if low <= ladder_high && high >= ladder_low {
+            ((low.max(ladder_low) - ladder_low) as usize, (high.min(ladder_high) - ladder_low) as usize + 1)
+        } else {
+            (0, 0)
+        };
+        Range { below, ladder: LadderRange { ladder: Some(ladder), front, back }, above }
+    }
+
+    fn empty_range(&self) -> Range<'_> {
+        Range {
+            below: self.tree.range(0..0),
+            ladder: LadderRange { ladder: None, front: 0, back: 0 },
+            above: self.tree.range(0..0),
+        }
+    }
+}
+
+// Prices of a PriceMap in order, the tree's below the ladder, then the ladder's, then
+// the tree's above it
+pub(crate) struct Range<'a> {
+    below: btree_map::Range<'a, u64, usize>,
+    ladder: LadderRange<'a>,
+    above: btree_map::Range<'a, u64, usize>,
+}
+
+struct LadderRange<'a> {
+    ladder: Option<&'a Ladder>,
+    // Slots front..back are left to hand out
+    front: usize,
+    back: usize,
+}
+
+impl<'a> Iterator for LadderRange<'a> {
+    type Item = (&'a u64, &'a usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ladder = self.ladder?;
+        let i = ladder.next_used(self.front, self.back)?;
+        self.front = i + 1;
+        ladder.slots[i].as_ref().map(|(p, u)| (p, u))
+    }
+}
+
+impl<'a> DoubleEndedIterator for LadderRange<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let ladder = self.ladder?;
+        let i = ladder.prev_used(self.front, self.back)?;
+        self.back = i;
+        ladder.slots[i].as_ref().map(|(p, u)| (p, u))
+    }
+}
+
+impl<'a> Iterator for Range<'a> {
+    type Item = (&'a u64, &'a usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.below.next().or_else(|| self.ladder.next()).or_else(|| self.above.next())
+    }
+}
+
+impl<'a> DoubleEndedIterator for Range<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.above.next_back().or_else(|| self.ladder.next_back()).or_else(|| self.below.next_back())
+    }
+}
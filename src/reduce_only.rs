@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use crate::{AccountId, Event, FillResult, OrderBook, OrderId, OrderKind, OrderOptions, Price, RejectReason, Side, TimeInForce};
+
+// Reduce-only order shrunk because its account's position no longer covers it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReduceOnlyResize {
+    pub order_id: OrderId,
+    pub owner: AccountId,
+    pub old_qty: u64,
+    // 0 if the order was cancelled, the position is flat or flipped
+    pub new_qty: u64,
+}
+
+// Applies a fill of `qty` on side `s` to the account's signed net position
+pub(crate) fn update_position(positions: &mut HashMap<AccountId, i64>, owner: AccountId, s: Side, qty: u64) {
+    let position = positions.entry(owner).or_insert(0);
+    let qty = i64::try_from(qty).unwrap_or(i64::MAX);
+    match s {
+        Side::Bid => *position = position.saturating_add(qty),
+        Side::Ask => *position = position.saturating_sub(qty),
+    }
+}
+
+// Most an order on side `s` can trade without flipping or increasing the position
+pub(crate) fn reducible_qty(positions: &HashMap<AccountId, i64>, owner: AccountId, s: Side) -> u64 {
+    let position = positions.get(&owner).copied().unwrap_or(0);
+    match s {
+        Side::Bid if position < 0 => position.unsigned_abs(),
+        Side::Ask if position > 0 => position.unsigned_abs(),
+        _ => 0,
+    }
+}
+
+impl OrderBook {
+    // Signed net position of `owner` from its fills, positive when long
+    pub fn position(&self, owner: AccountId) -> i64 {
+        self.positions.get(&owner).copied().unwrap_or(0)
+    }
+
+    // Limit order for `owner` that may only bring its position towards flat.
+    // It is rejected if it can't reduce the position at all and cut down to the position
+    // if it is larger. While it rests it keeps shrinking with the position, and it is
+    // cancelled once the position is flat or flipped. Each reduce-only order is held
+    // against the whole position on its own, resizes are reported by take_reduce_only_resizes.
+    pub fn add_reduce_only_order(
+        &mut self,
+        owner: AccountId,
+        s: Side,
+        price: impl Into<Price>,
+        order_qty: u64,
+    ) -> (OrderId, FillResult) {
+        let order_id = self.new_order_id();
+        let price = match self.limit_ticks(OrderKind::ReduceOnly, price.into(), order_qty) {
+            Ok(price) => price,
+            Err(rejected) => return (order_id, rejected),
+        };
+        let reducible = reducible_qty(&self.positions, owner, s);
+        if reducible == 0 {
+            let reason = RejectReason::ReduceOnlyWouldIncrease;
+            self.emit(Event::OrderRejected { kind: OrderKind::ReduceOnly, price: Some(self.price(price)), qty: order_qty, reason });
+            return (order_id, FillResult::rejected(order_qty, reason));
+        }
+
+        let mut qty = order_qty;
+        if qty > reducible {
+            self.emit(Event::ReduceOnlyResized { order_id, old_qty: qty, new_qty: reducible });
+            self.reduce_only_resizes.push(ReduceOnlyResize {
+                order_id,
+                owner,
+                old_qty: qty,
+                new_qty: reducible,
+            });
+            qty = reducible;
+        }
+
+        let options = OrderOptions { owner: Some(owner), reduce_only: true, ..Default::default() };
+        let fill_result = self.execute_limit_order(order_id, s, price, qty, TimeInForce::GoodTillCancel, options);
+        if fill_result.resting_price.is_some() {
+            self.reduce_only_orders.push(order_id);
+        }
+        self.process_triggers();
+        (order_id, fill_result)
+    }
+
+    // Reduce-only orders resized or cancelled since the previous call, at entry or while resting
+    pub fn take_reduce_only_resizes(&mut self) -> Vec<ReduceOnlyResize> {
+        std::mem::take(&mut self.reduce_only_resizes)
+    }
+
+    // Shrinks every resting reduce-only order in place to what its owner's position still allows
+    pub(crate) fn resize_reduce_only_orders(&mut self) {
+        self.reduce_only_orders.retain(|id| self.order_loc.contains_key(id));
+
+        let mut cancelled = false;
+        for order_id in self.reduce_only_orders.clone() {
+            let (side, _, _) = self.order_loc[&order_id];
+            let Some(o) = self.resting_order(order_id) else {
+                continue;
+            };
+            let Some(owner) = o.owner else {
+                continue;
+            };
+            let resting_qty = o.qty + o.reserve_qty;
+            let reducible = reducible_qty(&self.positions, owner, side);
+            if resting_qty <= reducible {
+                continue;
+            }
+
+            self.emit(Event::ReduceOnlyResized { order_id, old_qty: resting_qty, new_qty: reducible });
+            if reducible == 0 {
+                let _ = self.remove_order(order_id);
+                cancelled = true;
+            } else {
+                self.shrink_resting_order(order_id, reducible);
+            }
+            self.reduce_only_resizes.push(ReduceOnlyResize {
+                order_id,
+                owner,
+                old_qty: resting_qty,
+                new_qty: reducible,
+            });
+        }
+
+        if cancelled {
+            self.reduce_only_orders.retain(|id| self.order_loc.contains_key(id));
+            self.update_bbo();
+        }
+    }
+}
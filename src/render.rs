@@ -0,0 +1,80 @@
+use std::fmt;
+use std::fmt::Write;
+
+use crate::depth::signed_spread;
+use crate::{DepthLevel, OrderBook};
+
+// Levels per side Display shows
+const DISPLAY_DEPTH: usize = 10;
+
+// Column widths of a ladder, wide enough for its largest value and its header
+struct Widths {
+    price: usize,
+    qty: usize,
+    orders: usize,
+}
+
+fn write_level(out: &mut String, w: &Widths, side: &str, l: &DepthLevel, best: bool) {
+    let (pw, qw, ow) = (w.price, w.qty, w.orders);
+    let _ = write!(out, "{:<4}  {:>pw$}  {:>qw$}  {:>ow$}", side, l.price, l.qty, l.order_count);
+    if best {
+        let _ = write!(out, "  <- best {}", side);
+    }
+    out.push('\n');
+}
+
+impl OrderBook {
+    // Ladder of the top `depth` displayed levels of each side, asks above bids with the
+    // prices descending throughout, as decimals on the book's price scale, and the best
+    // level of each side marked. The spread is in ticks:
+    //
+    //   XYZ
+    //   side  price  qty  orders
+    //   ask     105   30       1
+    //   ask     104   10       2  <- best ask
+    //   ---- spread 4 ----
+    //   bid     100    5       1  <- best bid
+    pub fn render(&self, depth: usize) -> String {
+        let book = self.depth(depth);
+        let width = |header: &str, value: fn(&DepthLevel) -> String| {
+            let widest = book.asks.iter().chain(&book.bids).map(|l| value(l).len()).max();
+            widest.unwrap_or(0).max(header.len())
+        };
+        let w = Widths {
+            price: width("price", |l| l.price.to_string()),
+            qty: width("qty", |l| l.qty.to_string()),
+            orders: width("orders", |l| l.order_count.to_string()),
+        };
+
+        let mut out = String::new();
+        let _ = writeln!(out, "{}", self.symbol);
+        let (pw, qw, ow) = (w.price, w.qty, w.orders);
+        let _ = writeln!(out, "side  {:>pw$}  {:>qw$}  {:>ow$}", "price", "qty", "orders");
+        if book.asks.is_empty() {
+            out.push_str("(no asks)\n");
+        }
+        for (i, l) in book.asks.iter().enumerate().rev() {
+            write_level(&mut out, &w, "ask", l, i == 0);
+        }
+        match (book.asks.first(), book.bids.first()) {
+            (Some(ask), Some(bid)) => {
+                let _ = writeln!(out, "---- spread {} ----", signed_spread(bid.price.ticks(), ask.price.ticks()));
+            }
+            _ => out.push_str("---- no spread ----\n"),
+        }
+        for (i, l) in book.bids.iter().enumerate() {
+            write_level(&mut out, &w, "bid", l, i == 0);
+        }
+        if book.bids.is_empty() {
+            out.push_str("(no bids)\n");
+        }
+        out
+    }
+}
+
+// Same ladder as render, DISPLAY_DEPTH levels deep
+impl fmt::Display for OrderBook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(DISPLAY_DEPTH))
+    }
+}
@@ -0,0 +1,98 @@
+use crate::owners::index_owner;
+use crate::{BookConfig, HalfBook, Order, OrderBook, OrderIdMap, PeggedOrder, Price, PriceScale, Side, TickRounding};
+
+// One resting order with everything needed to put it back in the same place
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnapshotOrder {
+    pub side: Side,
+    pub price: Price,
+    // Orders ahead of it at its price
+    pub queue_position: usize,
+    // Ticks from the best unpegged price for pegged orders
+    pub peg_offset: Option<i64>,
+    pub order: Order,
+}
+
+impl SnapshotOrder {
+    // Qty still resting, iceberg reserve included
+    pub fn remaining_qty(&self) -> u64 {
+        self.order.qty + self.order.reserve_qty
+    }
+}
+
+// Level 3 view of the resting limit book
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BookSnapshot {
+    pub symbol: String,
+    // Restored along with the orders, so they rest and match under the same settings
+    pub config: BookConfig,
+    // Sequence number of the last change to the book before it was taken
+    pub seq: u64,
+    // Id of the last order the book gave an id to, so a restored book never reuses one
+    pub last_order_id: u64,
+    // Bids then asks, each from the best price outward in queue order
+    pub orders: Vec<SnapshotOrder>,
+}
+
+impl HalfBook {
+    fn snapshot_orders(&self, peg_offsets: &OrderIdMap<i64>, scale: PriceScale, orders: &mut Vec<SnapshotOrder>) {
+        let prices: Box<dyn Iterator<Item = (&u64, &usize)>> = match self.s {
+            Side::Bid => Box::new(self.price_map.iter().rev()),
+            Side::Ask => Box::new(self.price_map.iter()),
+        };
+        for (price, u) in prices {
+            for (queue_position, o) in self.price_levels[*u].iter().enumerate() {
+                let peg_offset = peg_offsets.get(&o.order_id).copied();
+                orders.push(SnapshotOrder { side: self.s, price: scale.price(*price), queue_position, peg_offset, order: o.clone() });
+            }
+        }
+    }
+}
+
+impl OrderBook {
+    // Every order resting in the limit book, in priority order, and the config.
+    // Stops, midpoint and on-close orders and links between orders are not included.
+    pub fn snapshot(&self) -> BookSnapshot {
+        let peg_offsets: OrderIdMap<i64> = self.pegged_orders.iter().map(|p| (p.order_id, p.offset)).collect();
+        let mut orders = Vec::new();
+        self.bid_book.snapshot_orders(&peg_offsets, self.config.price_scale, &mut orders);
+        self.ask_book.snapshot_orders(&peg_offsets, self.config.price_scale, &mut orders);
+        BookSnapshot {
+            symbol: self.symbol.clone(),
+            config: self.config.clone(),
+            seq: self.last_seq,
+            last_order_id: self.last_order_id,
+            orders,
+        }
+    }
+
+    // Rebuilds a book whose resting orders match exactly like those the snapshot was taken of,
+    // configured the same. Its sequence numbers and order ids carry on from the snapshot's.
+    // Prices are taken on the config's scale, to the nearest tick for one edited off it.
+    pub fn from_snapshot(snapshot: BookSnapshot) -> OrderBook {
+        let mut book = OrderBook::new(snapshot.symbol);
+        book.set_config(snapshot.config);
+        book.last_seq = snapshot.seq;
+        book.last_order_id = snapshot.last_order_id;
+        let mut orders = snapshot.orders;
+        orders.sort_by_key(|o| o.queue_position);
+        for SnapshotOrder { side, price, peg_offset, order, .. } in orders {
+            let price = book.ticks_toward(price, TickRounding::HalfUp);
+            let half_book = match side {
+                Side::Ask => &mut book.ask_book,
+                Side::Bid => &mut book.bid_book,
+            };
+            let order_id = order.order_id;
+            index_owner(&mut book.owner_orders, order.owner, order_id);
+            let (level, pos) = half_book.push_order(price, order);
+            book.order_loc.insert(order_id, (side, level, pos));
+            if let Some(offset) = peg_offset {
+                book.pegged_orders.push(PeggedOrder { order_id, side, offset, price });
+            }
+        }
+        book.update_bbo();
+        book
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{HalfBook, OrderBook, Price, PriceScale};
+
+// Running totals since the book was created
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Counters {
+    pub orders_accepted: u64,
+    pub orders_cancelled: u64,
+    pub trades: u64,
+    pub matched_qty: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SideStats {
+    // Levels with at least one resting order, hidden ones included
+    pub level_count: usize,
+    pub order_count: usize,
+    pub displayed_qty: u64,
+    pub best_price: Option<Price>,
+    // Furthest resting price from the best one
+    pub worst_price: Option<Price>,
+    // Level slots the side holds memory for, free ones included. Stays flat while the
+    // number of prices in use does.
+    pub allocated_levels: usize,
+    // Order slots the side's levels hold, gaps left by orders taken out of the middle
+    // of a queue included
+    pub order_slots: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BookStats {
+    pub bids: SideStats,
+    pub asks: SideStats,
+    // Limit, market, midpoint and on-close orders the book took, triggered stops
+    // counted as they enter the book
+    pub orders_accepted: u64,
+    pub orders_cancelled: u64,
+    // One per resting order an incoming order, uncross or midpoint cross traded with
+    pub trades: u64,
+    pub matched_qty: u64,
+    // Resting orders the book keeps a location for, to find them by id
+    pub tracked_orders: usize,
+}
+
+impl HalfBook {
+    fn stats(&self, now: u64, scale: PriceScale) -> SideStats {
+        let mut stats = SideStats {
+            allocated_levels: self.allocated_levels(),
+            order_slots: self.order_slots(),
+            ..SideStats::default()
+        };
+        for l in self.levels(now, scale) {
+            stats.level_count += 1;
+            stats.order_count += l.resting_orders().count();
+            stats.displayed_qty = stats.displayed_qty.saturating_add(l.qty);
+            stats.best_price.get_or_insert(l.price);
+            stats.worst_price = Some(l.price);
+        }
+        stats
+    }
+}
+
+impl OrderBook {
+    pub fn stats(&self) -> BookStats {
+        let now = self.clock.now();
+        BookStats {
+            bids: self.bid_book.stats(now, self.config.price_scale),
+            asks: self.ask_book.stats(now, self.config.price_scale),
+            orders_accepted: self.counters.orders_accepted,
+            orders_cancelled: self.counters.orders_cancelled,
+            trades: self.counters.trades,
+            matched_qty: self.counters.matched_qty,
+            tracked_orders: self.order_loc.len(),
+        }
+    }
+}
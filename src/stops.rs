@@ -0,0 +1,300 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use crate::{CancelReport, FillResult, OrderId, PriceScale, Side};
+
+// Stops buy when the price rises to the trigger, market-if-touched orders buy when it falls to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TriggerKind {
+    Stop,
+    MarketIfTouched,
+}
+
+// Price a stop or MIT order compares its trigger price against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TriggerSource {
+    #[default]
+    LastTrade,
+    BestBid,
+    BestAsk,
+    Midpoint,
+}
+
+impl TriggerSource {
+    const ALL: [TriggerSource; 4] = [
+        TriggerSource::LastTrade,
+        TriggerSource::BestBid,
+        TriggerSource::BestAsk,
+        TriggerSource::Midpoint,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+// Current value of every trigger source, None where there is none
+// (no trade yet, an empty side) so it can never trigger anything
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TriggerPrices {
+    pub last_trade: Option<u64>,
+    pub best_bid: Option<u64>,
+    pub best_ask: Option<u64>,
+    pub midpoint: Option<u64>,
+}
+
+impl TriggerPrices {
+    pub fn get(&self, source: TriggerSource) -> Option<u64> {
+        match source {
+            TriggerSource::LastTrade => self.last_trade,
+            TriggerSource::BestBid => self.best_bid,
+            TriggerSource::BestAsk => self.best_ask,
+            TriggerSource::Midpoint => self.midpoint,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StopOrder {
+    pub order_id: OrderId,
+    pub side: Side,
+    pub kind: TriggerKind,
+    pub trigger_source: TriggerSource,
+    // Trigger price, for market-if-touched orders as well
+    pub stop_price: u64,
+    // Stop-limit orders enter the book as a limit at this price, plain stops as a market order
+    pub limit_price: Option<u64>,
+    pub qty: u64,
+}
+
+// Distance a trailing stop keeps from the best price it has observed
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrailingOffset {
+    Ticks(u64),
+    // Percentage of the observed best price, e.g. 2.5 for 2.5%
+    Percent(f64),
+}
+
+impl TrailingOffset {
+    fn ticks_from(&self, reference_price: u64) -> u64 {
+        match self {
+            TrailingOffset::Ticks(n) => *n,
+            TrailingOffset::Percent(pct) => (reference_price as f64 * pct / 100.0).round() as u64,
+        }
+    }
+}
+
+// Sell trailing stops follow the highest best bid seen, buy trailing stops the lowest best ask
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrailingStopOrder {
+    pub order_id: OrderId,
+    pub side: Side,
+    pub offset: TrailingOffset,
+    pub qty: u64,
+    // Best observed price so far, None until the tracked side of the book has a price
+    pub reference_price: Option<u64>,
+}
+
+impl TrailingStopOrder {
+    pub fn stop_price(&self) -> Option<u64> {
+        let reference_price = self.reference_price?;
+        let offset = self.offset.ticks_from(reference_price);
+        match self.side {
+            Side::Ask => Some(reference_price.saturating_sub(offset)),
+            Side::Bid => Some(reference_price.saturating_add(offset)),
+        }
+    }
+
+    // Ratchets the reference price towards the market, it never moves back
+    fn observe(&mut self, best_bid: Option<u64>, best_ask: Option<u64>) {
+        let (observed, better): (Option<u64>, fn(u64, u64) -> bool) = match self.side {
+            Side::Ask => (best_bid, |new, old| new > old),
+            Side::Bid => (best_ask, |new, old| new < old),
+        };
+        if let Some(p) = observed {
+            if self.reference_price.is_none_or(|r| better(p, r)) {
+                self.reference_price = Some(p);
+            }
+        }
+    }
+
+    fn is_triggered(&self, best_bid: Option<u64>, best_ask: Option<u64>) -> bool {
+        match (&self.side, self.stop_price()) {
+            (Side::Ask, Some(stop)) => best_bid.is_some_and(|p| p <= stop),
+            (Side::Bid, Some(stop)) => best_ask.is_some_and(|p| p >= stop),
+            (_, None) => false,
+        }
+    }
+}
+
+// Result of a stop that was converted to a market or limit order
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TriggeredStop {
+    pub order_id: OrderId,
+    pub fill_result: FillResult,
+}
+
+impl StopOrder {
+    // Buy stops and sell market-if-touched orders wait for the price to rise to
+    // their trigger, the other two for it to fall to it
+    fn triggers_on_rise(&self) -> bool {
+        matches!(
+            (self.side, self.kind),
+            (Side::Bid, TriggerKind::Stop) | (Side::Ask, TriggerKind::MarketIfTouched)
+        )
+    }
+}
+
+// trigger price -> ids in arrival order, by the direction the price has to move
+#[derive(Debug, Clone, Default)]
+struct TriggerLevels {
+    rising: BTreeMap<u64, VecDeque<OrderId>>,
+    falling: BTreeMap<u64, VecDeque<OrderId>>,
+}
+
+// Stop and market-if-touched orders waiting for their trigger, kept out of the visible HalfBooks
+#[derive(Debug, Clone)]
+pub(crate) struct StopBook {
+    orders: HashMap<OrderId, StopOrder>,
+    // one per trigger source
+    triggers: [TriggerLevels; 4],
+    // in arrival order
+    trailing_stops: Vec<TrailingStopOrder>,
+}
+
+impl StopBook {
+    pub fn new() -> StopBook {
+        StopBook {
+            orders: HashMap::new(),
+            triggers: Default::default(),
+            trailing_stops: Vec::new(),
+        }
+    }
+
+    fn triggers_mut(&mut self, order: &StopOrder) -> &mut BTreeMap<u64, VecDeque<OrderId>> {
+        let levels = &mut self.triggers[order.trigger_source.index()];
+        if order.triggers_on_rise() {
+            &mut levels.rising
+        } else {
+            &mut levels.falling
+        }
+    }
+
+    pub fn insert(&mut self, order: StopOrder) {
+        let stops = self.triggers_mut(&order);
+        stops.entry(order.stop_price).or_default().push_back(order.order_id);
+        self.orders.insert(order.order_id, order);
+    }
+
+    pub fn insert_trailing(&mut self, order: TrailingStopOrder) {
+        self.trailing_stops.push(order);
+    }
+
+    pub fn contains(&self, order_id: OrderId) -> bool {
+        self.orders.contains_key(&order_id)
+    }
+
+    // Qty of a waiting stop, so it can be resized in place
+    pub fn qty_mut(&mut self, order_id: OrderId) -> Option<&mut u64> {
+        self.orders.get_mut(&order_id).map(|o| &mut o.qty)
+    }
+
+    // Cancels the stop or trailing stop with this id, None if there is none.
+    // The report's limit price is on `scale`.
+    pub fn cancel(&mut self, order_id: OrderId, now: u64, scale: PriceScale) -> Option<CancelReport> {
+        if let Some(i) = self.trailing_stops.iter().position(|t| t.order_id == order_id) {
+            let t = self.trailing_stops.remove(i);
+            return Some(CancelReport {
+                order_id: t.order_id,
+                owner: None,
+                tag: None,
+                side: t.side,
+                price: None,
+                cancelled_qty: t.qty,
+                previously_filled_qty: 0,
+                entered_at: None,
+                cancelled_at: now,
+            });
+        }
+
+        let order = self.orders.remove(&order_id)?;
+        let stops = self.triggers_mut(&order);
+        if let Some(ids) = stops.get_mut(&order.stop_price) {
+            ids.retain(|id| *id != order_id);
+            if ids.is_empty() {
+                stops.remove(&order.stop_price);
+            }
+        }
+        Some(CancelReport {
+            order_id: order.order_id,
+            owner: None,
+            tag: None,
+            side: order.side,
+            price: order.limit_price.map(|p| scale.price(p)),
+            cancelled_qty: order.qty,
+            previously_filled_qty: 0,
+            entered_at: None,
+            cancelled_at: now,
+        })
+    }
+
+    // Removes and returns every order its trigger source has reached.
+    // Buy stops and sell MITs trigger when the source >= trigger price, sell stops
+    // and buy MITs when the source <= trigger price. Sources are taken in declaration
+    // order, within one the orders the price crossed first come first, rising
+    // triggers before falling ones, then arrival order.
+    pub fn take_triggered(&mut self, prices: TriggerPrices) -> Vec<StopOrder> {
+        let mut triggered = Vec::new();
+        for source in TriggerSource::ALL {
+            let Some(price) = prices.get(source) else {
+                continue;
+            };
+            let levels = &mut self.triggers[source.index()];
+            let rising_prices: Vec<u64> = levels.rising
+                .range(..=price)
+                .map(|(p, _)| *p)
+                .collect();
+            let falling_prices: Vec<u64> = levels.falling
+                .range(price..)
+                .rev()
+                .map(|(p, _)| *p)
+                .collect();
+
+            for p in rising_prices {
+                for id in levels.rising.remove(&p).unwrap_or_default() {
+                    triggered.extend(self.orders.remove(&id));
+                }
+            }
+            for p in falling_prices {
+                for id in levels.falling.remove(&p).unwrap_or_default() {
+                    triggered.extend(self.orders.remove(&id));
+                }
+            }
+        }
+        triggered
+    }
+
+    // Moves every trailing stop's reference to the current BBO, then removes and
+    // returns those whose stop price the market has come back through
+    pub fn take_triggered_trailing(
+        &mut self,
+        best_bid: Option<u64>,
+        best_ask: Option<u64>,
+    ) -> Vec<TrailingStopOrder> {
+        for t in self.trailing_stops.iter_mut() {
+            t.observe(best_bid, best_ask);
+        }
+
+        let (triggered, waiting) = std::mem::take(&mut self.trailing_stops)
+            .into_iter()
+            .partition(|t| t.is_triggered(best_bid, best_ask));
+        self.trailing_stops = waiting;
+        triggered
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{update_position, AccountId, Error, Event, OrderBook, OrderId, OrderKind, Price, RejectReason, Side, TickRounding};
+
+// Trades the tape keeps unless the book is configured otherwise
+const DEFAULT_TRADE_TAPE_LEN: usize = 10_000;
+
+// Execution printed on the trade tape
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trade {
+    pub trade_id: u64,
+    // Sequence number of the change to the book that recorded it
+    pub seq: u64,
+    pub price: Price,
+    pub qty: u64,
+    // Orders on each side, None for block trades
+    pub buy_order_id: Option<OrderId>,
+    pub sell_order_id: Option<OrderId>,
+    pub buyer: Option<AccountId>,
+    pub seller: Option<AccountId>,
+    // Tags the orders on each side were submitted with
+    pub buy_tag: Option<u64>,
+    pub sell_tag: Option<u64>,
+    // Side of the incoming order that took liquidity. None when nobody did, for
+    // block trades, auction uncrosses and midpoint orders crossing each other.
+    pub aggressor_side: Option<Side>,
+    // Clock time the trade was recorded at
+    pub timestamp: u64,
+    // Negotiated off the book and only reported to it
+    pub block: bool,
+}
+
+impl Trade {
+    // Resting order that provided liquidity, if the trade had an aggressor
+    pub fn maker_order_id(&self) -> Option<OrderId> {
+        match self.aggressor_side? {
+            Side::Bid => self.sell_order_id,
+            Side::Ask => self.buy_order_id,
+        }
+    }
+
+    // Incoming order that took liquidity, if the trade had an aggressor
+    pub fn taker_order_id(&self) -> Option<OrderId> {
+        match self.aggressor_side? {
+            Side::Bid => self.buy_order_id,
+            Side::Ask => self.sell_order_id,
+        }
+    }
+}
+
+// One side of a trade as the tape records it
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Counterparty {
+    pub order_id: Option<OrderId>,
+    pub owner: Option<AccountId>,
+    pub tag: Option<u64>,
+}
+
+// How far a reported price may be from the last trade price
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PriceBand {
+    Ticks(u64),
+    // Percentage of the last trade price, e.g. 5.0 for 5%
+    Percent(f64),
+}
+
+impl PriceBand {
+    fn contains(&self, reference_price: u64, price: u64) -> bool {
+        let max_deviation = match self {
+            PriceBand::Ticks(n) => *n,
+            PriceBand::Percent(pct) => (reference_price as f64 * pct / 100.0).round() as u64,
+        };
+        price.abs_diff(reference_price) <= max_deviation
+    }
+}
+
+impl OrderBook {
+    // Records a privately negotiated trade on the tape without matching or moving the BBO.
+    // The price and qty must pass the book's order limits, and the price must be inside
+    // the configured band around the last trade price. Before the first trade the band
+    // is around the configured reference price or else the midpoint, with neither the
+    // trade is refused. It only triggers stops if the book is configured to let block
+    // trades do so. Both positions move, which may resize the accounts' reduce-only orders.
+    pub fn report_block_trade(
+        &mut self,
+        price: impl Into<Price>,
+        qty: u64,
+        buyer: AccountId,
+        seller: AccountId,
+    ) -> Result<Trade, Error> {
+        let price = price.into();
+        let checked = self.order_ticks(price).and_then(|ticks| {
+            self.check_block_trade(ticks, qty)?;
+            Ok(ticks)
+        });
+        let price = match checked {
+            Ok(ticks) => ticks,
+            Err(reason) => {
+                self.emit(Event::OrderRejected { kind: OrderKind::BlockTrade, price: Some(price), qty, reason });
+                return Err(Error::Rejected(reason));
+            }
+        };
+
+        let buy = Counterparty { owner: Some(buyer), ..Default::default() };
+        let sell = Counterparty { owner: Some(seller), ..Default::default() };
+        let trade = self.record_trade(price, qty, buy, sell, None, true);
+        self.emit(Event::BlockTradeReported { trade_id: trade.trade_id, price: trade.price, qty });
+        update_position(&mut self.positions, buyer, Side::Bid, qty);
+        update_position(&mut self.positions, seller, Side::Ask, qty);
+        self.resize_reduce_only_orders();
+        if self.config.block_trades_trigger_stops {
+            self.stop_trigger_price = Some(price);
+            self.process_triggers();
+        }
+        Ok(trade)
+    }
+
+    fn check_block_trade(&self, price: u64, qty: u64) -> Result<(), RejectReason> {
+        self.check_order_limits(Some(price), qty)?;
+        let Some(band) = self.config.price_band else {
+            return Ok(());
+        };
+        let configured = self.config.band_reference_price.map(|p| self.ticks_toward(p, TickRounding::HalfUp));
+        let reference_price = self.last_trade_price.or(configured).or_else(|| self.midpoint());
+        match reference_price {
+            Some(reference_price) if band.contains(reference_price, price) => Ok(()),
+            Some(_) => Err(RejectReason::OutsidePriceBand),
+            None => Err(RejectReason::NoBandReference),
+        }
+    }
+
+    // Records a match of the incoming order on side `aggressor_side` against a resting one
+    pub(crate) fn record_match(
+        &mut self,
+        price: u64,
+        qty: u64,
+        aggressor_side: Side,
+        maker: Counterparty,
+        taker: Counterparty,
+    ) -> Trade {
+        let (buy, sell) = match aggressor_side {
+            Side::Bid => (taker, maker),
+            Side::Ask => (maker, taker),
+        };
+        self.record_trade(price, qty, buy, sell, Some(aggressor_side), false)
+    }
+
+    pub(crate) fn record_trade(
+        &mut self,
+        price: u64,
+        qty: u64,
+        buy: Counterparty,
+        sell: Counterparty,
+        aggressor_side: Option<Side>,
+        block: bool,
+    ) -> Trade {
+        let seq = self.next_seq();
+        self.last_trade_id += 1;
+        self.last_trade_price = Some(price);
+        self.last_trade_qty = qty;
+        self.session_high = Some(self.session_high.map_or(price, |p| p.max(price)));
+        self.session_low = Some(self.session_low.map_or(price, |p| p.min(price)));
+        self.session_volume = self.session_volume.saturating_add(qty);
+        let trade = Trade {
+            trade_id: self.last_trade_id,
+            seq,
+            price: self.price(price),
+            qty,
+            buy_order_id: buy.order_id,
+            sell_order_id: sell.order_id,
+            buyer: buy.owner,
+            seller: sell.owner,
+            buy_tag: buy.tag,
+            sell_tag: sell.tag,
+            aggressor_side,
+            timestamp: self.clock.now(),
+            block,
+        };
+        if let Some(candles) = self.candles.as_mut() {
+            candles.on_trade(&trade);
+        }
+        if let Some(trade_stats) = self.trade_stats.as_mut() {
+            trade_stats.on_trade(&trade);
+        }
+        self.trade_tape.push_back(trade.clone());
+        self.trim_trade_tape();
+        self.emit(Event::Trade(trade.clone()));
+        trade
+    }
+
+    // Forgets the oldest trades beyond the configured tape length
+    pub(crate) fn trim_trade_tape(&mut self) {
+        let capacity = self.config.trade_tape_len.unwrap_or(DEFAULT_TRADE_TAPE_LEN);
+        while self.trade_tape.len() > capacity {
+            self.trade_tape.pop_front();
+        }
+    }
+
+    // Every trade still on the tape, oldest first
+    pub fn trade_tape(&self) -> impl DoubleEndedIterator<Item = &Trade> + ExactSizeIterator {
+        self.trade_tape.iter()
+    }
+
+    // Trades recorded after `seq`, oldest first. Trades already dropped from the tape
+    // are missing, whose first trade's seq tells if there is a gap.
+    pub fn trades_since(&self, seq: u64) -> impl DoubleEndedIterator<Item = &Trade> + ExactSizeIterator {
+        let start = self.trade_tape.partition_point(|t| t.seq <= seq);
+        self.trade_tape.range(start..)
+    }
+
+    // Up to the `n` latest trades, oldest first
+    pub fn recent_trades(&self, n: usize) -> impl DoubleEndedIterator<Item = &Trade> + ExactSizeIterator {
+        self.trade_tape.range(self.trade_tape.len().saturating_sub(n)..)
+    }
+
+    // Price of the latest trade, block trades included
+    pub fn last_trade_price(&self) -> Option<Price> {
+        self.last_trade_price.map(|p| self.price(p))
+    }
+
+    // Qty of the latest trade, 0 before the first
+    pub fn last_trade_qty(&self) -> u64 {
+        self.last_trade_qty
+    }
+
+    // Highest price traded so far, block trades included
+    pub fn session_high(&self) -> Option<Price> {
+        self.session_high.map(|p| self.price(p))
+    }
+
+    pub fn session_low(&self) -> Option<Price> {
+        self.session_low.map(|p| self.price(p))
+    }
+
+    // Qty traded so far, block trades included
+    pub fn session_volume(&self) -> u64 {
+        self.session_volume
+    }
+}
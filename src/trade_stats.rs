@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+
+use crate::{OrderBook, Price, Trade};
+
+// Trades the rolling figures of TradeStats are over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StatsWindow {
+    // The latest n trades
+    Trades(usize),
+    // Trades less than this many clock ticks older than the latest one
+    Time(u64),
+}
+
+// Session and rolling VWAP, volume and range of the trades it was fed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TradeStats {
+    window: StatsWindow,
+    session_notional: u128,
+    session_volume: u128,
+    session_high: Option<Price>,
+    session_low: Option<Price>,
+    // (timestamp, notional, qty) of the trades inside the window, oldest first
+    rolling: VecDeque<(u64, u128, u128)>,
+    rolling_notional: u128,
+    rolling_volume: u128,
+}
+
+impl TradeStats {
+    pub fn new(window: StatsWindow) -> TradeStats {
+        TradeStats {
+            window,
+            session_notional: 0,
+            session_volume: 0,
+            session_high: None,
+            session_low: None,
+            rolling: VecDeque::new(),
+            rolling_notional: 0,
+            rolling_volume: 0,
+        }
+    }
+
+    // Adds a trade and drops those that fell out of the window, trades come in time order
+    pub fn on_trade(&mut self, trade: &Trade) {
+        let (qty, notional) = (trade.qty as u128, trade.price.ticks() as u128 * trade.qty as u128);
+        self.session_notional += notional;
+        self.session_volume += qty;
+        self.session_high = Some(self.session_high.map_or(trade.price, |p| p.max(trade.price)));
+        self.session_low = Some(self.session_low.map_or(trade.price, |p| p.min(trade.price)));
+
+        self.rolling.push_back((trade.timestamp, notional, qty));
+        self.rolling_notional += notional;
+        self.rolling_volume += qty;
+        while let Some(&(timestamp, notional, qty)) = self.rolling.front() {
+            let evict = match self.window {
+                StatsWindow::Trades(n) => self.rolling.len() > n,
+                StatsWindow::Time(ticks) => trade.timestamp.saturating_sub(timestamp) >= ticks,
+            };
+            if !evict {
+                break;
+            }
+            self.rolling.pop_front();
+            self.rolling_notional -= notional;
+            self.rolling_volume -= qty;
+        }
+    }
+
+    // Volume weighted average price of every trade, None before the first
+    pub fn session_vwap(&self) -> Option<f64> {
+        (self.session_volume > 0).then(|| self.session_notional as f64 / self.session_volume as f64 * self.tick_value())
+    }
+
+    // VWAP of the trades in the window as of the latest trade
+    pub fn rolling_vwap(&self) -> Option<f64> {
+        (self.rolling_volume > 0).then(|| self.rolling_notional as f64 / self.rolling_volume as f64 * self.tick_value())
+    }
+
+    // Decimal value of one tick of the book the trades came from, the notionals are in ticks
+    fn tick_value(&self) -> f64 {
+        self.session_high.map_or(1.0, |p| p.scale().price(1).to_f64())
+    }
+
+    pub fn session_volume(&self) -> u128 {
+        self.session_volume
+    }
+
+    pub fn rolling_volume(&self) -> u128 {
+        self.rolling_volume
+    }
+
+    pub fn session_high(&self) -> Option<Price> {
+        self.session_high
+    }
+
+    pub fn session_low(&self) -> Option<Price> {
+        self.session_low
+    }
+}
+
+impl OrderBook {
+    // Keeps trade stats over `window` for every trade the book records from now on,
+    // block trades included. Replaces the stats kept so far.
+    pub fn enable_trade_stats(&mut self, window: StatsWindow) {
+        self.trade_stats = Some(TradeStats::new(window));
+    }
+
+    pub fn trade_stats(&self) -> Option<&TradeStats> {
+        self.trade_stats.as_ref()
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{Clock, Event, OrderBook, OrderId, OrderKind, OrderOptions, Price, Side, TimeInForce};
+
+// Order type each TWAP slice is entered as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TwapChild {
+    // Rests any unfilled part of the slice at this price
+    Limit(Price),
+    // Unfilled part of the slice is discarded
+    Market,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TwapStatus {
+    Working,
+    Filled,
+    // Every slice was sent and nothing rests any more, but the parent did not fill
+    Finished,
+    Cancelled,
+}
+
+// Parent order split into `num_slices` children sent `interval` nanoseconds apart,
+// the first one when the parent is created. It only drives the book through `poll`,
+// so the caller decides when simulated time moves forward.
+#[derive(Debug)]
+pub struct TwapOrder {
+    order_id: OrderId,
+    side: Side,
+    qty: u64,
+    num_slices: u64,
+    interval: u64,
+    child: TwapChild,
+    clock: Box<dyn Clock>,
+    start: u64,
+    slices_sent: u64,
+    filled_qty: u64,
+    // (child id, resting qty when last seen) of children still in the book
+    resting_children: Vec<(OrderId, u64)>,
+    status: TwapStatus,
+}
+
+impl TwapOrder {
+    // The parent takes its id from `book`, the one it will be polled with
+    pub fn new(
+        book: &mut OrderBook,
+        side: Side,
+        qty: u64,
+        num_slices: u64,
+        interval: u64,
+        child: TwapChild,
+        clock: impl Clock + 'static,
+    ) -> TwapOrder {
+        let start = clock.now();
+        TwapOrder {
+            order_id: book.new_order_id(),
+            side,
+            qty,
+            num_slices: num_slices.max(1),
+            interval,
+            child,
+            clock: Box::new(clock),
+            start,
+            slices_sent: 0,
+            filled_qty: 0,
+            resting_children: Vec::new(),
+            status: TwapStatus::Working,
+        }
+    }
+
+    pub fn order_id(&self) -> OrderId {
+        self.order_id
+    }
+
+    pub fn status(&self) -> TwapStatus {
+        self.status
+    }
+
+    // Qty filled across all children so far
+    pub fn filled_qty(&self) -> u64 {
+        self.filled_qty
+    }
+
+    // Picks up fills of resting children, then sends every slice that is due by now
+    pub fn poll(&mut self, book: &mut OrderBook) {
+        if self.status != TwapStatus::Working {
+            return;
+        }
+        self.refresh_fills(book);
+
+        let now = self.clock.now();
+        while self.filled_qty < self.qty && self.slices_sent < self.num_slices {
+            let due = self.start.saturating_add(self.slices_sent.saturating_mul(self.interval));
+            if due > now {
+                break;
+            }
+            self.send_slice(book);
+            self.slices_sent += 1;
+        }
+        self.update_status();
+    }
+
+    // Stops sending slices and cancels every child still resting in the book
+    pub fn cancel(&mut self, book: &mut OrderBook) {
+        if self.status != TwapStatus::Working {
+            return;
+        }
+        self.refresh_fills(book);
+        for (child_id, _) in std::mem::take(&mut self.resting_children) {
+            let _ = book.cancel_order(child_id);
+        }
+        self.status = TwapStatus::Cancelled;
+    }
+
+    // Scheduled qty of the next slice, the first slices take what integer division
+    // leaves over, capped so filled plus resting never exceeds the parent qty
+    fn next_slice_qty(&self) -> u64 {
+        let base = self.qty / self.num_slices;
+        let extra = u64::from(self.slices_sent < self.qty % self.num_slices);
+        let resting: u64 = self.resting_children.iter().map(|(_, q)| q).sum();
+        (base + extra).min(self.qty - self.filled_qty - resting)
+    }
+
+    fn send_slice(&mut self, book: &mut OrderBook) {
+        let qty = self.next_slice_qty();
+        if qty == 0 {
+            return;
+        }
+        let child_id = book.new_order_id();
+        book.emit(Event::TwapSlice {
+            order_id: self.order_id,
+            slice: self.slices_sent + 1,
+            num_slices: self.num_slices,
+            qty,
+        });
+        let fill_result = match self.child {
+            TwapChild::Limit(price) => match book.limit_ticks(OrderKind::Limit, price, qty) {
+                Ok(price) => book.execute_limit_order(
+                    child_id,
+                    self.side,
+                    price,
+                    qty,
+                    TimeInForce::GoodTillCancel,
+                    OrderOptions::default(),
+                ),
+                Err(rejected) => rejected,
+            },
+            TwapChild::Market => book.execute_market_order(child_id, self.side, qty),
+        };
+        let resting_qty = book.resting_qty(child_id).unwrap_or(0);
+        self.filled_qty += qty - fill_result.remaining_qty;
+        if resting_qty > 0 {
+            self.resting_children.push((child_id, resting_qty));
+        }
+
+        book.process_triggers();
+        // Triggers run by this slice may already have traded against the rest of it
+        self.refresh_fills(book);
+    }
+
+    // A child that left the book without being cancelled by us was filled
+    fn refresh_fills(&mut self, book: &OrderBook) {
+        let mut filled = 0;
+        self.resting_children.retain_mut(|(child_id, last_qty)| {
+            let resting_qty = book.resting_qty(*child_id).unwrap_or(0);
+            filled += *last_qty - resting_qty;
+            *last_qty = resting_qty;
+            resting_qty > 0
+        });
+        self.filled_qty += filled;
+    }
+
+    fn update_status(&mut self) {
+        if self.filled_qty >= self.qty {
+            self.status = TwapStatus::Filled;
+        } else if self.slices_sent == self.num_slices && self.resting_children.is_empty() {
+            self.status = TwapStatus::Finished;
+        }
+    }
+}
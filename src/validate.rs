@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use crate::{OrderBook, OrderId, Side};
+
+// An invariant of the book that does not hold, with the order or price it broke for
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConsistencyError {
+    // price_map points past the end of price_levels
+    LevelOutOfBounds { side: Side, price: u64, level_idx: usize },
+    // The price recorded for a level is not the one pointing at it
+    MispricedLevel { side: Side, price: u64, level_idx: usize },
+    // Orders rest in a level no price points at, so nothing can reach them
+    UnpricedLevel { side: Side, level_idx: usize },
+    // order_loc points at a level or position the order is not in, or a level since freed
+    MissingOrder { order_id: OrderId, side: Side, level_idx: usize },
+    // The same id rests more than once
+    DuplicateOrder { order_id: OrderId, count: usize },
+    // A resting order order_loc does not point at
+    UntrackedOrder { order_id: OrderId, side: Side, price: u64 },
+    ZeroQty { order_id: OrderId, side: Side, price: u64 },
+    // A price still points at a level every order left, which should have been freed
+    EmptyLevel { side: Side, price: u64 },
+    // A free level slot some price still points at
+    FreeLevelInUse { side: Side, level_idx: usize },
+    // The cached best price disagrees with the best displayed level
+    StaleBestPrice { side: Side, cached: Option<u64>, actual: Option<u64> },
+    // Outside an auction the best bid is never at or above the best ask
+    Crossed { best_bid: u64, best_ask: u64 },
+}
+
+impl OrderBook {
+    // Checks the invariants matching relies on and returns every one that is broken
+    pub fn validate(&self) -> Result<(), Vec<ConsistencyError>> {
+        let mut errors = Vec::new();
+        for book in [&self.bid_book, &self.ask_book] {
+            let side = book.s;
+            // Whether some price points at the slot. Done without a lookup table so a
+            // consistent book is checked without allocating.
+            let priced = |level_idx: usize| {
+                book.level_prices.get(level_idx).is_some_and(|price| book.price_map.get(price) == Some(&level_idx))
+            };
+            for (&price, &level_idx) in book.price_map.iter() {
+                let Some(level) = book.price_levels.get(level_idx) else {
+                    errors.push(ConsistencyError::LevelOutOfBounds { side, price, level_idx });
+                    continue;
+                };
+                if book.level_prices.get(level_idx) != Some(&price) {
+                    errors.push(ConsistencyError::MispricedLevel { side, price, level_idx });
+                }
+                if level.is_empty() {
+                    errors.push(ConsistencyError::EmptyLevel { side, price });
+                }
+                for (pos, o) in level.positions() {
+                    let order_id = o.order_id;
+                    if self.order_loc.get(&order_id) != Some(&(side, level.handle(level_idx), pos)) {
+                        errors.push(ConsistencyError::UntrackedOrder { order_id, side, price });
+                    }
+                    if o.qty == 0 {
+                        errors.push(ConsistencyError::ZeroQty { order_id, side, price });
+                    }
+                }
+            }
+            for &level_idx in &book.free_levels {
+                if level_idx >= book.price_levels.len() || priced(level_idx) {
+                    errors.push(ConsistencyError::FreeLevelInUse { side, level_idx });
+                }
+            }
+            for (level_idx, level) in book.price_levels.iter().enumerate() {
+                if !priced(level_idx) && !level.is_empty() {
+                    errors.push(ConsistencyError::UnpricedLevel { side, level_idx });
+                }
+            }
+        }
+        // order_loc can only point at one of the places an id rests twice, the others
+        // are untracked. Only then are the resting ids counted.
+        if errors.iter().any(|e| matches!(e, ConsistencyError::UntrackedOrder { .. })) {
+            self.find_duplicates(&mut errors);
+        }
+
+        for (order_id, &(side, level, pos)) in &self.order_loc {
+            let book = match side {
+                Side::Ask => &self.ask_book,
+                Side::Bid => &self.bid_book,
+            };
+            let found = book.level(level).and_then(|l| l.get(pos)).is_some_and(|o| &o.order_id == order_id);
+            if !found {
+                errors.push(ConsistencyError::MissingOrder { order_id: *order_id, side, level_idx: level.idx });
+            }
+        }
+
+        let (cached_bid, cached_ask) = (self.best_bid_price, self.best_ask_price);
+        let (best_bid, best_ask) = (self.bid_book.best_price(), self.ask_book.best_price());
+        if cached_bid != best_bid {
+            errors.push(ConsistencyError::StaleBestPrice { side: Side::Bid, cached: cached_bid, actual: best_bid });
+        }
+        if cached_ask != best_ask {
+            errors.push(ConsistencyError::StaleBestPrice { side: Side::Ask, cached: cached_ask, actual: best_ask });
+        }
+        if let (Some(best_bid), Some(best_ask)) = (best_bid, best_ask) {
+            if best_bid >= best_ask && !self.in_auction() {
+                errors.push(ConsistencyError::Crossed { best_bid, best_ask });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn find_duplicates(&self, errors: &mut Vec<ConsistencyError>) {
+        let mut counts: HashMap<OrderId, usize> = HashMap::new();
+        for book in [&self.bid_book, &self.ask_book] {
+            for (_, &level_idx) in book.price_map.iter() {
+                for o in book.price_levels.get(level_idx).into_iter().flat_map(|level| level.iter()) {
+                    *counts.entry(o.order_id).or_insert(0) += 1;
+                }
+            }
+        }
+        for (order_id, count) in counts {
+            if count > 1 {
+                errors.push(ConsistencyError::DuplicateOrder { order_id, count });
+            }
+        }
+    }
+
+    // Debug builds with the validate feature panic as soon as an operation leaves the
+    // book inconsistent. Release builds skip the check altogether.
+    pub(crate) fn debug_validate(&self) {
+        #[cfg(all(feature = "validate", debug_assertions))]
+        if let Err(errors) = self.validate() {
+            panic!("Order book is inconsistent: {:?}", errors);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::{HashMap, VecDeque};
+
+use orderbook::{ManualClock, OrderBook, OrderId, Side, SideStats};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// Orders resting at once
+const LIVE: usize = 200;
+// Prices either side picks from at once
+const BAND: u64 = 50;
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+// Live orders at each price of one side, and the most prices in use at once
+#[derive(Default)]
+struct Prices {
+    orders_at: HashMap<u64, usize>,
+    most_in_use: usize,
+}
+
+impl Prices {
+    fn add(&mut self, price: u64) {
+        *self.orders_at.entry(price).or_default() += 1;
+        self.most_in_use = self.most_in_use.max(self.orders_at.len());
+    }
+
+    fn remove(&mut self, price: u64) {
+        let orders = self.orders_at.get_mut(&price).unwrap();
+        *orders -= 1;
+        if *orders == 0 {
+            self.orders_at.remove(&price);
+        }
+    }
+}
+
+// The counters that grow with the memory a side holds, checked against what the
+// orders resting right now need
+fn assert_flat(side: &SideStats, prices: &Prices, cycle: u64) {
+    assert_eq!(side.level_count, prices.orders_at.len(), "after {cycle} cycles");
+    assert!(
+        side.allocated_levels <= prices.most_in_use,
+        "{} level slots for at most {} prices after {cycle} cycles",
+        side.allocated_levels,
+        prices.most_in_use
+    );
+    // Gaps are closed up once they outnumber the orders in a level
+    let max_slots = 2 * side.order_count + side.level_count;
+    assert!(side.order_slots <= max_slots, "{} order slots after {cycle} cycles", side.order_slots);
+}
+
+// Adds an order every cycle and cancels either the oldest or a random one, over a
+// band of prices that drifts up so that thousands of prices are used and freed
+// without the two sides ever crossing.
+fn cycle(cycles: u64) {
+    let mut rng = StdRng::seed_from_u64(61);
+    let mut book = book();
+    let mut live: VecDeque<(OrderId, Side, u64)> = VecDeque::with_capacity(LIVE + 1);
+    let (mut bids, mut asks) = (Prices::default(), Prices::default());
+    for i in 0..cycles {
+        let base = 1_000 + i / 500;
+        let (side, price) = if rng.gen_bool(0.5) {
+            (Side::Bid, base - rng.gen_range(0..BAND))
+        } else {
+            (Side::Ask, base + 10 + rng.gen_range(0..BAND))
+        };
+        let order_id = book.create_new_limit_order(side, price, rng.gen_range(1..10)).unwrap();
+        live.push_back((order_id, side, price));
+        match side {
+            Side::Bid => bids.add(price),
+            Side::Ask => asks.add(price),
+        }
+        if live.len() > LIVE {
+            let idx = if rng.gen_bool(0.5) { 0 } else { rng.gen_range(0..live.len()) };
+            let (order_id, side, price) = live.remove(idx).unwrap();
+            book.cancel_order(order_id).unwrap();
+            match side {
+                Side::Bid => bids.remove(price),
+                Side::Ask => asks.remove(price),
+            }
+        }
+        if i % 10_000 == 0 || i == cycles - 1 {
+            let stats = book.stats();
+            assert_eq!(stats.tracked_orders, live.len(), "after {i} cycles");
+            assert_eq!(stats.bids.order_count + stats.asks.order_count, live.len(), "after {i} cycles");
+            assert_flat(&stats.bids, &bids, i);
+            assert_flat(&stats.asks, &asks, i);
+        }
+    }
+    assert!(bids.most_in_use <= BAND as usize + 1 && asks.most_in_use <= BAND as usize + 1);
+    let stats = book.stats();
+    assert_eq!(stats.orders_cancelled, cycles - LIVE as u64);
+    assert_eq!(stats.trades, 0);
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn counters_stay_flat_over_a_million_add_cancel_cycles() {
+    cycle(1_000_000);
+}
+
+//     cargo test --release --test add_cancel_cycles -- --ignored
+#[test]
+#[ignore]
+fn counters_stay_flat_over_fifty_million_add_cancel_cycles() {
+    cycle(50_000_000);
+}
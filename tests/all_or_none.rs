@@ -0,0 +1,66 @@
+use orderbook::{ManualClock, OrderBook, OrderStatus, RejectReason, Side, TimeInForce};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+#[test]
+fn incoming_order_skips_an_aon_it_cannot_cover() {
+    let mut book = book();
+    let aon = book.add_all_or_none_order(Side::Ask, 100, 50).resting_order_id.unwrap();
+    let small_a = book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    let small_b = book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+
+    // All of it counts as qty at the price, only the small orders are executable
+    assert_eq!(book.qty_at(Side::Ask, 100), 60);
+    assert_eq!(book.executable_qty(Side::Bid, Some(100.into()), 20), 10);
+
+    let fill_result = book.add_limit_order_with_tif(Side::Bid, 100, 10, TimeInForce::ImmediateOrCancel);
+    let makers: Vec<_> = fill_result.filled_orders.iter().map(|f| (f.maker_order_id, f.qty)).collect();
+    assert_eq!(makers, vec![(small_a, 5), (small_b, 5)]);
+    assert_eq!(fill_result.status, OrderStatus::Filled);
+    assert_eq!(book.get_order(aon).unwrap().remaining_qty, 50);
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn incoming_order_covering_an_aon_fills_it_whole() {
+    let mut book = book();
+    let aon = book.add_all_or_none_order(Side::Ask, 100, 50).resting_order_id.unwrap();
+    let fill_result = book.add_limit_order(Side::Bid, 100, 60);
+    assert_eq!(fill_result.filled_orders.len(), 1);
+    assert_eq!((fill_result.filled_orders[0].maker_order_id, fill_result.filled_orders[0].qty), (aon, 50));
+    assert_eq!(fill_result.resting_price, Some(100.into()));
+    assert_eq!(book.qty_at(Side::Bid, 100), 10);
+}
+
+#[test]
+fn incoming_aon_that_would_cross_without_filling_is_rejected() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    let fill_result = book.add_all_or_none_order(Side::Bid, 100, 10);
+    assert_eq!(fill_result.status, OrderStatus::Rejected(RejectReason::AllOrNoneWouldCross));
+    assert!(fill_result.filled_orders.is_empty());
+    assert_eq!((book.best_bid(), book.best_ask()), (None, Some(100.into())));
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn incoming_aon_that_can_fill_completely_matches() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    book.create_new_limit_order(Side::Ask, 101, 5).unwrap();
+    let fill_result = book.add_all_or_none_order(Side::Bid, 101, 10);
+    assert_eq!(fill_result.status, OrderStatus::Filled);
+    assert_eq!(fill_result.total_filled_qty(), 10);
+}
+
+#[test]
+fn incoming_aon_that_does_not_cross_rests() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 5).unwrap();
+    let fill_result = book.add_all_or_none_order(Side::Bid, 100, 10);
+    assert_eq!(fill_result.status, OrderStatus::Created);
+    assert_eq!(book.best_bid(), Some(100.into()));
+    assert_eq!(book.validate(), Ok(()));
+}
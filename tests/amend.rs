@@ -0,0 +1,104 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use orderbook::{Error, Event, ManualClock, OrderBook, OrderId, OrderStatus, RejectReason, Side, TimeInForce};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+#[test]
+fn reducing_qty_keeps_the_place_in_the_queue() {
+    let mut book = book();
+    let first = book.create_new_limit_order(Side::Ask, 100, 10).unwrap();
+    let second = book.create_new_limit_order(Side::Ask, 100, 10).unwrap();
+
+    let amended = book.amend_order(first, 100, 4).unwrap();
+    assert!(amended.kept_priority);
+    let fill_result = book.add_limit_order(Side::Bid, 100, 4);
+    assert_eq!(fill_result.filled_orders[0].maker_order_id, first);
+    assert_eq!(book.get_order(second).unwrap().remaining_qty, 10);
+}
+
+#[test]
+fn growing_qty_goes_to_the_back_of_the_level() {
+    let mut book = book();
+    let first = book.create_new_limit_order(Side::Ask, 100, 10).unwrap();
+    let second = book.create_new_limit_order(Side::Ask, 100, 10).unwrap();
+
+    let amended = book.amend_order(first, 100, 12).unwrap();
+    assert!(!amended.kept_priority);
+    let fill_result = book.add_limit_order(Side::Bid, 100, 10);
+    assert_eq!(fill_result.filled_orders[0].maker_order_id, second);
+    assert_eq!(book.qty_at(Side::Ask, 100), 12);
+}
+
+#[test]
+fn amending_to_a_marketable_price_matches() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 5).unwrap();
+    let bid = book.create_new_limit_order(Side::Bid, 99, 8).unwrap();
+
+    let amended = book.amend_order(bid, 101, 8).unwrap();
+    assert_eq!(amended.fill_result.total_filled_qty(), 5);
+    assert_eq!(amended.fill_result.resting_order_id, Some(bid));
+    assert_eq!(book.get_order(bid).unwrap().remaining_qty, 3);
+    assert_eq!(book.best_bid(), Some(101.into()));
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn re_entry_is_not_counted_as_a_new_order() {
+    let mut book = book();
+    let received = Rc::new(RefCell::new(0));
+    let counter = received.clone();
+    book.on_event(move |_, event| {
+        if matches!(event, Event::OrderReceived { .. }) {
+            *counter.borrow_mut() += 1;
+        }
+    });
+    let id = book.add_limit_order(Side::Bid, 99, 10).resting_order_id.unwrap();
+    let seq = book.last_seq();
+
+    book.amend_order(id, 98, 10).unwrap();
+    // Only the amend itself takes a seq
+    assert_eq!(book.last_seq(), seq + 1);
+    assert_eq!(book.stats().orders_accepted, 1);
+    assert_eq!(*received.borrow(), 1);
+}
+
+#[test]
+fn amending_an_expired_gtd_order_is_refused_and_leaves_it() {
+    let clock = ManualClock::new(1);
+    let mut book = OrderBook::with_clock("TEST".to_string(), clock.clone());
+    let fill_result = book.add_limit_order_with_tif(Side::Bid, 99, 10, TimeInForce::GoodTillDate(100));
+    let id = fill_result.resting_order_id.unwrap();
+    clock.advance(200);
+
+    assert_eq!(book.amend_order(id, 98, 10), Err(Error::Rejected(RejectReason::ExpiryInPast)));
+    assert_eq!(book.qty_at(Side::Bid, 99), 10);
+    let expired = book.expire_orders(201);
+    assert_eq!(expired.len(), 1);
+    assert_eq!(book.get_order(id).unwrap().status, OrderStatus::Expired);
+}
+
+#[test]
+fn amending_an_aon_order_to_cross_is_refused_and_leaves_it() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 5).unwrap();
+    let aon = book.add_all_or_none_order(Side::Bid, 99, 10).resting_order_id.unwrap();
+
+    assert_eq!(book.amend_order(aon, 101, 10), Err(Error::Rejected(RejectReason::AllOrNoneWouldCross)));
+    assert_eq!(book.qty_at(Side::Bid, 99), 10);
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn amending_unknown_or_filled_orders_fails() {
+    let mut book = book();
+    let ask = book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    book.add_limit_order(Side::Bid, 100, 5);
+
+    assert!(matches!(book.amend_order(ask, 100, 5), Err(Error::AlreadyFilled { .. })));
+    assert!(book.amend_order(OrderId(999), 100, 5).is_err());
+}
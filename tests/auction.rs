@@ -0,0 +1,57 @@
+use orderbook::{AccountId, ManualClock, OrderBook, OrderStatus, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+#[test]
+fn uncross_matches_at_the_clearing_price() {
+    let mut book = book();
+    book.start_auction();
+    let bid = book.add_limit_order(Side::Bid, 101, 10).resting_order_id.unwrap();
+    let ask = book.add_limit_order(Side::Ask, 100, 6).resting_order_id.unwrap();
+
+    let report = book.uncross();
+    assert_eq!(report.matched_qty, 6);
+    assert!(report.clearing_price.is_some());
+    assert_eq!(book.get_order(ask).unwrap().status, OrderStatus::Filled);
+    assert_eq!(book.get_order(bid).unwrap().remaining_qty, 4);
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn same_owner_orders_do_not_trade_in_the_uncross() {
+    let mut book = book();
+    book.start_auction();
+    let owner = AccountId(1);
+    let bid = book.add_owned_limit_order(owner, Side::Bid, 101, 10, None).resting_order_id.unwrap();
+    book.add_owned_limit_order(owner, Side::Ask, 100, 10, None);
+
+    let report = book.uncross();
+    assert_eq!(report.matched_qty, 0);
+    assert!(report.fills.is_empty());
+    assert_eq!(book.recent_trades(10).len(), 0);
+    // The incoming bid is cancelled, the book is left uncrossed
+    assert_eq!(book.get_order(bid).unwrap().status, OrderStatus::Cancelled);
+    assert_eq!((book.best_bid(), book.best_ask()), (None, Some(100.into())));
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn bid_fills_other_owners_then_stops_at_its_own_ask() {
+    let mut book = book();
+    book.start_auction();
+    let owner = AccountId(1);
+    let bid = book.add_owned_limit_order(owner, Side::Bid, 101, 10, None).resting_order_id.unwrap();
+    book.add_owned_limit_order(AccountId(2), Side::Ask, 100, 4, None);
+    book.add_owned_limit_order(owner, Side::Ask, 100, 6, None);
+
+    let report = book.uncross();
+    assert_eq!(report.matched_qty, 4);
+    let bid_fills: Vec<_> = report.fills.iter().filter(|f| f.order_id == bid).map(|f| f.qty).collect();
+    assert_eq!(bid_fills, vec![4]);
+    assert_eq!(book.get_order(bid).unwrap().remaining_qty, 0);
+    assert_eq!(book.best_bid(), None);
+    assert_eq!(book.qty_at(Side::Ask, 100), 6);
+    assert_eq!(book.validate(), Ok(()));
+}
@@ -0,0 +1,48 @@
+use orderbook::{AvgPrice, ManualClock, OrderBook, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+#[test]
+fn no_fills_have_no_average() {
+    let mut book = book();
+    let fill_result = book.add_market_order(Side::Bid, 5);
+    assert_eq!(fill_result.avg_fill_price(), None);
+    assert_eq!(fill_result.avg_price_exact(), None);
+    assert_eq!((fill_result.total_filled_qty(), fill_result.total_notional()), (0, 0));
+}
+
+#[test]
+fn single_fill_averages_to_its_price() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    let fill_result = book.add_limit_order(Side::Bid, 100, 2);
+    assert_eq!(fill_result.avg_fill_price(), Some(100.0));
+    assert_eq!((fill_result.total_filled_qty(), fill_result.total_notional()), (2, 200));
+}
+
+#[test]
+fn fills_across_levels_average_by_qty() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 100, 3).unwrap();
+    book.create_new_limit_order(Side::Ask, 103, 5).unwrap();
+    let fill_result = book.add_market_order(Side::Bid, 6);
+    assert_eq!((fill_result.total_filled_qty(), fill_result.total_notional()), (6, 609));
+    assert_eq!(fill_result.avg_fill_price(), Some(101.5));
+    assert_eq!(fill_result.avg_price_exact().unwrap().to_fixed(2), Some(10150));
+}
+
+#[test]
+fn notional_past_u64_max_does_not_overflow() {
+    let mut book = book();
+    let price = u64::MAX / 2;
+    book.create_new_limit_order(Side::Ask, price, 4).unwrap();
+    book.create_new_limit_order(Side::Ask, price + 2, 4).unwrap();
+    let fill_result = book.add_market_order(Side::Bid, 8);
+    let notional = price as u128 * 4 + (price as u128 + 2) * 4;
+    assert_eq!(fill_result.total_notional(), notional);
+    assert_eq!(fill_result.avg_price_exact(), AvgPrice::new(notional, 8));
+    assert_eq!(fill_result.avg_price_exact().unwrap().to_fixed(0), Some(price as u128 + 1));
+    assert_eq!(fill_result.avg_fill_price(), Some((price + 1) as f64));
+}
@@ -0,0 +1,149 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use orderbook::{BookConfig, ManualClock, OrderBook, PriceLadder, Side};
+
+fn book(price_ladder: Option<PriceLadder>) -> OrderBook {
+    let mut book = OrderBook::with_clock("TEST".to_string(), ManualClock::new(1));
+    book.set_config(BookConfig { price_ladder, ..BookConfig::default() });
+    book
+}
+
+// The same tests for the tree and for a ladder over 95..105, so both prices on the
+// ladder and off it on either side are used
+macro_rules! behavior_suite {
+    ($backend:ident, $price_ladder:expr) => {
+        mod $backend {
+            use super::*;
+
+            fn book() -> OrderBook {
+                super::book($price_ladder)
+            }
+
+            #[test]
+            fn best_prices_track_adds_and_cancels() {
+                let mut book = book();
+                let far = book.create_new_limit_order(Side::Bid, 90, 5).unwrap();
+                let near = book.create_new_limit_order(Side::Bid, 99, 5).unwrap();
+                book.create_new_limit_order(Side::Ask, 101, 5).unwrap();
+                book.create_new_limit_order(Side::Ask, 120, 5).unwrap();
+                assert_eq!((book.best_bid(), book.best_ask()), (Some(99.into()), Some(101.into())));
+
+                book.cancel_order(near).unwrap();
+                assert_eq!(book.best_bid(), Some(90.into()));
+                book.cancel_order(far).unwrap();
+                assert_eq!(book.best_bid(), None);
+                assert_eq!(book.validate(), Ok(()));
+            }
+
+            #[test]
+            fn sweep_walks_levels_on_and_off_the_ladder_in_price_order() {
+                let mut book = book();
+                for price in [120, 104, 96, 101, 94] {
+                    book.create_new_limit_order(Side::Ask, price, 2).unwrap();
+                }
+                let fill_result = book.add_limit_order(Side::Bid, 110, 9);
+                let prices: Vec<_> = fill_result.filled_orders.iter().map(|f| (f.price.ticks(), f.qty)).collect();
+                assert_eq!(prices, vec![(94, 2), (96, 2), (101, 2), (104, 2)]);
+                assert_eq!(book.resting_qty(fill_result.resting_order_id.unwrap()), Some(1));
+                assert_eq!((book.best_bid(), book.best_ask()), (Some(110.into()), Some(120.into())));
+            }
+
+            #[test]
+            fn time_priority_within_a_level() {
+                let mut book = book();
+                let first = book.create_new_limit_order(Side::Bid, 100, 3).unwrap();
+                let second = book.create_new_limit_order(Side::Bid, 100, 3).unwrap();
+                let fill_result = book.add_limit_order(Side::Ask, 100, 4);
+                let makers: Vec<_> = fill_result.filled_orders.iter().map(|f| (f.maker_order_id, f.qty)).collect();
+                assert_eq!(makers, vec![(first, 3), (second, 1)]);
+            }
+
+            #[test]
+            fn depth_lists_best_levels_first() {
+                let mut book = book();
+                for (side, price) in [(Side::Bid, 99), (Side::Bid, 80), (Side::Bid, 97), (Side::Ask, 130), (Side::Ask, 102)] {
+                    book.create_new_limit_order(side, price, 1).unwrap();
+                }
+                let depth = book.depth(usize::MAX);
+                let bids: Vec<_> = depth.bids.iter().map(|l| l.price.ticks()).collect();
+                let asks: Vec<_> = depth.asks.iter().map(|l| l.price.ticks()).collect();
+                assert_eq!((bids, asks), (vec![99, 97, 80], vec![102, 130]));
+            }
+
+            #[test]
+            fn cancel_range_spans_the_ladder_edge() {
+                let mut book = book();
+                for price in [92, 95, 98, 104, 106] {
+                    book.create_new_limit_order(Side::Ask, price, 1).unwrap();
+                }
+                book.cancel_range(Side::Ask, 93, 105);
+                assert_eq!(book.levels(Side::Ask).map(|l| l.price).collect::<Vec<_>>(), vec![92, 106]);
+                assert_eq!(book.validate(), Ok(()));
+            }
+        }
+    };
+}
+
+behavior_suite!(tree, None);
+behavior_suite!(ladder, Some(PriceLadder { low: 95, ticks: 10 }));
+
+#[test]
+fn ladder_and_tree_books_stay_identical_under_random_flow() {
+    let ladders = [
+        PriceLadder { low: 95, ticks: 10 },
+        PriceLadder { low: 0, ticks: 1_000 },
+        PriceLadder { low: 100, ticks: 130 },
+        PriceLadder { low: 50, ticks: 64 },
+    ];
+    for (seed, ladder) in ladders.into_iter().enumerate() {
+        let mut rng = StdRng::seed_from_u64(seed as u64);
+        let (mut tree, mut on_ladder) = (book(None), book(Some(ladder)));
+        let mut order_ids = Vec::new();
+        for step in 0..5_000 {
+            let side = if rng.gen_bool(0.5) { Side::Bid } else { Side::Ask };
+            let price = rng.gen_range(80..140);
+            let qty = rng.gen_range(1..20);
+            match rng.gen_range(0..7) {
+                0..=2 => {
+                    let (a, b) = (tree.add_limit_order(side, price, qty), on_ladder.add_limit_order(side, price, qty));
+                    assert_eq!(a.filled_orders, b.filled_orders);
+                    order_ids.extend(a.resting_order_id);
+                }
+                3 => {
+                    let (a, b) = (tree.add_hidden_order(side, price, qty), on_ladder.add_hidden_order(side, price, qty));
+                    assert_eq!(a.filled_orders, b.filled_orders);
+                    order_ids.extend(a.resting_order_id);
+                }
+                4 if !order_ids.is_empty() => {
+                    let order_id = order_ids[rng.gen_range(0..order_ids.len())];
+                    assert_eq!(tree.cancel_order(order_id), on_ladder.cancel_order(order_id));
+                }
+                5 => {
+                    let (a, b) = (tree.add_market_order(side, qty * 3), on_ladder.add_market_order(side, qty * 3));
+                    assert_eq!(a.filled_orders, b.filled_orders);
+                }
+                6 => assert_eq!(tree.cancel_range(side, price, price + 5), on_ladder.cancel_range(side, price, price + 5)),
+                _ => {}
+            }
+            assert_eq!(tree.depth(usize::MAX), on_ladder.depth(usize::MAX), "seed {} step {}", seed, step);
+            assert_eq!(tree.bbo(), on_ladder.bbo());
+            assert_eq!(on_ladder.validate(), Ok(()));
+        }
+        assert_eq!(tree.snapshot().orders, on_ladder.snapshot().orders);
+    }
+}
+
+#[test]
+fn switching_backends_keeps_the_book() {
+    let mut book = book(None);
+    for (side, price) in [(Side::Bid, 90), (Side::Bid, 99), (Side::Ask, 101), (Side::Ask, 130)] {
+        book.create_new_limit_order(side, price, 4).unwrap();
+    }
+    let before = book.snapshot().orders;
+    book.set_config(BookConfig { price_ladder: Some(PriceLadder { low: 95, ticks: 10 }), ..BookConfig::default() });
+    assert_eq!(book.snapshot().orders, before);
+    assert_eq!(book.validate(), Ok(()));
+    book.set_config(BookConfig::default());
+    assert_eq!(book.snapshot().orders, before);
+}
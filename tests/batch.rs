@@ -0,0 +1,129 @@
+use orderbook::{
+    BookOp, Error, ManualClock, OrderBook, OrderId, Side, TimeInForce, TriggerKind, TriggerSource,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+fn add(side: Side, price: u64, qty: u64) -> BookOp {
+    BookOp::Add { side, price: price.into(), qty, tif: TimeInForce::GoodTillCancel, owner: None }
+}
+
+// Bids at 100 and 98 and a sell stop on the best bid at 99
+fn book_with_best_bid_stop() -> (OrderBook, OrderId, OrderId) {
+    let mut book = book();
+    let best = book.create_new_limit_order(Side::Bid, 100, 5).unwrap();
+    book.create_new_limit_order(Side::Bid, 98, 5).unwrap();
+    let stop_id = book
+        .create_stop_order_triggered_by(Side::Ask, TriggerKind::Stop, 99, None, 3, TriggerSource::BestBid)
+        .unwrap();
+    (book, best, stop_id)
+}
+
+#[test]
+fn stops_are_triggered_by_the_book_a_batch_leaves() {
+    let (mut book, best, stop_id) = book_with_best_bid_stop();
+    let results = book.apply_batch(&[BookOp::Cancel { order_id: best }, add(Side::Bid, 100, 5)]);
+    assert!(results.iter().all(|result| result.error().is_none()));
+    // The best bid was back at 100 by the end of the batch
+    assert!(book.take_triggered_stops().is_empty());
+    assert_eq!(book.qty_at(Side::Bid, 98), 5);
+
+    let results = book.apply_batch(&[BookOp::Cancel { order_id: OrderId(4) }, add(Side::Bid, 50, 5)]);
+    assert!(results.iter().all(|result| result.error().is_none()));
+    let triggered = book.take_triggered_stops();
+    assert_eq!(triggered.len(), 1);
+    assert_eq!(triggered[0].order_id, stop_id);
+    assert_eq!(book.qty_at(Side::Bid, 98), 2);
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn atomic_batches_trigger_stops_once_they_apply() {
+    let (mut book, best, stop_id) = book_with_best_bid_stop();
+    let results = book.apply_batch_atomic(&[BookOp::Cancel { order_id: best }]).unwrap();
+    assert_eq!(results.len(), 1);
+    let triggered = book.take_triggered_stops();
+    assert_eq!(triggered.len(), 1);
+    assert_eq!(triggered[0].order_id, stop_id);
+
+    let (mut book, best, _) = book_with_best_bid_stop();
+    let err = book
+        .apply_batch_atomic(&[BookOp::Cancel { order_id: best }, BookOp::Cancel { order_id: OrderId(99) }])
+        .unwrap_err();
+    assert!(matches!(err, Error::BatchRolledBack { index: 1, .. }), "{err:?}");
+    assert!(book.take_triggered_stops().is_empty());
+    assert_eq!(book.qty_at(Side::Bid, 100), 5);
+}
+
+// Ops on a book with both sides around 1_000, some crossing, some failing and some
+// acting on orders earlier ops in the same batch act on
+fn random_batch(rng: &mut StdRng, last_order_id: u64) -> Vec<BookOp> {
+    (0..rng.gen_range(1..8))
+        .map(|_| {
+            let order_id = OrderId(rng.gen_range(1..=last_order_id + 3));
+            let side = if rng.gen_bool(0.5) { Side::Bid } else { Side::Ask };
+            let price = match side {
+                Side::Bid => rng.gen_range(985..1_003),
+                Side::Ask => rng.gen_range(998..1_016),
+            };
+            match rng.gen_range(0..10) {
+                0..=3 => add(side, price, rng.gen_range(0..20)),
+                4 => BookOp::Add { side, price: price.into(), qty: 5, tif: TimeInForce::GoodTillDate(rng.gen_range(0..3)), owner: None },
+                5..=7 => BookOp::Cancel { order_id },
+                _ => BookOp::Amend { order_id, new_price: price.into(), new_qty: rng.gen_range(0..20) },
+            }
+        })
+        .collect()
+}
+
+// Both sides around 1_000 and a sell stop below them
+fn random_batch_book() -> OrderBook {
+    let mut book = book();
+    for i in 0..20 {
+        book.create_new_limit_order(Side::Bid, 990 + i % 10, 10).unwrap();
+        book.create_new_limit_order(Side::Ask, 1_001 + i % 10, 10).unwrap();
+    }
+    book.create_stop_order_triggered_by(Side::Ask, TriggerKind::Stop, 985, None, 3, TriggerSource::BestBid)
+        .unwrap();
+    book
+}
+
+#[test]
+fn atomic_batches_apply_as_batches_do_or_not_at_all() {
+    let mut rng = StdRng::seed_from_u64(99);
+    let mut atomic = random_batch_book();
+    let mut plain = random_batch_book();
+    let mut applied: Vec<Vec<BookOp>> = Vec::new();
+    let mut rolled_back = 0;
+    for _ in 0..500 {
+        let ops = random_batch(&mut rng, atomic.snapshot().last_order_id);
+        let before = atomic.snapshot();
+        match atomic.apply_batch_atomic(&ops) {
+            Ok(results) => {
+                assert_eq!(results, plain.apply_batch(&ops));
+                applied.push(ops);
+            }
+            Err(Error::BatchRolledBack { index, error }) => {
+                assert_eq!(atomic.snapshot(), before);
+                let results = plain.apply_batch(&ops);
+                let first_error = results.iter().position(|result| result.error().is_some());
+                assert_eq!(first_error, Some(index));
+                assert_eq!(results[index].error(), Some(*error));
+                // Back to the batches that applied, for the next batch
+                plain = random_batch_book();
+                for ops in &applied {
+                    plain.apply_batch(ops);
+                }
+                rolled_back += 1;
+            }
+            Err(err) => panic!("{err:?}"),
+        }
+        assert_eq!(atomic.snapshot(), plain.snapshot());
+        assert_eq!(atomic.validate(), Ok(()));
+    }
+    assert!(applied.len() > 50 && rolled_back > 50, "{} applied, {rolled_back} rolled back", applied.len());
+}
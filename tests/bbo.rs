@@ -0,0 +1,144 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use orderbook::{Clock, ManualClock, OrderBook, OrderId, OrderStatus, PostOnlyMode, Price, Side, TimeInForce};
+
+// Best prices recomputed from every order the test entered that still rests
+fn brute_force_bbo(book: &OrderBook, ids: &[OrderId]) -> (Option<Price>, Option<Price>) {
+    let resting = ids.iter().filter_map(|id| book.get_order(*id)).filter(|info| info.remaining_qty > 0);
+    let (mut best_bid, mut best_ask) = (None, None);
+    for info in resting {
+        match info.side {
+            Side::Bid => best_bid = best_bid.max(Some(info.price)),
+            Side::Ask => best_ask = Some(best_ask.map_or(info.price, |p: Price| p.min(info.price))),
+        }
+    }
+    (best_bid, best_ask)
+}
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+fn random_side(rng: &mut StdRng) -> Side {
+    if rng.gen_bool(0.5) {
+        Side::Bid
+    } else {
+        Side::Ask
+    }
+}
+
+#[test]
+fn incremental_bbo_matches_a_brute_force_recomputation() {
+    for seed in 0..20 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let clock = ManualClock::new(1);
+        let mut book = OrderBook::with_clock("TEST".to_string(), clock.clone());
+        let mut ids = Vec::new();
+
+        for step in 0..2_000 {
+            let side = random_side(&mut rng);
+            let price = rng.gen_range(90..=110);
+            let qty = rng.gen_range(1..=20);
+            match rng.gen_range(0..10) {
+                0..=3 => {
+                    let fill_result = book.add_limit_order(side, price, qty);
+                    ids.extend(fill_result.resting_order_id);
+                }
+                4 => {
+                    let expires_at = 1 + step + rng.gen_range(1..50);
+                    let fill_result = book.add_limit_order_with_tif(side, price, qty, TimeInForce::GoodTillDate(expires_at));
+                    ids.extend(fill_result.resting_order_id);
+                }
+                5 => {
+                    book.add_limit_order_with_tif(side, price, qty, TimeInForce::ImmediateOrCancel);
+                }
+                6 => {
+                    book.add_market_order(side, qty);
+                }
+                7 if !ids.is_empty() => {
+                    let id = ids[rng.gen_range(0..ids.len())];
+                    let _ = book.cancel_order(id);
+                }
+                8 if !ids.is_empty() => {
+                    let id = ids[rng.gen_range(0..ids.len())];
+                    let _ = book.amend_order(id, price, qty);
+                }
+                9 if !ids.is_empty() => {
+                    let id = ids[rng.gen_range(0..ids.len())];
+                    let _ = book.reduce_order(id, rng.gen_range(1..=5));
+                }
+                _ => {}
+            }
+            // Expired orders leave the book as the clock passes their expiry
+            clock.advance(1);
+            book.expire_orders(clock.now());
+
+            ids.retain(|id| book.get_order(*id).is_some_and(|info| info.remaining_qty > 0));
+            let expected = brute_force_bbo(&book, &ids);
+            assert_eq!((book.best_bid(), book.best_ask()), expected, "seed {} step {}", seed, step);
+            let bbo = book.bbo();
+            assert_eq!((bbo.bid.map(|(p, _)| p), bbo.ask.map(|(p, _)| p)), expected, "seed {} step {}", seed, step);
+        }
+    }
+}
+
+#[test]
+fn sweeping_a_side_clean_reports_no_price_for_it() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 5).unwrap();
+    book.create_new_limit_order(Side::Ask, 102, 5).unwrap();
+    book.create_new_limit_order(Side::Bid, 99, 5).unwrap();
+
+    book.add_market_order(Side::Bid, 10);
+    assert_eq!(book.best_ask(), None);
+    assert_eq!(book.bbo().ask, None);
+    assert_eq!(book.bbo().bid, Some((99.into(), 5)));
+    assert_eq!((book.spread(), book.mid_price(), book.microprice()), (None, None, None));
+    assert_eq!(book.validate(), Ok(()));
+
+    book.add_market_order(Side::Ask, 5);
+    assert_eq!(book.bbo(), Default::default());
+}
+
+#[test]
+fn cancelling_the_last_order_of_a_side_clears_its_price() {
+    let mut book = book();
+    let order_id = book.create_new_limit_order(Side::Bid, 99, 5).unwrap();
+    book.create_new_limit_order(Side::Ask, 101, 5).unwrap();
+    book.cancel_order(order_id).unwrap();
+    assert_eq!((book.best_bid(), book.best_ask()), (None, Some(101.into())));
+    assert_eq!(book.bbo().bid, None);
+}
+
+#[test]
+fn consumers_of_an_emptied_side_see_it_missing() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 5).unwrap();
+    book.add_market_order(Side::Bid, 5);
+
+    // Nothing to peg to or to cross any more
+    assert!(book.create_pegged_order(Side::Ask, 0, 5).is_err());
+    let fill_result = book.add_post_only_order(Side::Bid, 105, 5, PostOnlyMode::Reject);
+    assert_eq!(fill_result.status, OrderStatus::Created);
+    assert_eq!((book.best_bid(), book.best_ask()), (Some(105.into()), None));
+}
+
+#[test]
+fn bbo_agrees_with_the_best_prices_while_expired_orders_wait_to_be_purged() {
+    let clock = ManualClock::new(1);
+    let mut book = OrderBook::with_clock("TEST".to_string(), clock.clone());
+    book.add_limit_order_with_tif(Side::Bid, 100, 5, TimeInForce::GoodTillDate(10));
+    book.create_new_limit_order(Side::Bid, 99, 3).unwrap();
+    book.create_new_limit_order(Side::Ask, 101, 4).unwrap();
+    clock.set(20);
+
+    let bbo = book.bbo();
+    assert_eq!(bbo.bid.map(|(price, _)| price), book.best_bid());
+    assert_eq!(bbo.ask, Some((101.into(), 4)));
+
+    // Once purged both move on together
+    book.expire_orders(20);
+    assert_eq!(book.bbo().bid, Some((99.into(), 3)));
+    assert_eq!(book.best_bid(), Some(99.into()));
+}
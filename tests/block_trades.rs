@@ -0,0 +1,69 @@
+use orderbook::{AccountId, BookConfig, Error, ManualClock, OrderBook, Price, PriceBand, RejectReason, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+fn banded_book(band_reference_price: Option<u64>) -> OrderBook {
+    let mut book = book();
+    book.set_config(BookConfig {
+        price_band: Some(PriceBand::Ticks(5)),
+        band_reference_price: band_reference_price.map(Price::from),
+        ..Default::default()
+    });
+    book
+}
+
+const BUYER: AccountId = AccountId(1);
+const SELLER: AccountId = AccountId(2);
+
+#[test]
+fn block_trade_goes_on_the_tape_without_touching_the_book() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Bid, 99, 10).unwrap();
+    book.create_new_limit_order(Side::Ask, 101, 10).unwrap();
+
+    let trade = book.report_block_trade(100, 500, BUYER, SELLER).unwrap();
+    assert!(trade.block);
+    assert_eq!((trade.buyer, trade.seller), (Some(BUYER), Some(SELLER)));
+    assert_eq!(trade.seq, book.last_seq());
+    assert_eq!((book.best_bid(), book.best_ask()), (Some(99.into()), Some(101.into())));
+    assert_eq!((book.qty_at(Side::Bid, 99), book.qty_at(Side::Ask, 101)), (10, 10));
+}
+
+#[test]
+fn zero_qty_or_price_is_refused() {
+    let mut book = book();
+    assert_eq!(book.report_block_trade(100, 0, BUYER, SELLER), Err(Error::Rejected(RejectReason::ZeroQty)));
+    assert_eq!(book.report_block_trade(0, 10, BUYER, SELLER), Err(Error::Rejected(RejectReason::ZeroPrice)));
+    assert_eq!(book.recent_trades(10).len(), 0);
+}
+
+#[test]
+fn band_is_around_the_last_trade_price() {
+    let mut book = banded_book(Some(200));
+    book.report_block_trade(102, 10, BUYER, SELLER).unwrap_err();
+    book.create_new_limit_order(Side::Ask, 100, 1).unwrap();
+    book.add_limit_order(Side::Bid, 100, 1);
+
+    assert_eq!(book.report_block_trade(106, 10, BUYER, SELLER), Err(Error::Rejected(RejectReason::OutsidePriceBand)));
+    assert!(book.report_block_trade(105, 10, BUYER, SELLER).is_ok());
+}
+
+#[test]
+fn band_is_around_the_reference_price_before_the_first_trade() {
+    let mut book = banded_book(Some(100));
+    assert_eq!(book.report_block_trade(110, 10, BUYER, SELLER), Err(Error::Rejected(RejectReason::OutsidePriceBand)));
+    assert!(book.report_block_trade(95, 10, BUYER, SELLER).is_ok());
+}
+
+#[test]
+fn band_is_around_the_midpoint_without_a_reference_price() {
+    let mut book = banded_book(None);
+    assert_eq!(book.report_block_trade(100, 10, BUYER, SELLER), Err(Error::Rejected(RejectReason::NoBandReference)));
+
+    book.create_new_limit_order(Side::Bid, 98, 10).unwrap();
+    book.create_new_limit_order(Side::Ask, 102, 10).unwrap();
+    assert!(book.report_block_trade(105, 10, BUYER, SELLER).is_ok());
+    assert_eq!(book.report_block_trade(112, 10, BUYER, SELLER), Err(Error::Rejected(RejectReason::OutsidePriceBand)));
+}
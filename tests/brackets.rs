@@ -0,0 +1,76 @@
+use orderbook::{ManualClock, OrderBook, OrderStatus, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+#[test]
+fn bracket_lifecycle_from_partial_entry_to_stop_loss() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 100, 4).unwrap();
+
+    // The entry partially fills, the take-profit covers what filled
+    let (entry, fill_result) = book.create_bracket_order(Side::Bid, 100, 10, 110, 90);
+    assert_eq!(fill_result.status, OrderStatus::PartiallyFilled);
+    assert_eq!(fill_result.remaining_qty, 6);
+    assert_eq!(book.qty_at(Side::Ask, 110), 4);
+
+    // Filling more of the entry grows the exits
+    book.add_limit_order(Side::Ask, 100, 3);
+    assert_eq!(book.qty_at(Side::Ask, 110), 7);
+    assert_eq!(book.get_order(entry).unwrap().remaining_qty, 3);
+
+    // Cancelling the rest of the entry keeps the exits of what filled
+    assert_eq!(book.cancel_order(entry).unwrap().cancelled_qty, 3);
+    assert_eq!(book.qty_at(Side::Ask, 110), 7);
+
+    // A trade at the stop-loss price triggers it for the filled qty
+    book.create_new_limit_order(Side::Bid, 90, 20).unwrap();
+    book.add_limit_order(Side::Ask, 90, 1);
+    let triggered = book.take_triggered_stops();
+    assert_eq!(triggered.len(), 1);
+    assert_eq!(triggered[0].fill_result.status, OrderStatus::Filled);
+    assert_eq!(triggered[0].fill_result.total_filled_qty(), 7);
+    assert_eq!(book.qty_at(Side::Bid, 90), 12);
+
+    // and the take-profit is cancelled as its OCO sibling
+    assert_eq!(book.qty_at(Side::Ask, 110), 0);
+    let cancels = book.take_linked_cancels();
+    assert_eq!(cancels.len(), 1);
+    assert_eq!(cancels[0].cause_order_id, triggered[0].order_id);
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn cancelling_an_unfilled_entry_spawns_no_exits() {
+    let mut book = book();
+    let (entry, fill_result) = book.create_bracket_order(Side::Bid, 100, 10, 110, 90);
+    assert_eq!(fill_result.status, OrderStatus::Created);
+    assert_eq!(book.cancel_order(entry).unwrap().cancelled_qty, 10);
+
+    book.create_new_limit_order(Side::Bid, 100, 10).unwrap();
+    book.add_limit_order(Side::Ask, 100, 10);
+    book.create_new_limit_order(Side::Bid, 90, 10).unwrap();
+    book.add_limit_order(Side::Ask, 90, 1);
+    assert!(book.take_triggered_stops().is_empty());
+    assert_eq!(book.best_ask(), None);
+}
+
+#[test]
+fn take_profit_fill_shrinks_the_stop_loss() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 100, 10).unwrap();
+    let (_, fill_result) = book.create_bracket_order(Side::Bid, 100, 10, 110, 90);
+    assert_eq!(fill_result.status, OrderStatus::Filled);
+
+    book.add_limit_order(Side::Bid, 110, 4);
+    assert_eq!(book.qty_at(Side::Ask, 110), 6);
+
+    // The stop-loss only sells what the take-profit did not
+    book.create_new_limit_order(Side::Bid, 90, 20).unwrap();
+    book.add_limit_order(Side::Ask, 90, 1);
+    let triggered = book.take_triggered_stops();
+    assert_eq!(triggered.len(), 1);
+    assert_eq!(triggered[0].fill_result.total_filled_qty(), 6);
+    assert_eq!(book.qty_at(Side::Ask, 110), 0);
+}
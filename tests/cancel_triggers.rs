@@ -0,0 +1,73 @@
+use orderbook::{ManualClock, OrderBook, OrderId, Side, TriggerKind, TriggerSource};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+// Bids at 100 and 98 and a sell stop on the best bid at 99, so taking the 100 bid
+// away and nothing else fires it
+fn book_with_best_bid_stop() -> (OrderBook, OrderId, OrderId) {
+    let mut book = book();
+    let best = book.create_new_limit_order(Side::Bid, 100, 5).unwrap();
+    book.create_new_limit_order(Side::Bid, 98, 5).unwrap();
+    let stop_id = book
+        .create_stop_order_triggered_by(Side::Ask, TriggerKind::Stop, 99, None, 3, TriggerSource::BestBid)
+        .unwrap();
+    assert!(book.take_triggered_stops().is_empty());
+    (book, best, stop_id)
+}
+
+fn assert_stop_fired(book: &mut OrderBook, stop_id: OrderId) {
+    let triggered = book.take_triggered_stops();
+    assert_eq!(triggered.len(), 1);
+    assert_eq!(triggered[0].order_id, stop_id);
+    assert_eq!(triggered[0].fill_result.total_filled_qty(), 3);
+    assert_eq!(book.qty_at(Side::Bid, 98), 2);
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn cancelling_the_best_bid_fires_a_best_bid_stop() {
+    let (mut book, best, stop_id) = book_with_best_bid_stop();
+    book.cancel_order(best).unwrap();
+    assert_stop_fired(&mut book, stop_id);
+}
+
+#[test]
+fn reducing_the_best_bid_away_fires_a_best_bid_stop() {
+    let (mut book, best, stop_id) = book_with_best_bid_stop();
+    assert_eq!(book.reduce_order(best, 5), Ok(0));
+    assert_stop_fired(&mut book, stop_id);
+}
+
+#[test]
+fn mass_cancels_fire_a_best_bid_stop() {
+    let (mut book, _, stop_id) = book_with_best_bid_stop();
+    book.cancel_range(Side::Bid, 99, 101);
+    assert_stop_fired(&mut book, stop_id);
+}
+
+#[test]
+fn cancelling_the_best_bid_re_evaluates_trailing_stops() {
+    let mut book = book();
+    let best = book.create_new_limit_order(Side::Bid, 100, 5).unwrap();
+    book.create_new_limit_order(Side::Bid, 97, 5).unwrap();
+    // Trails the best bid of 100 by 2
+    let stop_id = book.create_trailing_stop_order(Side::Ask, 2, 3).unwrap();
+    assert!(book.take_triggered_stops().is_empty());
+
+    book.cancel_order(best).unwrap();
+    let triggered = book.take_triggered_stops();
+    assert_eq!(triggered.len(), 1);
+    assert_eq!(triggered[0].order_id, stop_id);
+    assert_eq!(book.qty_at(Side::Bid, 97), 2);
+}
+
+#[test]
+fn cancel_that_leaves_the_bbo_alone_fires_nothing() {
+    let (mut book, _, _) = book_with_best_bid_stop();
+    let far = book.create_new_limit_order(Side::Bid, 90, 5).unwrap();
+    book.cancel_order(far).unwrap();
+    assert!(book.take_triggered_stops().is_empty());
+    assert_eq!(book.best_bid(), Some(100.into()));
+}
@@ -0,0 +1,55 @@
+use orderbook::{Candle, CandleBuilder, CandleGaps, ManualClock, OrderBook, Side};
+
+// Trades 100 x 10 at 10, 100 x 40 and 105 x 5 at 20, then 105 x 5 at 70
+fn scripted(gaps: CandleGaps) -> (OrderBook, ManualClock) {
+    let clock = ManualClock::new(5);
+    let mut book = OrderBook::with_clock("TEST".to_string(), clock.clone());
+    book.enable_candles(60, gaps);
+    book.create_new_limit_order(Side::Ask, 100, 50).unwrap();
+    book.create_new_limit_order(Side::Ask, 105, 50).unwrap();
+    for (now, qty) in [(10, 10), (20, 45), (70, 5)] {
+        clock.set(now);
+        book.add_market_order(Side::Bid, qty);
+    }
+    (book, clock)
+}
+
+fn candle(start: u64, [open, high, low, close]: [u64; 4], volume: u64, trade_count: u64) -> Candle {
+    Candle { start, open: open.into(), high: high.into(), low: low.into(), close: close.into(), volume, trade_count }
+}
+
+#[test]
+fn trades_aggregate_into_candles_as_they_happen() {
+    let (mut book, clock) = scripted(CandleGaps::Skip);
+    assert_eq!(book.current_candle(), Some(&candle(60, [105, 105, 105, 105], 5, 1)));
+    assert_eq!(book.take_candles(), vec![candle(0, [100, 105, 100, 105], 55, 3)]);
+    assert!(book.take_candles().is_empty());
+
+    clock.set(200);
+    assert_eq!(book.take_candles(), vec![candle(60, [105, 105, 105, 105], 5, 1)]);
+    assert_eq!(book.current_candle(), None);
+}
+
+#[test]
+fn empty_intervals_carry_the_close_forward() {
+    let (mut book, clock) = scripted(CandleGaps::CarryForward);
+    clock.set(200);
+    assert_eq!(book.take_candles(), vec![
+        candle(0, [100, 105, 100, 105], 55, 3),
+        candle(60, [105, 105, 105, 105], 5, 1),
+        candle(120, [105, 105, 105, 105], 0, 0),
+    ]);
+    assert_eq!(book.current_candle(), Some(&candle(180, [105, 105, 105, 105], 0, 0)));
+}
+
+#[test]
+fn builder_fed_from_the_tape_gives_the_same_candles() {
+    let (mut book, clock) = scripted(CandleGaps::Skip);
+    clock.set(200);
+    let mut builder = CandleBuilder::new(60, CandleGaps::Skip);
+    for trade in book.trade_tape() {
+        builder.on_trade(trade);
+    }
+    builder.advance_to(200);
+    assert_eq!(builder.take_completed(), book.take_candles());
+}
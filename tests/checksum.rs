@@ -0,0 +1,54 @@
+use orderbook::{ChecksumScale, ManualClock, OrderBook, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+// Asks 101 x 10 and 102 x 5, bids 99 x 20 and 98 x 7
+fn two_level_book() -> OrderBook {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 102, 5).unwrap();
+    book.create_new_limit_order(Side::Ask, 101, 10).unwrap();
+    book.create_new_limit_order(Side::Bid, 98, 7).unwrap();
+    book.create_new_limit_order(Side::Bid, 99, 20).unwrap();
+    book
+}
+
+#[test]
+fn crc32_check_value() {
+    // "123456789"
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 12345, 6789).unwrap();
+    assert_eq!(book.checksum(10), 0xCBF4_3926);
+}
+
+#[test]
+fn asks_lowest_first_then_bids_highest_first() {
+    // "1011010259920987"
+    assert_eq!(two_level_book().checksum(10), 0x7A52_587B);
+}
+
+#[test]
+fn only_the_top_levels_count() {
+    // "101109920"
+    let mut book = two_level_book();
+    assert_eq!(book.checksum(1), 0x57AD_5CC3);
+    // Hidden qty is not in the feed either
+    book.add_hidden_order(Side::Ask, 100, 50);
+    assert_eq!(book.checksum(1), 0x57AD_5CC3);
+}
+
+#[test]
+fn scaled_prices_and_qtys_get_zeros_appended() {
+    // "50050002000" then "4990000100"
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 5005, 20).unwrap();
+    book.create_new_limit_order(Side::Bid, 4990, 1).unwrap();
+    let scale = ChecksumScale { price_decimals: 3, qty_decimals: 2 };
+    assert_eq!(book.checksum_scaled(10, scale), 0x650A_6E43);
+}
+
+#[test]
+fn empty_book_checksums_the_empty_string() {
+    assert_eq!(book().checksum(10), 0);
+}
@@ -0,0 +1,36 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use orderbook::{Event, ManualClock, OrderBook, Side, TimeInForce};
+
+// Orders expired by the time they are reached are purged by matching, which makes the
+// ones matching walks over show: they are no longer tracked.
+#[test]
+fn one_lot_against_a_deep_book_touches_only_the_first_resting_order() {
+    let clock = ManualClock::new(1);
+    let mut book = OrderBook::with_clock("TEST".to_string(), clock.clone());
+    let first = book.create_new_limit_order(Side::Ask, 100, 10).unwrap();
+    for price in 100..200 {
+        for _ in 0..100 {
+            book.add_limit_order_with_tif(Side::Ask, price, 10, TimeInForce::GoodTillDate(5));
+        }
+    }
+    clock.set(10);
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let seen = Rc::clone(&events);
+    book.on_event(move |_, event| seen.borrow_mut().push(event.clone()));
+
+    let fill_result = book.add_limit_order(Side::Bid, 199, 1);
+    assert_eq!(fill_result.remaining_qty, 0);
+    assert_eq!(fill_result.filled_orders.len(), 1);
+    assert_eq!(fill_result.filled_orders[0].maker_order_id, first);
+    assert_eq!(book.stats().tracked_orders, 1 + 100 * 100);
+    let matched = events.borrow().iter().filter(|event| matches!(event, Event::Matched { .. })).count();
+    assert_eq!(matched, 1);
+
+    // The next order that reaches them purges them
+    let fill_result = book.add_limit_order(Side::Bid, 199, 10);
+    assert_eq!(fill_result.filled_orders.len(), 1);
+    assert_eq!(fill_result.remaining_qty, 1);
+    assert_eq!(book.stats().tracked_orders, 1);
+}
@@ -0,0 +1,69 @@
+use orderbook::{AccountId, ChecksumScale, ManualClock, OrderBook, OrderId, Side, TickRounding};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+// Calls every read method of the book, none may panic whatever state it is in
+fn read_everything(book: &OrderBook) {
+    let unknown = OrderId(999_999);
+    let owner = AccountId(1);
+    book.get_bbo();
+    book.print_bbo();
+    let _ = format!("{}", book);
+    let _ = book.render(5);
+    let _ = (book.symbol(), book.last_seq(), book.phase(), book.stats());
+    let _ = (book.best_bid(), book.best_ask(), book.bbo(), book.bbo().spread());
+    let _ = (book.spread(), book.mid_price(), book.microprice(), book.imbalance(5));
+    let _ = (book.depth(5), book.depth(0), book.snapshot());
+    let _ = (book.checksum(5), book.checksum_scaled(5, ChecksumScale { price_decimals: 2, qty_decimals: 2 }));
+    for s in [Side::Bid, Side::Ask] {
+        let _ = (book.qty_at(s, 100), book.qty_at(s, 0), book.qty_at(s, u64::MAX));
+        let _ = (book.cumulative_qty(s, 100), book.cumulative_qty(s, 0), book.cumulative_qty(s, u64::MAX));
+        let _ = book.levels(s).count();
+        let _ = book.aggregated_depth(s, 5, 3);
+        let _ = (book.liquidity_within(s, 10), book.liquidity_within_notional(s, 10));
+        let _ = (book.executable_qty(s, None, 5), book.executable_qty(s, Some(100.into()), u64::MAX));
+        let _ = book.estimate_fill(s, 10);
+    }
+    let _ = (book.get_order(unknown), book.resting_qty(unknown), book.queue_position(unknown));
+    let _ = book.order_id_for_client_id(owner, "a");
+    let _ = (book.orders_for(owner), book.position(owner));
+    let _ = (book.trade_tape().len(), book.trades_since(0).len(), book.recent_trades(5).len());
+    let _ = (book.last_trade_price(), book.last_trade_qty());
+    let _ = (book.session_high(), book.session_low(), book.session_volume());
+    let _ = (book.indicative_auction(), book.closing_price(), book.closing_volume());
+    let _ = (book.trade_stats(), book.current_candle());
+    let _ = (book.price(0), book.parse_price("1.5", TickRounding::HalfUp));
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn every_read_method_works_on_an_empty_book() {
+    let book = book();
+    read_everything(&book);
+    assert_eq!((book.best_bid(), book.best_ask(), book.spread()), (None, None, None));
+}
+
+#[test]
+fn every_read_method_works_on_one_sided_books() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Bid, 100, 5).unwrap();
+    read_everything(&book);
+
+    let mut book = self::book();
+    book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    read_everything(&book);
+}
+
+#[test]
+fn every_read_method_works_once_both_sides_traded_away() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Bid, 100, 5).unwrap();
+    book.add_market_order(Side::Ask, 5);
+    read_everything(&book);
+    book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    book.add_market_order(Side::Bid, 5);
+    read_everything(&book);
+    assert_eq!((book.best_bid(), book.best_ask()), (None, None));
+}
@@ -0,0 +1,41 @@
+use orderbook::{AccountId, Error, ManualClock, OrderBook, OrderId, RejectReason, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+#[test]
+fn errors_carry_the_ids_they_are_about() {
+    let mut book = book();
+    let id = book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    book.add_limit_order(Side::Bid, 100, 5);
+
+    assert_eq!(book.cancel_order(id), Err(Error::AlreadyFilled { order_id: id, filled_qty: 5 }));
+    assert_eq!(book.cancel_order(OrderId(99)), Err(Error::UnknownOrder { order_id: OrderId(99) }));
+    assert_eq!(book.create_new_limit_order(Side::Bid, 100, 0), Err(Error::Rejected(RejectReason::ZeroQty)));
+}
+
+#[test]
+fn errors_display_for_people() {
+    let unknown = Error::UnknownClientOrderId { owner: AccountId(4), client_order_id: "abc".to_string() };
+    assert_eq!(unknown.to_string(), "No order abc for account 4!");
+    assert_eq!(Error::Rejected(RejectReason::ZeroQty).to_string(), "Order qty is 0!");
+    let batch = Error::BatchRolledBack { index: 2, error: Box::new(Error::WasRejected { order_id: OrderId(8) }) };
+    assert_eq!(batch.to_string(), format!("Batch rolled back, op 2 failed: Order {} was rejected!", OrderId(8)));
+
+    // Usable wherever a std error is expected
+    let boxed: Box<dyn std::error::Error> = Box::new(Error::Rejected(RejectReason::ZeroPrice));
+    assert_eq!(boxed.to_string(), "Order price is 0!");
+}
+
+#[test]
+fn pegged_orders_are_held_to_the_order_limits() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Bid, 100, 5).unwrap();
+    assert_eq!(book.create_pegged_order(Side::Bid, 0, 0), Err(Error::Rejected(RejectReason::ZeroQty)));
+    // Pegged below the lowest price there is
+    assert_eq!(book.create_pegged_order(Side::Bid, -100, 5), Err(Error::Rejected(RejectReason::ZeroPrice)));
+    assert_eq!(book.stats().orders_accepted, 1);
+    assert_eq!(book.qty_at(Side::Bid, 100), 5);
+    assert_eq!(book.validate(), Ok(()));
+}
@@ -0,0 +1,107 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use orderbook::{Fill, OrderBook, OrderStatus, Side, TimeInForce};
+
+// Debug builds with the validate feature check the whole book after every operation,
+// so they replay fewer orders against less depth
+const CHECKED: bool = cfg!(all(feature = "validate", debug_assertions));
+const ORDERS: u64 = if CHECKED { 40_000 } else { 100_000 };
+const DEPTH: u64 = if CHECKED { 100 } else { 1_000 };
+const PRICES: u64 = 50;
+
+// Counts allocations made on a thread while its COUNTING is set, so the test
+// harness's own threads don't count
+struct CountingAlloc;
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+fn count() {
+    if COUNTING.with(Cell::get) {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations(run: impl FnOnce()) -> u64 {
+    ALLOCATIONS.with(|n| n.set(0));
+    COUNTING.with(|c| c.set(true));
+    run();
+    COUNTING.with(|c| c.set(false));
+    ALLOCATIONS.with(Cell::get)
+}
+
+// Every order rests and is cancelled, or rests and is then filled by an ask for the
+// same qty at its price. The book ends each round as it started.
+fn replay(book: &mut OrderBook, fills: &mut Vec<Fill>, filled: &mut u64) {
+    let tif = TimeInForce::GoodTillCancel;
+    for i in 0..ORDERS {
+        let price = 1_000 + i % PRICES;
+        let resting = book.add_limit_order_into(Side::Bid, price, 10, tif, fills);
+        if i % 2 == 0 {
+            book.cancel_order(resting.resting_order_id.unwrap()).unwrap();
+        } else if i % 4 == 1 {
+            let fill_result = book.add_limit_order_into(Side::Ask, price, 10, tif, fills);
+            assert_eq!(fill_result.status, OrderStatus::Filled);
+            *filled += fills.iter().map(|f| f.qty).sum::<u64>();
+        } else {
+            let fill_result = book.add_limit_order_with(Side::Ask, price, 10, tif, |f| *filled += f.qty);
+            assert_eq!(fill_result.status, OrderStatus::Filled);
+        }
+    }
+}
+
+#[test]
+fn resting_and_single_level_fills_do_not_allocate() {
+    let mut book = OrderBook::new("ALLOC".to_string());
+    // Depth on both sides that the loop never reaches
+    for i in 0..DEPTH {
+        book.create_new_limit_order(Side::Bid, 1 + i % 500, 10).unwrap();
+        book.create_new_limit_order(Side::Ask, 10_000 + i % 500, 10).unwrap();
+    }
+    let mut fills = Vec::with_capacity(16);
+    let mut filled = 0;
+    // Grows the book's buffers, the tape and the order history to their working size
+    replay(&mut book, &mut fills, &mut filled);
+
+    let counted = allocations(|| replay(&mut book, &mut fills, &mut filled));
+    assert_eq!(counted, 0);
+    // Half the orders of each replay filled
+    assert_eq!(filled, 10 * ORDERS);
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn add_limit_order_allocates_the_fills_it_returns() {
+    let mut book = OrderBook::new("ALLOC".to_string());
+    let mut fills = Vec::with_capacity(16);
+    let mut filled = 0;
+    replay(&mut book, &mut fills, &mut filled);
+
+    let counted = allocations(|| {
+        book.add_limit_order(Side::Bid, 1_000, 10);
+        book.add_limit_order(Side::Ask, 1_000, 10);
+    });
+    assert!(counted > 0);
+}
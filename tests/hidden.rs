@@ -0,0 +1,55 @@
+use orderbook::{ManualClock, OrderBook, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+#[test]
+fn newer_displayed_order_fills_before_an_older_hidden_one() {
+    let mut book = book();
+    let hidden = book.add_hidden_order(Side::Ask, 100, 5).resting_order_id.unwrap();
+    let displayed = book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+
+    let fill_result = book.add_limit_order(Side::Bid, 100, 7);
+    let makers: Vec<_> = fill_result.filled_orders.iter().map(|f| (f.maker_order_id, f.qty)).collect();
+    assert_eq!(makers, vec![(displayed, 5), (hidden, 2)]);
+    assert_eq!(book.resting_qty(hidden), Some(3));
+}
+
+#[test]
+fn time_priority_holds_within_each_class() {
+    let mut book = book();
+    let hidden_a = book.add_hidden_order(Side::Ask, 100, 2).resting_order_id.unwrap();
+    let displayed_a = book.create_new_limit_order(Side::Ask, 100, 2).unwrap();
+    let hidden_b = book.add_hidden_order(Side::Ask, 100, 2).resting_order_id.unwrap();
+    let displayed_b = book.create_new_limit_order(Side::Ask, 100, 2).unwrap();
+
+    let fill_result = book.add_limit_order(Side::Bid, 100, 8);
+    let makers: Vec<_> = fill_result.filled_orders.iter().map(|f| f.maker_order_id).collect();
+    assert_eq!(makers, vec![displayed_a, displayed_b, hidden_a, hidden_b]);
+}
+
+#[test]
+fn displayed_orders_at_a_worse_price_wait_for_hidden_ones_at_a_better_price() {
+    let mut book = book();
+    let hidden = book.add_hidden_order(Side::Ask, 100, 5).resting_order_id.unwrap();
+    book.create_new_limit_order(Side::Ask, 101, 5).unwrap();
+
+    let fill_result = book.add_limit_order(Side::Bid, 101, 5);
+    assert_eq!(fill_result.filled_orders.len(), 1);
+    assert_eq!((fill_result.filled_orders[0].maker_order_id, fill_result.filled_orders[0].price), (hidden, 100.into()));
+    assert_eq!(book.qty_at(Side::Ask, 101), 5);
+}
+
+#[test]
+fn uncross_takes_displayed_bids_before_older_hidden_ones() {
+    let mut book = book();
+    book.start_auction();
+    let hidden = book.add_hidden_order(Side::Bid, 100, 5).resting_order_id.unwrap();
+    let displayed = book.create_new_limit_order(Side::Bid, 100, 5).unwrap();
+    book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+
+    assert_eq!(book.uncross().matched_qty, 5);
+    assert_eq!(book.resting_qty(displayed), None);
+    assert_eq!(book.resting_qty(hidden), Some(5));
+}
@@ -0,0 +1,56 @@
+use orderbook::{ManualClock, OrderBook, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+#[test]
+fn empty_book_has_no_imbalance() {
+    assert_eq!(book().imbalance(5), None);
+}
+
+#[test]
+fn one_sided_books_are_fully_imbalanced() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 10).unwrap();
+    assert_eq!(book.imbalance(5), Some(-1.0));
+
+    let mut book = self::book();
+    book.create_new_limit_order(Side::Bid, 99, 10).unwrap();
+    assert_eq!(book.imbalance(5), Some(1.0));
+}
+
+#[test]
+fn asymmetric_book_over_the_top_levels() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 10).unwrap();
+    book.create_new_limit_order(Side::Ask, 102, 30).unwrap();
+    book.create_new_limit_order(Side::Bid, 99, 30).unwrap();
+    book.create_new_limit_order(Side::Bid, 98, 130).unwrap();
+
+    assert_eq!(book.imbalance(1), Some(0.5));
+    assert_eq!(book.imbalance(2), Some(0.6));
+    assert_eq!(book.imbalance(0), None);
+}
+
+#[test]
+fn more_levels_than_the_book_has_counts_all_of_them() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 10).unwrap();
+    book.create_new_limit_order(Side::Bid, 99, 30).unwrap();
+    book.create_new_limit_order(Side::Bid, 98, 60).unwrap();
+    assert_eq!(book.imbalance(100), Some(0.8));
+    assert_eq!(book.imbalance(usize::MAX), Some(0.8));
+}
+
+#[test]
+fn hidden_qty_and_emptied_levels_do_not_count() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 10).unwrap();
+    book.add_hidden_order(Side::Ask, 102, 1000);
+    let bid = book.create_new_limit_order(Side::Bid, 99, 50).unwrap();
+    book.create_new_limit_order(Side::Bid, 98, 30).unwrap();
+    book.cancel_order(bid).unwrap();
+
+    assert_eq!(book.imbalance(1), Some(0.5));
+}
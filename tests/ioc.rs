@@ -0,0 +1,48 @@
+use orderbook::{ManualClock, OrderBook, OrderStatus, Side, TimeInForce};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+#[test]
+fn fully_filled_ioc() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    book.create_new_limit_order(Side::Ask, 101, 5).unwrap();
+
+    let fill_result = book.add_limit_order_with_tif(Side::Bid, 101, 8, TimeInForce::ImmediateOrCancel);
+    assert_eq!(fill_result.status, OrderStatus::Filled);
+    assert_eq!(fill_result.total_filled_qty(), 8);
+    assert_eq!(fill_result.remaining_qty, 0);
+    assert_eq!(fill_result.resting_order_id, None);
+    assert_eq!(book.qty_at(Side::Ask, 101), 2);
+}
+
+#[test]
+fn partially_filled_ioc_leaves_nothing_resting() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 100, 3).unwrap();
+
+    let fill_result = book.add_limit_order_with_tif(Side::Bid, 100, 5, TimeInForce::ImmediateOrCancel);
+    assert_eq!(fill_result.status, OrderStatus::PartiallyFilled);
+    assert_eq!(fill_result.total_filled_qty(), 3);
+    assert_eq!(fill_result.remaining_qty, 2);
+    assert_eq!((fill_result.resting_order_id, fill_result.resting_price), (None, None));
+    assert_eq!((book.best_bid(), book.best_ask()), (None, None));
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn ioc_that_crosses_nothing_leaves_the_book_untouched() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 5).unwrap();
+    book.create_new_limit_order(Side::Bid, 99, 5).unwrap();
+    let before = book.snapshot();
+
+    let fill_result = book.add_limit_order_with_tif(Side::Bid, 100, 5, TimeInForce::ImmediateOrCancel);
+    assert_eq!(fill_result.status, OrderStatus::Cancelled);
+    assert!(fill_result.filled_orders.is_empty());
+    assert_eq!(fill_result.remaining_qty, 5);
+    assert_eq!(book.snapshot().orders, before.orders);
+    assert_eq!((book.best_bid(), book.best_ask()), (Some(99.into()), Some(101.into())));
+}
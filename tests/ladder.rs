@@ -0,0 +1,61 @@
+use orderbook::{ManualClock, OrderBook, OrderId, ScaleDistribution, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+// (price, qty) of each order, in the order the ids are given
+fn ladder(book: &OrderBook, order_ids: &[OrderId]) -> Vec<(u64, u64)> {
+    order_ids.iter().map(|id| book.get_order(*id).map(|o| (o.price.ticks(), o.remaining_qty)).unwrap()).collect()
+}
+
+#[test]
+fn flat_ladder_adds_up_with_the_remainder_nearest_price_from() {
+    let mut book = book();
+    let order_ids = book.create_scaled_orders(Side::Bid, 99, 95, 10_003, 5, ScaleDistribution::Flat);
+    assert_eq!(ladder(&book, &order_ids), vec![(99, 2001), (98, 2001), (97, 2001), (96, 2000), (95, 2000)]);
+    assert_eq!(book.executable_qty(Side::Ask, None, u64::MAX), 10_003);
+}
+
+#[test]
+fn ten_orders_over_five_ticks_round_down_to_whole_ticks() {
+    let mut book = book();
+    let order_ids = book.create_scaled_orders(Side::Bid, 95, 99, 10_000, 10, ScaleDistribution::Flat);
+    let prices: Vec<u64> = ladder(&book, &order_ids).iter().map(|(p, _)| *p).collect();
+    assert_eq!(prices, vec![95, 95, 95, 96, 96, 97, 97, 98, 98, 99]);
+    assert_eq!(book.qty_at(Side::Bid, 99), 1000);
+    assert_eq!(book.qty_at(Side::Bid, 95), 3000);
+}
+
+#[test]
+fn weighted_ladders_lean_towards_their_end() {
+    let mut book = book();
+    let order_ids = book.create_scaled_orders(Side::Ask, 110, 100, 100, 4, ScaleDistribution::WeightedTowardsTo);
+    assert_eq!(ladder(&book, &order_ids), vec![(110, 10), (107, 20), (104, 30), (100, 40)]);
+
+    let order_ids = book.create_scaled_orders(Side::Ask, 120, 130, 11, 4, ScaleDistribution::WeightedTowardsFrom);
+    let qtys: Vec<u64> = ladder(&book, &order_ids).iter().map(|(_, q)| *q).collect();
+    assert_eq!(qtys.iter().sum::<u64>(), 11);
+    assert!(qtys.windows(2).all(|w| w[0] >= w[1]));
+}
+
+#[test]
+fn orders_left_without_qty_are_not_placed() {
+    let mut book = book();
+    let order_ids = book.create_scaled_orders(Side::Bid, 100, 90, 3, 5, ScaleDistribution::Flat);
+    assert_eq!(ladder(&book, &order_ids), vec![(100, 1), (98, 1), (95, 1)]);
+    assert!(book.create_scaled_orders(Side::Bid, 100, 90, 3, 0, ScaleDistribution::Flat).is_empty());
+}
+
+#[test]
+fn whole_ladder_cancels_through_its_ids() {
+    let mut book = book();
+    let order_ids = book.create_scaled_orders(Side::Bid, 95, 99, 10_000, 10, ScaleDistribution::Flat);
+    let other = book.create_new_limit_order(Side::Bid, 97, 5).unwrap();
+
+    assert_eq!(book.cancel_orders(&order_ids), 10);
+    assert_eq!(book.cancel_orders(&order_ids), 0);
+    assert_eq!(book.best_bid(), Some(97.into()));
+    assert_eq!(book.get_order(other).unwrap().remaining_qty, 5);
+    assert_eq!(book.validate(), Ok(()));
+}
@@ -0,0 +1,84 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use orderbook::{ManualClock, OrderBook, OrderId, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+#[test]
+fn cancels_of_orders_on_freed_levels_do_not_reach_reused_slots() {
+    let mut book = book();
+    let keep = book.create_new_limit_order(Side::Ask, 1_000_000, 5).unwrap();
+    let mut stale: Vec<OrderId> = Vec::new();
+    for round in 0..2_000u64 {
+        // A fresh price every round, drained by a cancel or a match
+        let price = 1_000 + round;
+        let a = book.create_new_limit_order(Side::Ask, price, 1).unwrap();
+        let b = book.create_new_limit_order(Side::Ask, price, 2).unwrap();
+        if round % 2 == 0 {
+            book.cancel_order(a).unwrap();
+            book.cancel_order(b).unwrap();
+        } else {
+            assert_eq!(book.add_market_order(Side::Bid, 3).total_filled_qty(), 3);
+        }
+        stale.push(a);
+        stale.push(b);
+        assert_eq!(book.validate(), Ok(()));
+    }
+
+    for &order_id in &stale {
+        assert!(book.cancel_order(order_id).is_err());
+    }
+    let cancelled = book.cancel_order(keep).unwrap();
+    assert_eq!((cancelled.price, cancelled.cancelled_qty), (Some(1_000_000.into()), 5));
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn stale_ids_at_a_reused_price_leave_the_new_orders_alone() {
+    let mut book = book();
+    let old = book.create_new_limit_order(Side::Bid, 100, 10).unwrap();
+    book.cancel_order(old).unwrap();
+    let new = book.create_new_limit_order(Side::Bid, 100, 7).unwrap();
+
+    assert!(book.cancel_order(old).is_err());
+    assert!(book.reduce_order(old, 1).is_err());
+    assert_eq!(book.resting_qty(new), Some(7));
+    assert_eq!(book.qty_at(Side::Bid, 100), 7);
+}
+
+#[test]
+fn random_churn_keeps_every_order_on_its_own_level() {
+    let mut rng = StdRng::seed_from_u64(78);
+    let mut book = book();
+    let mut live: Vec<(OrderId, u64)> = Vec::new();
+    for _ in 0..20_000 {
+        match rng.gen_range(0..10) {
+            0..=5 => {
+                let side = if rng.gen_bool(0.5) { Side::Bid } else { Side::Ask };
+                // Bids below 500 and asks above, so nothing crosses
+                let price = match side {
+                    Side::Bid => rng.gen_range(400..500),
+                    Side::Ask => rng.gen_range(500..600),
+                };
+                live.push((book.create_new_limit_order(side, price, rng.gen_range(1..10)).unwrap(), price));
+            }
+            6..=8 if !live.is_empty() => {
+                let (order_id, price) = live.swap_remove(rng.gen_range(0..live.len()));
+                assert_eq!(book.cancel_order(order_id).unwrap().price, Some(price.into()));
+            }
+            _ => {
+                // Sweep a side so its levels are freed and later reused
+                let side = if rng.gen_bool(0.5) { Side::Bid } else { Side::Ask };
+                book.add_market_order(side, 50);
+                live.retain(|&(order_id, _)| book.resting_qty(order_id).is_some());
+            }
+        }
+    }
+    for &(order_id, price) in &live {
+        assert_eq!(book.get_order(order_id).unwrap().price, price);
+    }
+    assert_eq!(book.validate(), Ok(()));
+}
@@ -0,0 +1,53 @@
+use std::collections::VecDeque;
+
+use orderbook::{OrderBook, OrderId, Side};
+
+// Bids resting at once, each at its own price
+const LIVE: usize = 1_000;
+// Slots either side may hold, the live prices plus room for the matching below
+const MAX_LEVELS: usize = LIVE + 64;
+
+// Cycles orders through a band of prices that keeps moving, so every level is
+// created, emptied and freed many times over, and checks the level slots the book
+// holds stay bounded by the prices in use rather than by the prices ever seen.
+fn soak(orders: u64) {
+    let mut book = OrderBook::new("SOAK".to_string());
+    let mut live: VecDeque<OrderId> = VecDeque::with_capacity(LIVE + 1);
+    for i in 0..orders {
+        // Bid prices climb and wrap, so no price is in use for long
+        let price = 1 + i % 1_000_000;
+        live.push_back(book.create_new_limit_order(Side::Bid, price, 10).unwrap());
+        if live.len() > LIVE {
+            let _ = book.cancel_order(live.pop_front().unwrap());
+        }
+        // Now and then an ask takes out the best bids
+        if i % 97 == 0 {
+            book.add_market_order(Side::Ask, 25);
+        }
+        // And a range cancel or a full one clears a stretch at a time
+        if i % (orders / 40) == 0 {
+            book.cancel_range(Side::Bid, price.saturating_sub(300), price);
+        }
+        if i % (orders / 4) == 0 {
+            book.cancel_all();
+        }
+        if i % 10_000 == 0 {
+            let stats = book.stats();
+            let levels = stats.bids.allocated_levels.max(stats.asks.allocated_levels);
+            assert!(levels <= MAX_LEVELS, "{} level slots after {} orders", levels, i);
+        }
+    }
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn level_slots_stay_bounded() {
+    soak(400_000);
+}
+
+//     cargo test --release --test level_soak -- --ignored
+#[test]
+#[ignore]
+fn level_slots_stay_bounded_over_ten_million_orders() {
+    soak(10_000_000);
+}
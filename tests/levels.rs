@@ -0,0 +1,72 @@
+use orderbook::{ManualClock, OrderBook, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+fn prices(book: &OrderBook, s: Side) -> Vec<u64> {
+    book.levels(s).map(|l| l.price.ticks()).collect()
+}
+
+#[test]
+fn levels_come_best_first_on_both_sides() {
+    let mut book = book();
+    for price in [102, 104, 101, 103] {
+        book.create_new_limit_order(Side::Ask, price, 10).unwrap();
+    }
+    for price in [97, 99, 96, 98] {
+        book.create_new_limit_order(Side::Bid, price, 10).unwrap();
+    }
+    assert_eq!(prices(&book, Side::Ask), vec![101, 102, 103, 104]);
+    assert_eq!(prices(&book, Side::Bid), vec![99, 98, 97, 96]);
+}
+
+#[test]
+fn emptied_levels_are_skipped() {
+    let mut book = book();
+    assert_eq!(book.levels(Side::Bid).count(), 0);
+    book.create_new_limit_order(Side::Ask, 101, 10).unwrap();
+    let cancelled = book.create_new_limit_order(Side::Ask, 102, 10).unwrap();
+    book.create_new_limit_order(Side::Ask, 103, 10).unwrap();
+    book.cancel_order(cancelled).unwrap();
+    book.add_limit_order(Side::Bid, 101, 10);
+    assert_eq!(prices(&book, Side::Ask), vec![103]);
+}
+
+#[test]
+fn level_view_gives_displayed_qty_and_displayed_orders_in_priority_order() {
+    let mut book = book();
+    book.add_hidden_order(Side::Ask, 101, 7);
+    let first = book.create_new_limit_order(Side::Ask, 101, 10).unwrap();
+    let second = book.create_new_limit_order(Side::Ask, 101, 5).unwrap();
+
+    let level = book.levels(Side::Ask).next().unwrap();
+    assert_eq!((level.price, level.qty), (101.into(), 15));
+    let order_ids: Vec<_> = level.orders().map(|o| o.order_id).collect();
+    assert_eq!(order_ids, vec![first, second]);
+}
+
+#[test]
+fn levels_holding_only_hidden_orders_are_not_shown() {
+    let mut book = book();
+    book.add_hidden_order(Side::Ask, 100, 7);
+    book.create_new_limit_order(Side::Ask, 101, 10).unwrap();
+    book.add_hidden_order(Side::Bid, 99, 7);
+
+    assert_eq!(prices(&book, Side::Ask), vec![101]);
+    assert_eq!(prices(&book, Side::Bid), Vec::<u64>::new());
+    let depth = book.depth(usize::MAX);
+    assert_eq!(depth.asks.iter().map(|l| l.price.ticks()).collect::<Vec<_>>(), prices(&book, Side::Ask));
+}
+
+#[test]
+fn several_readers_can_walk_the_book_at_once() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 10).unwrap();
+    book.create_new_limit_order(Side::Bid, 99, 10).unwrap();
+
+    let (mut asks, mut bids) = (book.levels(Side::Ask), book.levels(Side::Bid));
+    let (ask, bid) = (asks.next().unwrap(), bids.next().unwrap());
+    assert_eq!((bid.price, ask.price), (99.into(), 101.into()));
+    assert_eq!(book.best_bid(), Some(99.into()));
+}
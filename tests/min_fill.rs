@@ -0,0 +1,82 @@
+use orderbook::{ManualClock, MinFill, MinFillResidual, OrderBook, OrderStatus, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+fn min_fill(qty: u64, residual: MinFillResidual) -> MinFill {
+    MinFill { qty, residual }
+}
+
+#[test]
+fn small_order_skips_the_min_fill_order_and_fills_the_one_behind() {
+    let mut book = book();
+    let big = book.add_min_fill_order(Side::Ask, 100, 1000, min_fill(200, MinFillResidual::Cancel));
+    let big = big.resting_order_id.unwrap();
+    let small = book.create_new_limit_order(Side::Ask, 100, 150).unwrap();
+
+    let fill_result = book.add_limit_order(Side::Bid, 100, 150);
+    assert_eq!(fill_result.status, OrderStatus::Filled);
+    assert_eq!(fill_result.filled_orders.len(), 1);
+    assert_eq!((fill_result.filled_orders[0].maker_order_id, fill_result.filled_orders[0].qty), (small, 150));
+    assert_eq!(book.get_order(big).unwrap().remaining_qty, 1000);
+}
+
+#[test]
+fn remainder_does_not_rest_through_a_skipped_min_fill_order() {
+    let mut book = book();
+    book.add_min_fill_order(Side::Ask, 100, 1000, min_fill(200, MinFillResidual::Cancel));
+
+    let fill_result = book.add_limit_order(Side::Bid, 100, 150);
+    assert_eq!(fill_result.status, OrderStatus::Cancelled);
+    assert_eq!(fill_result.resting_price, None);
+    assert_eq!((book.best_bid(), book.best_ask()), (None, Some(100.into())));
+    assert_eq!(book.validate(), Ok(()));
+
+    // Below the skipped price it rests as usual
+    let fill_result = book.add_limit_order(Side::Bid, 99, 150);
+    assert_eq!(fill_result.status, OrderStatus::Created);
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn remainder_after_partial_fills_is_cancelled_when_it_would_cross() {
+    let mut book = book();
+    book.add_min_fill_order(Side::Ask, 100, 1000, min_fill(200, MinFillResidual::Cancel));
+    book.create_new_limit_order(Side::Ask, 101, 100).unwrap();
+
+    let fill_result = book.add_limit_order(Side::Bid, 101, 150);
+    assert_eq!(fill_result.total_filled_qty(), 100);
+    assert_eq!(fill_result.remaining_qty, 50);
+    // As for an IOC order, partially filled with the rest cancelled
+    assert_eq!(fill_result.status, OrderStatus::PartiallyFilled);
+    assert_eq!(fill_result.resting_price, None);
+    assert_eq!(book.best_bid(), None);
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn residual_below_the_minimum_is_cancelled() {
+    let mut book = book();
+    let id = book.add_min_fill_order(Side::Ask, 100, 1000, min_fill(200, MinFillResidual::Cancel));
+    let id = id.resting_order_id.unwrap();
+
+    let fill_result = book.add_limit_order(Side::Bid, 100, 850);
+    assert_eq!(fill_result.status, OrderStatus::Filled);
+    assert_eq!(book.get_order(id).unwrap().status, OrderStatus::Cancelled);
+    assert_eq!(book.best_ask(), None);
+}
+
+#[test]
+fn residual_below_the_minimum_drops_the_constraint() {
+    let mut book = book();
+    let id = book.add_min_fill_order(Side::Ask, 100, 1000, min_fill(200, MinFillResidual::DropConstraint));
+    let id = id.resting_order_id.unwrap();
+
+    book.add_limit_order(Side::Bid, 100, 850);
+    assert_eq!(book.get_order(id).unwrap().remaining_qty, 150);
+    // Any qty can hit the residual now
+    let fill_result = book.add_limit_order(Side::Bid, 100, 10);
+    assert_eq!(fill_result.status, OrderStatus::Filled);
+    assert_eq!(book.get_order(id).unwrap().remaining_qty, 140);
+}
@@ -0,0 +1,72 @@
+use orderbook::{BookConfig, ManualClock, MatchingAlgorithm, OrderBook, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+// Just over half of u64::MAX, so two of them overflow a u64 sum
+const HALF: u64 = u64::MAX / 2 + 10;
+
+#[test]
+fn level_qty_saturates() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 100, HALF).unwrap();
+    book.create_new_limit_order(Side::Ask, 100, HALF).unwrap();
+    assert_eq!(book.qty_at(Side::Ask, 100), u64::MAX);
+    assert_eq!(book.bbo().ask, Some((100.into(), u64::MAX)));
+    assert_eq!(book.depth(1).asks[0].qty, u64::MAX);
+    assert_eq!(book.cumulative_qty(Side::Ask, 100), u64::MAX);
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn cumulative_qty_over_levels_saturates() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Bid, 99, HALF).unwrap();
+    book.create_new_limit_order(Side::Bid, 98, HALF).unwrap();
+    assert_eq!(book.cumulative_qty(Side::Bid, 98), u64::MAX);
+    assert_eq!(book.executable_qty(Side::Ask, None, u64::MAX), u64::MAX);
+}
+
+#[test]
+fn notional_of_huge_prices_and_qtys_is_exact() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, u64::MAX - 1, HALF).unwrap();
+    let fill_result = book.add_market_order(Side::Bid, HALF);
+    assert_eq!(fill_result.total_notional(), (u64::MAX - 1) as u128 * HALF as u128);
+    assert_eq!(fill_result.avg_fill_price(), Some((u64::MAX - 1) as f64));
+    assert_eq!(book.stats().matched_qty, HALF);
+}
+
+#[test]
+fn matching_u64_max_against_two_huge_orders() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 100, HALF).unwrap();
+    book.create_new_limit_order(Side::Ask, 100, HALF).unwrap();
+    let fill_result = book.add_market_order(Side::Bid, u64::MAX);
+    assert_eq!(fill_result.total_filled_qty(), u64::MAX);
+    assert_eq!(book.qty_at(Side::Ask, 100), HALF - (u64::MAX - HALF));
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn pro_rata_shares_of_huge_orders_do_not_overflow() {
+    let mut book = book();
+    book.set_config(BookConfig { matching: MatchingAlgorithm::ProRata, ..BookConfig::default() });
+    book.create_new_limit_order(Side::Ask, 100, HALF).unwrap();
+    book.create_new_limit_order(Side::Ask, 100, HALF).unwrap();
+    let fill_result = book.add_market_order(Side::Bid, u64::MAX);
+    assert_eq!(fill_result.total_filled_qty(), u64::MAX);
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn analytics_of_huge_books_stay_finite() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, u64::MAX - 1, HALF).unwrap();
+    book.create_new_limit_order(Side::Bid, 1, HALF).unwrap();
+    assert!(book.mid_price().unwrap().is_finite());
+    assert!(book.microprice().unwrap().is_finite());
+    assert_eq!(book.imbalance(5), Some(0.0));
+    assert_eq!(book.liquidity_within_notional(Side::Ask, 10_000), (u64::MAX - 1) as u128 * HALF as u128);
+}
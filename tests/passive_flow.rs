@@ -0,0 +1,109 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use orderbook::{Event, Fill, ManualClock, OrderBook, OrderStatus, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+fn rested_events(book: &mut OrderBook) -> Rc<RefCell<Vec<Event>>> {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let sink = events.clone();
+    book.on_event(move |_, event| {
+        if matches!(event, Event::Rested { .. }) {
+            sink.borrow_mut().push(event.clone());
+        }
+    });
+    events
+}
+
+// The same qty resting at the same price, once through matching and once without
+#[test]
+fn remainder_rested_after_matching_is_the_same_as_a_passive_order() {
+    let mut slow = book();
+    let mut fast = book();
+    let slow_events = rested_events(&mut slow);
+    let fast_events = rested_events(&mut fast);
+    for book in [&mut slow, &mut fast] {
+        book.create_new_limit_order(Side::Bid, 98, 4).unwrap();
+        book.create_new_limit_order(Side::Bid, 100, 2).unwrap();
+        book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+        book.create_new_limit_order(Side::Ask, 103, 6).unwrap();
+    }
+
+    // Crosses, takes the ask at 100 and rests the other 5
+    let slow_order = slow.add_limit_order(Side::Bid, 100, 10);
+    // The ask at 100 goes first, then the 5 come in with nothing to cross
+    fast.add_market_order(Side::Bid, 5);
+    let fast_order = fast.add_limit_order(Side::Bid, 100, 5);
+    assert!(fast_order.filled_orders.is_empty());
+
+    assert_eq!(slow_events.borrow().last(), fast_events.borrow().last());
+    assert_eq!(slow_order.resting_price, fast_order.resting_price);
+    assert_eq!(slow.depth(usize::MAX), fast.depth(usize::MAX));
+    assert_eq!(slow.bbo(), fast.bbo());
+    assert_eq!((slow.best_bid(), slow.best_ask()), (fast.best_bid(), fast.best_ask()));
+    let slow_info = slow.get_order(slow_order.resting_order_id.unwrap()).unwrap();
+    let fast_info = fast.get_order(fast_order.resting_order_id.unwrap()).unwrap();
+    assert_eq!((slow_info.remaining_qty, slow_info.queue_position), (fast_info.remaining_qty, fast_info.queue_position));
+    assert_eq!(slow.validate(), Ok(()));
+    assert_eq!(fast.validate(), Ok(()));
+
+    // And both trade the same from here on
+    let a = slow.add_limit_order(Side::Ask, 98, 9);
+    let b = fast.add_limit_order(Side::Ask, 98, 9);
+    let fills = |fills: &[Fill]| fills.iter().map(|f| (f.price, f.qty)).collect::<Vec<_>>();
+    assert_eq!(fills(&a.filled_orders), fills(&b.filled_orders));
+    assert_eq!(slow.depth(usize::MAX), fast.depth(usize::MAX));
+}
+
+#[test]
+fn passive_order_result_is_that_of_an_order_that_found_nothing() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 5).unwrap();
+    let fill_result = book.add_limit_order(Side::Bid, 99, 7);
+
+    assert_eq!(fill_result.status, OrderStatus::Created);
+    assert!(fill_result.filled_orders.is_empty());
+    assert_eq!(fill_result.remaining_qty, 7);
+    assert_eq!(fill_result.resting_price, Some(99.into()));
+    assert_eq!(book.resting_qty(fill_result.resting_order_id.unwrap()), Some(7));
+    assert_eq!(book.bbo().bid, Some((99.into(), 7)));
+}
+
+#[test]
+fn random_flow_keeps_the_cached_best_prices_right() {
+    for seed in 0..20 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut book = book();
+        let mut order_ids = Vec::new();
+        for step in 0..2_000 {
+            let side = if rng.gen_bool(0.5) { Side::Bid } else { Side::Ask };
+            let price = rng.gen_range(90..110);
+            let qty = rng.gen_range(1..20);
+            // Mostly orders that rest without crossing, now and then one that does
+            let fill_result = match rng.gen_range(0..10) {
+                0..=4 => book.add_limit_order(side, price, qty),
+                5 => book.add_hidden_order(side, price, qty),
+                6 => book.add_iceberg_order(side, price, qty * 3, qty),
+                7 if !order_ids.is_empty() => {
+                    let _ = book.cancel_order(order_ids[rng.gen_range(0..order_ids.len())]);
+                    continue;
+                }
+                _ => book.add_market_order(side, qty),
+            };
+            if fill_result.filled_orders.is_empty() && fill_result.resting_order_id.is_some() {
+                assert_eq!(fill_result.status, OrderStatus::Created);
+            }
+            order_ids.extend(fill_result.resting_order_id);
+            assert_eq!(book.validate(), Ok(()), "seed {} step {}", seed, step);
+            let bbo = book.bbo();
+            assert_eq!(book.best_bid(), bbo.bid.map(|(price, _)| price), "seed {} step {}", seed, step);
+            assert_eq!(book.best_ask(), bbo.ask.map(|(price, _)| price), "seed {} step {}", seed, step);
+        }
+    }
+}
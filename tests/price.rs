@@ -0,0 +1,205 @@
+use orderbook::{
+    BookConfig, ManualClock, OrderBook, OrderStatus, Price, PriceError, PriceScale, RejectReason, Side, TickRounding,
+};
+
+// Thousandths, cents, ticks of 0.05 and ticks of 1000
+const MILLIS: PriceScale = PriceScale { tick_size: 1, exponent: -3 };
+const CENTS: PriceScale = PriceScale { tick_size: 1, exponent: -2 };
+const NICKELS: PriceScale = PriceScale { tick_size: 5, exponent: -2 };
+const THOUSANDS: PriceScale = PriceScale { tick_size: 1, exponent: 3 };
+
+fn book(price_scale: PriceScale) -> OrderBook {
+    let mut book = OrderBook::with_clock("TEST".to_string(), ManualClock::new(1));
+    book.set_config(BookConfig { price_scale, ..Default::default() });
+    book
+}
+
+fn ticks(scale: PriceScale, s: &str, rounding: TickRounding) -> Result<u64, PriceError> {
+    scale.parse(s, rounding).map(|p| p.ticks())
+}
+
+#[test]
+fn prices_on_the_tick_parse_the_same_whatever_the_rounding() {
+    for rounding in [TickRounding::Reject, TickRounding::HalfUp, TickRounding::Down, TickRounding::Up] {
+        assert_eq!(ticks(CENTS, "101.25", rounding), Ok(10_125));
+        assert_eq!(ticks(CENTS, "0101.2500", rounding), Ok(10_125));
+        assert_eq!(ticks(NICKELS, "101.25", rounding), Ok(2_025));
+        assert_eq!(ticks(THOUSANDS, "42000", rounding), Ok(42));
+        assert_eq!(ticks(CENTS, "0", rounding), Ok(0));
+    }
+}
+
+#[test]
+fn off_tick_prices_are_rejected_or_rounded_as_asked() {
+    let cases = [
+        // (scale, price, half up, down, up)
+        (CENTS, "101.254", 10_125, 10_125, 10_126),
+        (CENTS, "101.255", 10_126, 10_125, 10_126),
+        (CENTS, "101.2549999", 10_125, 10_125, 10_126),
+        (NICKELS, "101.27", 2_025, 2_025, 2_026),
+        (NICKELS, "101.275", 2_026, 2_025, 2_026),
+        (NICKELS, "0.02", 0, 0, 1),
+        (THOUSANDS, "42499", 42, 42, 43),
+        (THOUSANDS, "42500", 43, 42, 43),
+        (THOUSANDS, "0.5", 0, 0, 1),
+    ];
+    for (scale, s, half_up, down, up) in cases {
+        assert_eq!(ticks(scale, s, TickRounding::Reject), Err(PriceError::OffTick), "{s}");
+        assert_eq!(ticks(scale, s, TickRounding::HalfUp), Ok(half_up), "{s}");
+        assert_eq!(ticks(scale, s, TickRounding::Down), Ok(down), "{s}");
+        assert_eq!(ticks(scale, s, TickRounding::Up), Ok(up), "{s}");
+    }
+}
+
+#[test]
+fn malformed_negative_and_oversized_prices_are_refused() {
+    assert_eq!(ticks(CENTS, "-1.00", TickRounding::HalfUp), Err(PriceError::Negative));
+    for s in ["", ".", "1.2.3", "1e5", "1,00", " 1"] {
+        assert_eq!(ticks(CENTS, s, TickRounding::HalfUp), Err(PriceError::Malformed), "{s:?}");
+    }
+    assert_eq!(ticks(CENTS, "184467440737095516.16", TickRounding::Reject), Err(PriceError::OutOfRange));
+    assert_eq!(ticks(CENTS, "184467440737095516.15", TickRounding::Reject), Ok(u64::MAX));
+    let no_ticks = PriceScale { tick_size: 0, exponent: 0 };
+    assert_eq!(ticks(no_ticks, "1", TickRounding::HalfUp), Err(PriceError::OutOfRange));
+}
+
+#[test]
+fn f64_prices_round_from_their_shortest_decimal() {
+    assert_eq!(CENTS.parse_f64(0.1 + 0.2, TickRounding::Reject).map(|p| p.ticks()), Err(PriceError::OffTick));
+    assert_eq!(CENTS.parse_f64(0.1 + 0.2, TickRounding::HalfUp).map(|p| p.ticks()), Ok(30));
+    assert_eq!(CENTS.parse_f64(101.25, TickRounding::Reject).map(|p| p.ticks()), Ok(10_125));
+    assert_eq!(CENTS.parse_f64(-0.01, TickRounding::HalfUp), Err(PriceError::Negative));
+    assert_eq!(CENTS.parse_f64(f64::NAN, TickRounding::HalfUp), Err(PriceError::Malformed));
+}
+
+#[test]
+fn display_shows_the_decimal_value_for_any_exponent() {
+    assert_eq!(CENTS.price(10_125).to_string(), "101.25");
+    assert_eq!(CENTS.price(5).to_string(), "0.05");
+    assert_eq!(CENTS.price(0).to_string(), "0.00");
+    assert_eq!(NICKELS.price(3).to_string(), "0.15");
+    assert_eq!(PriceScale { tick_size: 25, exponent: -4 }.price(1).to_string(), "0.0025");
+    assert_eq!(THOUSANDS.price(42).to_string(), "42000");
+    assert_eq!(THOUSANDS.price(0).to_string(), "0");
+    assert_eq!(Price::from(7).to_string(), "7");
+    // Display reads back as the same price
+    for price in [CENTS.price(10_125), NICKELS.price(3), THOUSANDS.price(42)] {
+        assert_eq!(price.scale().parse(&price.to_string(), TickRounding::Reject), Ok(price));
+    }
+}
+
+#[test]
+fn prices_on_different_scales_compare_by_value() {
+    assert_eq!(CENTS.price(10_000), Price::from(100));
+    assert_eq!(NICKELS.price(2), CENTS.price(10));
+    assert_eq!(THOUSANDS.price(1), CENTS.price(100_000));
+    assert!(CENTS.price(10_001) > Price::from(100));
+    assert!(NICKELS.price(1) < CENTS.price(6));
+    assert!(THOUSANDS.price(u64::MAX) > CENTS.price(u64::MAX));
+    assert_eq!(CENTS.price(0), THOUSANDS.price(0));
+    assert_eq!(CENTS.price(10_000), 100);
+    let mut prices = vec![THOUSANDS.price(1), CENTS.price(5), Price::from(3), NICKELS.price(3)];
+    prices.sort();
+    assert_eq!(prices, vec![CENTS.price(5), NICKELS.price(3), Price::from(3), THOUSANDS.price(1)]);
+}
+
+#[test]
+fn equal_prices_hash_alike() {
+    use std::collections::HashSet;
+    let prices: HashSet<Price> = [CENTS.price(10_000), Price::from(100), NICKELS.price(2_000), CENTS.price(0), THOUSANDS.price(0)]
+        .into_iter()
+        .collect();
+    assert_eq!(prices.len(), 2);
+}
+
+#[test]
+fn a_price_on_another_scale_converts_by_value_or_not_at_all() {
+    assert_eq!(NICKELS.ticks(CENTS.price(10_125), TickRounding::Reject), Ok(2_025));
+    assert_eq!(NICKELS.ticks(CENTS.price(10_126), TickRounding::Reject), Err(PriceError::OffTick));
+    assert_eq!(NICKELS.ticks(CENTS.price(10_126), TickRounding::HalfUp), Ok(2_025));
+    assert_eq!(NICKELS.ticks(CENTS.price(10_128), TickRounding::HalfUp), Ok(2_026));
+    assert_eq!(CENTS.ticks(Price::from(3), TickRounding::Reject), Ok(300));
+    assert_eq!(THOUSANDS.ticks(Price::from(3), TickRounding::Reject), Err(PriceError::OffTick));
+    assert_eq!(THOUSANDS.ticks(Price::from(3), TickRounding::Up), Ok(1));
+    assert_eq!(CENTS.ticks(THOUSANDS.price(u64::MAX), TickRounding::Reject), Err(PriceError::OutOfRange));
+}
+
+#[test]
+fn books_take_prices_on_their_ticks_and_reject_the_rest() {
+    let mut book = book(NICKELS);
+    let fill_result = book.add_limit_order(Side::Bid, CENTS.price(10_125), 5);
+    assert_eq!(fill_result.resting_price, Some(NICKELS.price(2_025)));
+    assert_eq!(fill_result.resting_price.unwrap().to_string(), "101.25");
+    // Plain integers are whole units
+    book.add_limit_order(Side::Bid, 101, 5);
+    assert_eq!(book.qty_at(Side::Bid, NICKELS.price(2_020)), 5);
+
+    let fill_result = book.add_limit_order(Side::Bid, CENTS.price(10_126), 5);
+    assert_eq!(fill_result.status, OrderStatus::Rejected(RejectReason::OffTick));
+    assert_eq!(fill_result.remaining_qty, 5);
+    let fill_result = book.add_limit_order(Side::Ask, THOUSANDS.price(u64::MAX), 5);
+    assert_eq!(fill_result.status, OrderStatus::Rejected(RejectReason::PriceOutOfRange));
+    assert_eq!(
+        book.create_new_limit_order(Side::Ask, CENTS.price(10_201), 5),
+        Err(orderbook::Error::Rejected(RejectReason::OffTick))
+    );
+    assert_eq!(book.qty_at(Side::Bid, CENTS.price(10_126)), 0);
+    assert_eq!(book.best_bid(), Some(NICKELS.price(2_025)));
+    assert_eq!(book.best_ask(), None);
+}
+
+#[test]
+fn queries_return_prices_on_the_book_scale() {
+    let mut book = book(CENTS);
+    let bid = book.parse_price("99.95", TickRounding::Reject).unwrap();
+    book.add_limit_order(Side::Bid, bid, 5);
+    book.add_limit_order(Side::Ask, book.parse_price("100.05", TickRounding::Reject).unwrap(), 5);
+    let fill_result = book.add_limit_order(Side::Ask, bid, 2);
+    assert_eq!(fill_result.filled_orders[0].price.to_string(), "99.95");
+    assert_eq!(fill_result.avg_fill_price(), Some(99.95));
+    assert_eq!(book.last_trade_price().map(|p| p.to_string()), Some("99.95".to_string()));
+    assert_eq!(book.spread(), Some(10));
+    assert_eq!(book.mid_price(), Some(100.0));
+    assert_eq!(book.bbo().mid().map(|p| p.to_string()), Some("100.00".to_string()));
+    let depth = book.depth(1);
+    assert_eq!((depth.bids[0].price.scale(), depth.bids[0].price.ticks()), (CENTS, 9_995));
+    // Query bounds between ticks take the ticks within them
+    assert_eq!(book.cumulative_qty(Side::Bid, MILLIS.price(99_949)), 3);
+    assert_eq!(book.cumulative_qty(Side::Bid, MILLIS.price(99_951)), 0);
+    assert_eq!(book.executable_qty(Side::Ask, Some(MILLIS.price(99_949)), 10), 3);
+    assert_eq!(book.executable_qty(Side::Ask, Some(MILLIS.price(99_951)), 10), 0);
+    assert_eq!(book.cancel_range(Side::Bid, MILLIS.price(99_951), MILLIS.price(99_959)).len(), 0);
+    assert_eq!(book.cancel_range(Side::Bid, MILLIS.price(99_949), MILLIS.price(99_951)).len(), 1);
+}
+
+#[cfg(feature = "decimal")]
+mod decimal {
+    use rust_decimal::Decimal;
+
+    use super::*;
+
+    #[test]
+    fn decimals_convert_like_decimal_strings() {
+        for (s, rounding) in [("101.25", TickRounding::Reject), ("101.254", TickRounding::HalfUp), ("101.255", TickRounding::Down)] {
+            let value: Decimal = s.parse().unwrap();
+            assert_eq!(CENTS.from_decimal(value, rounding), CENTS.parse(s, rounding), "{s}");
+            assert_eq!(NICKELS.from_decimal(value, rounding), NICKELS.parse(s, rounding), "{s}");
+        }
+        let off_tick: Decimal = "101.254".parse().unwrap();
+        assert_eq!(CENTS.from_decimal(off_tick, TickRounding::Reject), Err(PriceError::OffTick));
+        assert_eq!(CENTS.from_decimal(Decimal::NEGATIVE_ONE, TickRounding::HalfUp), Err(PriceError::Negative));
+        assert_eq!(CENTS.from_decimal(Decimal::ZERO, TickRounding::Reject).map(|p| p.ticks()), Ok(0));
+    }
+
+    #[test]
+    fn prices_convert_to_their_exact_decimal() {
+        assert_eq!(CENTS.price(10_125).to_decimal(), Some("101.25".parse().unwrap()));
+        assert_eq!(NICKELS.price(3).to_decimal(), Some("0.15".parse().unwrap()));
+        assert_eq!(THOUSANDS.price(42).to_decimal(), Some(Decimal::from(42_000)));
+        assert_eq!(PriceScale { tick_size: 1, exponent: 30 }.price(u64::MAX).to_decimal(), None);
+        for price in [CENTS.price(10_125), NICKELS.price(3), THOUSANDS.price(42)] {
+            let value = price.to_decimal().unwrap();
+            assert_eq!(price.scale().from_decimal(value, TickRounding::Reject), Ok(price));
+        }
+    }
+}
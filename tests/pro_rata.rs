@@ -0,0 +1,66 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use orderbook::{BookConfig, FillResult, ManualClock, MatchingAlgorithm, OrderBook, OrderId, Side};
+
+fn book() -> OrderBook {
+    let mut book = OrderBook::with_clock("TEST".to_string(), ManualClock::new(1));
+    book.set_config(BookConfig { matching: MatchingAlgorithm::ProRata, ..BookConfig::default() });
+    book
+}
+
+fn filled(fill_result: &FillResult, order_id: OrderId) -> u64 {
+    fill_result.filled_orders.iter().filter(|f| f.maker_order_id == order_id).map(|f| f.qty).sum()
+}
+
+#[test]
+fn incoming_qty_is_split_in_proportion_to_size() {
+    let mut book = book();
+    let a = book.create_new_limit_order(Side::Ask, 100, 100).unwrap();
+    let b = book.create_new_limit_order(Side::Ask, 100, 300).unwrap();
+
+    let fill_result = book.add_limit_order(Side::Bid, 100, 40);
+    assert_eq!((filled(&fill_result, a), filled(&fill_result, b)), (10, 30));
+    assert_eq!((book.resting_qty(a), book.resting_qty(b)), (Some(90), Some(270)));
+}
+
+#[test]
+fn every_order_gets_a_lot_before_leftovers_go_first_in_first_out() {
+    let mut book = book();
+    let a = book.create_new_limit_order(Side::Ask, 100, 100).unwrap();
+    let b = book.create_new_limit_order(Side::Ask, 100, 300).unwrap();
+    let c = book.create_new_limit_order(Side::Ask, 100, 1).unwrap();
+
+    // 25 and 75 rounded down, c rounds to nothing and gets its one lot
+    let fill_result = book.add_limit_order(Side::Bid, 100, 101);
+    assert_eq!((filled(&fill_result, a), filled(&fill_result, b), filled(&fill_result, c)), (25, 75, 1));
+
+    // 1.25 and 3.75 rounded down, the lot left over goes to the older order
+    let fill_result = book.add_limit_order(Side::Bid, 100, 5);
+    assert_eq!((filled(&fill_result, a), filled(&fill_result, b)), (2, 3));
+}
+
+#[test]
+fn allocations_conserve_qty_across_the_level() {
+    for seed in 0..50 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut book = book();
+        let orders: Vec<(OrderId, u64)> = (0..rng.gen_range(1..20))
+            .map(|_| {
+                let qty = rng.gen_range(1..1_000);
+                (book.create_new_limit_order(Side::Ask, 100, qty).unwrap(), qty)
+            })
+            .collect();
+        let level_qty: u64 = orders.iter().map(|(_, q)| q).sum();
+
+        let incoming = rng.gen_range(1..level_qty * 2);
+        let fill_result = book.add_limit_order(Side::Bid, 100, incoming);
+        // Never more than the incoming order, and all of it while the level lasts
+        assert_eq!(fill_result.total_filled_qty(), incoming.min(level_qty), "seed {}", seed);
+        for &(order_id, qty) in &orders {
+            let left = book.resting_qty(order_id).unwrap_or(0);
+            assert_eq!(left + filled(&fill_result, order_id), qty, "seed {}", seed);
+        }
+        assert_eq!(book.validate(), Ok(()));
+    }
+}
@@ -0,0 +1,55 @@
+use orderbook::{Error, ManualClock, OrderBook, OrderStatus, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+#[test]
+fn reduced_order_keeps_its_place_ahead_of_later_arrivals() {
+    let mut book = book();
+    let first = book.create_new_limit_order(Side::Ask, 100, 10).unwrap();
+    let second = book.create_new_limit_order(Side::Ask, 100, 10).unwrap();
+
+    assert_eq!(book.reduce_order(first, 4), Ok(6));
+    assert_eq!(book.get_order(first).unwrap().queue_position, Some(0));
+    assert_eq!(book.qty_at(Side::Ask, 100), 16);
+
+    let fill_result = book.add_limit_order(Side::Bid, 100, 8);
+    let makers: Vec<_> = fill_result.filled_orders.iter().map(|f| (f.maker_order_id, f.qty)).collect();
+    assert_eq!(makers, vec![(first, 6), (second, 2)]);
+}
+
+#[test]
+fn iceberg_is_reduced_from_its_reserve_first() {
+    let mut book = book();
+    let iceberg = book.add_iceberg_order(Side::Ask, 100, 20, 5).resting_order_id.unwrap();
+
+    assert_eq!(book.reduce_order(iceberg, 12), Ok(8));
+    assert_eq!(book.qty_at(Side::Ask, 100), 5);
+    assert_eq!(book.resting_qty(iceberg), Some(8));
+}
+
+#[test]
+fn reducing_by_all_that_is_left_cancels_the_order() {
+    let mut book = book();
+    let order_id = book.create_new_limit_order(Side::Ask, 100, 10).unwrap();
+
+    assert_eq!(book.reduce_order(order_id, 40), Ok(0));
+    assert_eq!(book.get_order(order_id).unwrap().status, OrderStatus::Cancelled);
+    assert_eq!(book.best_ask(), None);
+    assert!(book.reduce_order(order_id, 1).is_err());
+    assert!(book.cancel_order(order_id).is_err());
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn reducing_an_order_that_left_the_book_fails() {
+    let mut book = book();
+    let order_id = book.create_new_limit_order(Side::Ask, 100, 10).unwrap();
+    book.cancel_order(order_id).unwrap();
+    assert_eq!(book.reduce_order(order_id, 1), Err(Error::AlreadyCancelled { order_id, filled_qty: 0 }));
+
+    let order_id = book.create_new_limit_order(Side::Ask, 100, 10).unwrap();
+    book.add_limit_order(Side::Bid, 100, 10);
+    assert_eq!(book.reduce_order(order_id, 1), Err(Error::AlreadyFilled { order_id, filled_qty: 10 }));
+}
@@ -0,0 +1,45 @@
+use orderbook::{ManualClock, OrderBook, OrderStatus, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+#[test]
+fn partially_filled_remainder_is_cancelled_through_the_returned_id() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    let fill_result = book.add_limit_order(Side::Bid, 100, 8);
+    assert_eq!(fill_result.status, OrderStatus::PartiallyFilled);
+    let order_id = fill_result.resting_order_id.unwrap();
+    assert_eq!(book.resting_qty(order_id), Some(3));
+
+    assert_eq!(book.cancel_order(order_id).unwrap().cancelled_qty, 3);
+    assert_eq!(book.resting_qty(order_id), None);
+    assert_eq!(book.best_bid(), None);
+    assert_eq!(book.get_order(order_id).unwrap().status, OrderStatus::Cancelled);
+}
+
+#[test]
+fn remainder_keeps_the_id_of_the_whole_order() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    let fill_result = book.add_limit_order(Side::Bid, 100, 8);
+    let order_id = fill_result.resting_order_id.unwrap();
+
+    let info = book.get_order(order_id).unwrap();
+    assert_eq!((info.filled_qty, info.remaining_qty), (5, 3));
+    // The maker's trade names the same id as the buyer
+    assert_eq!(book.recent_trades(1).next().unwrap().buy_order_id, Some(order_id));
+}
+
+#[test]
+fn nothing_resting_means_no_id() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    assert_eq!(book.add_limit_order(Side::Bid, 100, 5).resting_order_id, None);
+    assert_eq!(book.add_market_order(Side::Bid, 3).resting_order_id, None);
+
+    let fill_result = book.add_limit_order(Side::Bid, 90, 3);
+    assert_eq!(fill_result.status, OrderStatus::Created);
+    assert!(fill_result.resting_order_id.is_some());
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "serde")]
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use orderbook::{
+    AccountId, BookSnapshot, Event, ManualClock, OrderBook, OrderId, OrderStatus, RejectReason, Side, TimeInForce,
+};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+fn round_trip<T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug>(value: &T) {
+    let json = serde_json::to_string(value).unwrap();
+    let back: T = serde_json::from_str(&json).unwrap();
+    assert_eq!(&back, value, "{}", json);
+}
+
+#[test]
+fn plain_types_round_trip() {
+    round_trip(&Side::Bid);
+    round_trip(&OrderId(7));
+    round_trip(&AccountId(3));
+    round_trip(&TimeInForce::GoodTillDate(100));
+    round_trip(&OrderStatus::Rejected(RejectReason::ZeroQty));
+}
+
+#[test]
+fn fills_trades_and_order_info_round_trip() {
+    let mut book = book();
+    let ask = book.create_new_limit_order(Side::Ask, 100, 10).unwrap();
+    let fill_result = book.add_owned_limit_order(AccountId(1), Side::Bid, 100, 4, None);
+    round_trip(&fill_result);
+    round_trip(book.recent_trades(1).next().unwrap());
+    round_trip(&book.get_order(ask).unwrap());
+    round_trip(&book.bbo());
+    round_trip(&book.depth(5));
+    round_trip(&book.cancel_order(ask).unwrap());
+}
+
+#[test]
+fn events_round_trip() {
+    let mut book = book();
+    let events = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let sink = events.clone();
+    book.on_event(move |_, event| sink.borrow_mut().push(event.clone()));
+    book.create_new_limit_order(Side::Ask, 100, 10).unwrap();
+    book.add_limit_order(Side::Bid, 100, 4);
+    book.add_limit_order_with_tif(Side::Bid, 0, 4, TimeInForce::ImmediateOrCancel);
+
+    let events: Vec<Event> = events.borrow().clone();
+    assert!(!events.is_empty());
+    round_trip(&events);
+}
+
+#[test]
+fn snapshot_round_trips_into_the_same_book() {
+    let mut book = book();
+    for i in 0..10 {
+        book.create_new_limit_order(Side::Bid, 90 + i, 5 + i).unwrap();
+        book.create_new_limit_order(Side::Ask, 110 + i, 5 + i).unwrap();
+    }
+    book.add_limit_order(Side::Bid, 111, 8);
+    let snapshot = book.snapshot();
+    round_trip(&snapshot);
+
+    let json = serde_json::to_string(&snapshot).unwrap();
+    let restored = OrderBook::from_snapshot(serde_json::from_str::<BookSnapshot>(&json).unwrap());
+    assert_eq!(restored.snapshot(), snapshot);
+    assert_eq!((restored.best_bid(), restored.best_ask()), (book.best_bid(), book.best_ask()));
+}
@@ -0,0 +1,94 @@
+use orderbook::{
+    BookConfig, Error, Fill, ManualClock, OrderBook, OrderLimits, PriceLadder, RejectReason, Side, TriggerSource,
+};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+// Book with plain, hidden, iceberg, pegged and partially filled orders on both sides
+fn busy_book() -> OrderBook {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 10).unwrap();
+    book.create_new_limit_order(Side::Ask, 101, 5).unwrap();
+    book.add_hidden_order(Side::Ask, 102, 5);
+    book.add_iceberg_order(Side::Ask, 103, 50, 5);
+    book.create_new_limit_order(Side::Bid, 99, 3).unwrap();
+    book.create_new_limit_order(Side::Bid, 98, 3).unwrap();
+    book.create_new_limit_order(Side::Bid, 99, 4).unwrap();
+    book.create_pegged_order(Side::Bid, 0, 3).unwrap();
+    book.add_limit_order(Side::Bid, 101, 2);
+    book
+}
+
+fn fills(fills: &[Fill]) -> Vec<(u64, u64, u64)> {
+    fills.iter().map(|f| (f.maker_order_id.0, f.price.ticks(), f.qty)).collect()
+}
+
+#[test]
+fn snapshot_lists_orders_by_side_price_and_queue_position() {
+    let snapshot = busy_book().snapshot();
+    let orders: Vec<_> = snapshot.orders.iter().map(|o| (o.side, o.price.ticks(), o.queue_position, o.order.qty)).collect();
+    assert_eq!(orders, vec![
+        (Side::Bid, 99, 0, 3),
+        (Side::Bid, 99, 1, 4),
+        (Side::Bid, 99, 2, 3),
+        (Side::Bid, 98, 0, 3),
+        (Side::Ask, 101, 0, 8),
+        (Side::Ask, 101, 1, 5),
+        (Side::Ask, 102, 0, 5),
+        (Side::Ask, 103, 0, 5),
+    ]);
+    assert_eq!(snapshot.orders.iter().filter(|o| o.peg_offset.is_some()).count(), 1);
+}
+
+#[test]
+fn snapshot_restore_snapshot_gives_the_same_snapshot() {
+    let snapshot = busy_book().snapshot();
+    let restored = OrderBook::from_snapshot(snapshot.clone());
+    assert_eq!(restored.snapshot(), snapshot);
+    assert_eq!(restored.validate(), Ok(()));
+    assert_eq!((restored.best_bid(), restored.best_ask()), (Some(99.into()), Some(101.into())));
+}
+
+#[test]
+fn restored_book_matches_like_the_original() {
+    let mut original = busy_book();
+    let mut restored = OrderBook::from_snapshot(original.snapshot());
+
+    for (side, price, qty) in [(Side::Bid, 103, 30), (Side::Ask, 98, 8), (Side::Bid, 103, 10)] {
+        let a = original.add_limit_order(side, price, qty);
+        let b = restored.add_limit_order(side, price, qty);
+        assert_eq!(fills(&a.filled_orders), fills(&b.filled_orders));
+        assert_eq!(a.resting_order_id, b.resting_order_id);
+    }
+    assert_eq!(original.snapshot(), restored.snapshot());
+    assert_eq!(original.last_seq(), restored.last_seq());
+}
+
+#[test]
+fn restored_book_keeps_the_config() {
+    let mut original = book();
+    let config = BookConfig {
+        order_limits: OrderLimits { max_qty: Some(50), ..OrderLimits::default() },
+        stop_trigger_source: TriggerSource::BestBid,
+        price_ladder: Some(PriceLadder { low: 90, ticks: 20 }),
+        trade_tape_len: Some(3),
+        ..BookConfig::default()
+    };
+    original.set_config(config.clone());
+    let bid = original.create_new_limit_order(Side::Bid, 99, 10).unwrap();
+    original.create_new_limit_order(Side::Ask, 130, 10).unwrap();
+
+    let snapshot = original.snapshot();
+    assert_eq!(snapshot.config, config);
+    let mut restored = OrderBook::from_snapshot(snapshot);
+    assert_eq!(restored.validate(), Ok(()));
+    assert_eq!(restored.create_new_limit_order(Side::Bid, 98, 51), Err(Error::Rejected(RejectReason::QtyAboveMax)));
+
+    // Stops still trigger off the best bid, with no trade to trigger them otherwise
+    let stop_id = restored.create_stop_order(Side::Ask, 98, 5).unwrap();
+    restored.create_new_limit_order(Side::Bid, 97, 20).unwrap();
+    restored.cancel_order(bid).unwrap();
+    assert_eq!(restored.take_triggered_stops()[0].order_id, stop_id);
+}
@@ -0,0 +1,67 @@
+use orderbook::{ManualClock, OrderBook, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+fn spreads(book: &OrderBook) -> (Option<i64>, Option<i64>) {
+    (book.spread(), book.bbo().spread())
+}
+
+#[test]
+fn empty_book_has_no_spread() {
+    let book = book();
+    assert_eq!(spreads(&book), (None, None));
+    assert!(book.render(3).contains("no spread"));
+}
+
+#[test]
+fn bid_only_book_has_no_spread() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Bid, 100, 5).unwrap();
+    assert_eq!(spreads(&book), (None, None));
+    assert!(book.render(3).contains("no spread"));
+}
+
+#[test]
+fn ask_only_book_has_no_spread() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    assert_eq!(spreads(&book), (None, None));
+}
+
+#[test]
+fn normal_spread() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Bid, 100, 5).unwrap();
+    book.create_new_limit_order(Side::Ask, 104, 5).unwrap();
+    assert_eq!(spreads(&book), (Some(4), Some(4)));
+    assert!(book.render(3).contains("spread 4"));
+}
+
+#[test]
+fn locked_market_has_a_zero_spread() {
+    let mut book = book();
+    book.start_auction();
+    book.create_new_limit_order(Side::Bid, 100, 5).unwrap();
+    book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    assert_eq!(spreads(&book), (Some(0), Some(0)));
+}
+
+#[test]
+fn crossed_market_has_a_negative_spread() {
+    let mut book = book();
+    book.start_auction();
+    book.create_new_limit_order(Side::Bid, 105, 5).unwrap();
+    book.create_new_limit_order(Side::Ask, 100, 5).unwrap();
+    assert_eq!(spreads(&book), (Some(-5), Some(-5)));
+    assert!(book.render(3).contains("spread -5"));
+}
+
+#[test]
+fn spreads_wider_than_i64_saturate() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Bid, 1, 5).unwrap();
+    book.create_new_limit_order(Side::Ask, u64::MAX - 1, 5).unwrap();
+    assert_eq!(spreads(&book), (Some(i64::MAX), Some(i64::MAX)));
+}
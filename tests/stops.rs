@@ -0,0 +1,109 @@
+use orderbook::{Error, ManualClock, OcoTrigger, OrderBook, OrderSpec, OrderStatus, RejectReason, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+#[test]
+fn stop_limit_triggers_on_a_trade() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 105, 10).unwrap();
+    let stop_id = book.create_stop_limit_order(Side::Bid, 102, 105, 4).unwrap();
+    assert!(book.take_triggered_stops().is_empty());
+
+    book.create_new_limit_order(Side::Ask, 102, 1).unwrap();
+    book.add_limit_order(Side::Bid, 102, 1);
+
+    let triggered = book.take_triggered_stops();
+    assert_eq!(triggered.len(), 1);
+    assert_eq!(triggered[0].order_id, stop_id);
+    assert_eq!(triggered[0].fill_result.status, OrderStatus::Filled);
+    assert_eq!(triggered[0].fill_result.total_filled_qty(), 4);
+    assert_eq!(book.qty_at(Side::Ask, 105), 6);
+}
+
+#[test]
+fn stop_limit_cancelled_before_it_triggers() {
+    let mut book = book();
+    let stop_id = book.create_stop_limit_order(Side::Bid, 102, 105, 4).unwrap();
+    let report = book.cancel_order(stop_id).unwrap();
+    assert_eq!(report.cancelled_qty, 4);
+
+    book.create_new_limit_order(Side::Ask, 102, 1).unwrap();
+    book.add_limit_order(Side::Bid, 102, 1);
+    assert!(book.take_triggered_stops().is_empty());
+    assert!(book.cancel_order(stop_id).is_err());
+}
+
+#[test]
+fn triggered_stop_limit_partially_fills_and_rests_under_its_id() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 104, 3).unwrap();
+    let stop_id = book.create_stop_limit_order(Side::Bid, 102, 104, 10).unwrap();
+
+    book.create_new_limit_order(Side::Ask, 102, 1).unwrap();
+    book.add_limit_order(Side::Bid, 102, 1);
+
+    let triggered = book.take_triggered_stops();
+    let fill_result = &triggered[0].fill_result;
+    assert_eq!(fill_result.status, OrderStatus::PartiallyFilled);
+    assert_eq!(fill_result.total_filled_qty(), 3);
+    assert_eq!(fill_result.resting_order_id, Some(stop_id));
+    assert_eq!(book.qty_at(Side::Bid, 104), 7);
+
+    // The id create_stop_limit_order gave still reaches the order
+    let info = book.get_order(stop_id).unwrap();
+    assert_eq!((info.price, info.filled_qty), (104.into(), 3));
+    book.amend_order(stop_id, 104, 5).unwrap();
+    assert_eq!(book.cancel_order(stop_id).unwrap().cancelled_qty, 5);
+    assert_eq!(book.validate(), Ok(()));
+}
+
+#[test]
+fn triggered_stop_limit_that_is_no_longer_marketable_rests() {
+    let mut book = book();
+    let stop_id = book.create_stop_limit_order(Side::Bid, 102, 101, 5).unwrap();
+    book.create_new_limit_order(Side::Ask, 102, 1).unwrap();
+    book.add_limit_order(Side::Bid, 102, 1);
+
+    let triggered = book.take_triggered_stops();
+    assert_eq!(triggered[0].fill_result.status, OrderStatus::Created);
+    assert_eq!(triggered[0].fill_result.resting_price, Some(101.into()));
+    assert_eq!(book.get_order(stop_id).unwrap().price, 101);
+}
+
+#[test]
+fn stops_are_accepted_once_with_a_seq() {
+    let mut book = book();
+    let seq = book.last_seq();
+    book.create_stop_limit_order(Side::Bid, 102, 101, 5).unwrap();
+    assert_eq!(book.last_seq(), seq + 1);
+    assert_eq!(book.stats().orders_accepted, 1);
+
+    // Triggering it does not count it again
+    book.create_new_limit_order(Side::Ask, 102, 1).unwrap();
+    book.add_limit_order(Side::Bid, 102, 1);
+    assert_eq!(book.take_triggered_stops().len(), 1);
+    assert_eq!(book.stats().orders_accepted, 3);
+}
+
+#[test]
+fn stops_with_zero_qty_or_price_are_refused() {
+    let mut book = book();
+    assert_eq!(book.create_stop_order(Side::Bid, 102, 0), Err(Error::Rejected(RejectReason::ZeroQty)));
+    assert_eq!(book.create_mit_order(Side::Ask, 0, 5), Err(Error::Rejected(RejectReason::ZeroPrice)));
+    assert_eq!(book.create_stop_limit_order(Side::Bid, 102, 0, 5), Err(Error::Rejected(RejectReason::ZeroPrice)));
+    assert_eq!(book.create_trailing_stop_order(Side::Ask, 2, 0), Err(Error::Rejected(RejectReason::ZeroQty)));
+    assert_eq!((book.last_seq(), book.stats().orders_accepted), (0, 0));
+}
+
+#[test]
+fn oco_with_a_refused_leg_submits_neither() {
+    let mut book = book();
+    let limit = OrderSpec::Limit { side: Side::Bid, price: 100.into(), qty: 5 };
+    let stop = OrderSpec::Stop { side: Side::Bid, stop_price: 105.into(), qty: 0 };
+    let result = book.create_oco(limit, stop, OcoTrigger::FirstFill);
+    assert_eq!(result, Err(Error::Rejected(RejectReason::ZeroQty)));
+    assert_eq!(book.best_bid(), None);
+    assert_eq!(book.stats().orders_accepted, 0);
+}
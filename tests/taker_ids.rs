@@ -0,0 +1,47 @@
+use orderbook::{ManualClock, OrderBook, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+#[test]
+fn market_orders_trade_under_their_own_id() {
+    let mut book = book();
+    let maker = book.create_new_limit_order(Side::Ask, 100, 10).unwrap();
+    book.add_market_order(Side::Bid, 4);
+    book.add_market_order(Side::Bid, 4);
+    let after = book.create_new_limit_order(Side::Ask, 101, 1).unwrap();
+
+    let takers: Vec<_> = book.recent_trades(2).map(|t| (t.maker_order_id(), t.taker_order_id())).collect();
+    assert!(takers.iter().all(|&(m, t)| m == Some(maker) && t.is_some_and(|t| t != maker && t < after)));
+    assert_ne!(takers[0].1, takers[1].1);
+}
+
+#[test]
+fn triggered_stop_trades_under_the_stop_id() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 105, 10).unwrap();
+    let stop_id = book.create_stop_order(Side::Bid, 102, 4).unwrap();
+
+    book.create_new_limit_order(Side::Ask, 102, 1).unwrap();
+    book.add_limit_order(Side::Bid, 102, 1);
+
+    assert_eq!(book.take_triggered_stops()[0].order_id, stop_id);
+    let trade = book.recent_trades(1).next().unwrap();
+    assert_eq!((trade.price, trade.qty), (105.into(), 4));
+    assert_eq!(trade.taker_order_id(), Some(stop_id));
+    assert_eq!(trade.buy_order_id, Some(stop_id));
+}
+
+#[test]
+fn triggered_trailing_stop_trades_under_its_id() {
+    let mut book = book();
+    let best = book.create_new_limit_order(Side::Bid, 100, 5).unwrap();
+    book.create_new_limit_order(Side::Bid, 97, 5).unwrap();
+    let stop_id = book.create_trailing_stop_order(Side::Ask, 2, 3).unwrap();
+
+    book.cancel_order(best).unwrap();
+    let trade = book.recent_trades(1).next().unwrap();
+    assert_eq!(trade.taker_order_id(), Some(stop_id));
+    assert_eq!(trade.sell_order_id, Some(stop_id));
+}
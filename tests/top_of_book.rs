@@ -0,0 +1,46 @@
+use orderbook::{ManualClock, OrderBook, Side};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+fn analytics(book: &OrderBook) -> (Option<f64>, Option<i64>, Option<f64>) {
+    (book.mid_price(), book.spread(), book.microprice())
+}
+
+#[test]
+fn empty_book_has_no_top_of_book_analytics() {
+    assert_eq!(analytics(&book()), (None, None, None));
+}
+
+#[test]
+fn one_sided_book_has_no_top_of_book_analytics() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 10).unwrap();
+    assert_eq!(analytics(&book), (None, None, None));
+
+    let mut book = self::book();
+    book.create_new_limit_order(Side::Bid, 99, 10).unwrap();
+    assert_eq!(analytics(&book), (None, None, None));
+}
+
+#[test]
+fn two_sided_book() {
+    let mut book = book();
+    book.create_new_limit_order(Side::Ask, 101, 10).unwrap();
+    book.create_new_limit_order(Side::Bid, 99, 30).unwrap();
+    // Hidden qty counts for neither the prices nor the weights
+    book.add_hidden_order(Side::Ask, 100, 50);
+
+    let microprice = (99.0 * 10.0 + 101.0 * 30.0) / 40.0;
+    assert_eq!(analytics(&book), (Some(100.0), Some(2), Some(microprice)));
+}
+
+#[test]
+fn locked_book_has_a_zero_spread() {
+    let mut book = book();
+    book.start_auction();
+    book.create_new_limit_order(Side::Ask, 100, 1).unwrap();
+    book.create_new_limit_order(Side::Bid, 100, 3).unwrap();
+    assert_eq!(analytics(&book), (Some(100.0), Some(0), Some(100.0)));
+}
@@ -0,0 +1,102 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use orderbook::{ManualClock, OrderBook, OrderId, Side, TimeInForce};
+
+fn book() -> OrderBook {
+    OrderBook::with_clock("TEST".to_string(), ManualClock::new(1))
+}
+
+fn side(rng: &mut StdRng) -> Side {
+    if rng.gen_bool(0.5) { Side::Bid } else { Side::Ask }
+}
+
+// One random operation, any order id it rested is kept for later cancels and amends
+fn random_op(rng: &mut StdRng, book: &mut OrderBook, order_ids: &mut Vec<OrderId>) {
+    let s = side(rng);
+    let price = rng.gen_range(90..110);
+    let qty = rng.gen_range(1..20);
+    let resting = match rng.gen_range(0..16) {
+        0..=3 => book.add_limit_order(s, price, qty).resting_order_id,
+        4 => book.add_hidden_order(s, price, qty).resting_order_id,
+        5 => book.add_iceberg_order(s, price, qty * 4, qty).resting_order_id,
+        6 => book.add_limit_order_with_tif(s, price, qty, TimeInForce::ImmediateOrCancel).resting_order_id,
+        7 => book.add_limit_order_with_tif(s, price, qty, TimeInForce::FillOrKill).resting_order_id,
+        8 => book.add_market_order(s, qty * 2).resting_order_id,
+        9 => book.create_pegged_order(s, rng.gen_range(-3..3), qty).ok(),
+        10 => book.create_stop_order(s, price, qty).ok(),
+        11 => book.create_trailing_stop_order(s, rng.gen_range(1..5), qty).ok(),
+        12 if !order_ids.is_empty() => {
+            let _ = book.cancel_order(order_ids[rng.gen_range(0..order_ids.len())]);
+            None
+        }
+        13 if !order_ids.is_empty() => {
+            let _ = book.reduce_order(order_ids[rng.gen_range(0..order_ids.len())], rng.gen_range(1..5));
+            None
+        }
+        14 if !order_ids.is_empty() => {
+            let _ = book.amend_order(order_ids[rng.gen_range(0..order_ids.len())], price, qty);
+            None
+        }
+        15 => {
+            book.cancel_range(s, price, price + 3);
+            None
+        }
+        _ => None,
+    };
+    order_ids.extend(resting);
+}
+
+#[test]
+fn book_stays_consistent_under_random_operations() {
+    for seed in 0..100 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut book = book();
+        let mut order_ids = Vec::new();
+        for step in 0..500 {
+            random_op(&mut rng, &mut book, &mut order_ids);
+            assert_eq!(book.validate(), Ok(()), "seed {} step {}", seed, step);
+        }
+    }
+}
+
+#[test]
+fn book_stays_consistent_through_auctions_and_mass_cancels() {
+    for seed in 0..50 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut book = book();
+        let mut order_ids = Vec::new();
+        for step in 0..500 {
+            match rng.gen_range(0..40) {
+                0 => book.start_auction(),
+                1 => {
+                    book.uncross();
+                }
+                2 => {
+                    book.cancel_side(side(&mut rng));
+                }
+                3 => {
+                    book.cancel_all();
+                }
+                _ => random_op(&mut rng, &mut book, &mut order_ids),
+            }
+            assert_eq!(book.validate(), Ok(()), "seed {} step {}", seed, step);
+        }
+    }
+}
+
+#[test]
+fn restored_books_are_consistent() {
+    let mut rng = StdRng::seed_from_u64(53);
+    let mut book = book();
+    let mut order_ids = Vec::new();
+    for _ in 0..2_000 {
+        random_op(&mut rng, &mut book, &mut order_ids);
+    }
+    let mut restored = OrderBook::from_snapshot(book.snapshot());
+    assert_eq!(restored.validate(), Ok(()));
+    for step in 0..500 {
+        random_op(&mut rng, &mut restored, &mut order_ids);
+        assert_eq!(restored.validate(), Ok(()), "step {}", step);
+    }
+}